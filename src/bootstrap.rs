@@ -0,0 +1,266 @@
+use crate::{
+    config::Bootstrap,
+    github::{encode, ApiError, GitHub},
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+#[derive(Clone, Debug, Deserialize)]
+struct RepositoryInfo {
+    default_branch: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Ref {
+    object: RefObject,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RefObject {
+    sha: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum Contents {
+    Directory(Vec<Entry>),
+    File(FileContent),
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Entry {
+    path: String,
+    r#type: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct FileContent {
+    path: String,
+    content: String,
+    sha: String,
+}
+
+/// Copy the files of the template repository into the target repository.
+///
+/// The files get added on a new branch, for which a pull request is opened. If the target
+/// repository is still empty, there is nothing to open a pull request against, and the files
+/// get committed to the default branch directly.
+///
+/// This may run again for the same repository, like when the event is delivered again. An open
+/// pull request is left as it is, and an existing branch and files are updated.
+pub fn bootstrap(github: &GitHub, bootstrap: &Bootstrap, repository: &str) -> anyhow::Result<()> {
+    let files = collect(github, &bootstrap.template, &bootstrap.files)?;
+    if files.is_empty() {
+        log::info!("No files to bootstrap from: {}", bootstrap.template);
+        return Ok(());
+    }
+
+    let owner = repository.split('/').next().unwrap_or_default();
+    let open: Vec<Value> = github.get(&format!(
+        "repos/{}/pulls?state=open&head={}",
+        repository,
+        encode(&format!("{}:{}", owner, bootstrap.branch))
+    ))?;
+    if !open.is_empty() {
+        log::info!(
+            "Repository {} has an open bootstrap pull request",
+            repository
+        );
+        return Ok(());
+    }
+
+    let info: RepositoryInfo = github.get(&format!("repos/{}", repository))?;
+
+    let head = match github.get_optional::<Ref>(&format!(
+        "repos/{}/git/ref/heads/{}",
+        repository, info.default_branch
+    )) {
+        Ok(head) => head,
+        // an empty repository reports a conflict rather than a missing ref
+        Err(err)
+            if matches!(
+                err.downcast_ref::<ApiError>(),
+                Some(ApiError {
+                    status: Some(409),
+                    ..
+                })
+            ) =>
+        {
+            None
+        }
+        Err(err) => return Err(err),
+    };
+
+    let branch = match head {
+        Some(head) => {
+            let existing = github.get_optional::<Ref>(&format!(
+                "repos/{}/git/ref/heads/{}",
+                repository, bootstrap.branch
+            ))?;
+            if existing.is_none() {
+                github.post(
+                    &format!("repos/{}/git/refs", repository),
+                    &json!({
+                        "ref": format!("refs/heads/{}", bootstrap.branch),
+                        "sha": head.object.sha,
+                    }),
+                )?;
+            }
+            Some(&bootstrap.branch)
+        }
+        None => {
+            log::info!(
+                "Repository {} is empty, committing to the default branch",
+                repository
+            );
+            None
+        }
+    };
+
+    for file in &files {
+        let content = file.content.replace('\n', "");
+        let path = format!("repos/{}/contents/{}", repository, file.path);
+        let existing = match branch {
+            Some(branch) => {
+                github.get_optional::<FileContent>(&format!("{}?ref={}", path, encode(branch)))?
+            }
+            None => None,
+        };
+
+        let mut body = json!({
+            "message": format!("Add {}", file.path),
+            "content": content,
+        });
+        if let Some(existing) = existing {
+            if existing.content.replace('\n', "") == content {
+                log::info!("Already added: {}", file.path);
+                continue;
+            }
+            // updating a file requires the blob it replaces
+            body["sha"] = json!(existing.sha);
+        }
+        if let Some(branch) = branch {
+            body["branch"] = json!(branch);
+        }
+
+        log::info!("Adding: {}", file.path);
+        github.put(&path, &body)?;
+    }
+
+    if branch.is_some() {
+        github.post(
+            &format!("repos/{}/pulls", repository),
+            &json!({
+                "title": bootstrap.title,
+                "head": bootstrap.branch,
+                "base": info.default_branch,
+                "body": format!("Adds the standard files from `{}`.", bootstrap.template),
+            }),
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Collect all files for the provided paths, descending into directories.
+fn collect(github: &GitHub, template: &str, paths: &[String]) -> anyhow::Result<Vec<FileContent>> {
+    let mut result = Vec::new();
+
+    for path in paths {
+        match github.get_optional::<Contents>(&format!("repos/{}/contents/{}", template, path))? {
+            Some(Contents::File(file)) => result.push(file),
+            Some(Contents::Directory(entries)) => {
+                let entries = entries
+                    .into_iter()
+                    .filter(|e| e.r#type == "file" || e.r#type == "dir")
+                    .map(|e| e.path)
+                    .collect::<Vec<_>>();
+                result.extend(collect(github, template, &entries)?);
+            }
+            None => log::info!("Template {} has no file: {}", template, path),
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::MockGitHub;
+
+    #[test]
+    fn test_bootstrap() -> anyhow::Result<()> {
+        let config: Bootstrap = serde_yaml::from_str(
+            r#"
+template: ctron/template
+files: ["LICENSE"]
+"#,
+        )?;
+        let writes = |mock: &MockGitHub, start: usize| {
+            mock.calls()
+                .into_iter()
+                .skip(start)
+                .filter(|call| call.method != "GET")
+                .collect::<Vec<_>>()
+        };
+
+        let mock = MockGitHub::new().install();
+        mock.respond(
+            "GET",
+            "repos/ctron/template/contents/LICENSE",
+            json!({"path": "LICENSE", "content": "QXBhY2hl\n", "sha": "1"}),
+        );
+        mock.respond("GET", "repos/ctron/new/pulls", json!([]));
+        mock.respond("GET", "repos/ctron/new", json!({"default_branch": "main"}));
+        mock.respond(
+            "GET",
+            "repos/ctron/new/git/ref/heads/main",
+            json!({"object": {"sha": "abc"}}),
+        );
+        mock.respond("POST", "repos/ctron/new/git/refs", json!({}));
+        mock.respond("PUT", "repos/ctron/new/contents/LICENSE", json!({}));
+        mock.respond("POST", "repos/ctron/new/pulls", json!({}));
+
+        // the first run creates the branch, the file and the pull request
+        bootstrap(&GitHub::new(), &config, "ctron/new")?;
+        let calls = writes(&mock, 0);
+        assert_eq!(
+            calls.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(),
+            vec![
+                "repos/ctron/new/git/refs",
+                "repos/ctron/new/contents/LICENSE",
+                "repos/ctron/new/pulls"
+            ]
+        );
+        assert_eq!(calls[1].body.as_ref().unwrap()["sha"], Value::Null);
+
+        // running again, after the branch and a changed file were created, but not the pull request
+        let start = mock.calls().len();
+        mock.respond(
+            "GET",
+            "repos/ctron/new/git/ref/heads/rodbot/bootstrap",
+            json!({"object": {"sha": "def"}}),
+        );
+        mock.respond(
+            "GET",
+            "repos/ctron/new/contents/LICENSE",
+            json!({"path": "LICENSE", "content": "TUlU\n", "sha": "2"}),
+        );
+        bootstrap(&GitHub::new(), &config, "ctron/new")?;
+        let calls = writes(&mock, start);
+        assert_eq!(
+            calls.iter().map(|c| c.path.as_str()).collect::<Vec<_>>(),
+            vec!["repos/ctron/new/contents/LICENSE", "repos/ctron/new/pulls"]
+        );
+        assert_eq!(calls[0].body.as_ref().unwrap()["sha"], "2");
+
+        // running again with the pull request open
+        let start = mock.calls().len();
+        mock.respond("GET", "repos/ctron/new/pulls", json!([{"number": 1}]));
+        bootstrap(&GitHub::new(), &config, "ctron/new")?;
+        assert!(writes(&mock, start).is_empty());
+
+        Ok(())
+    }
+}
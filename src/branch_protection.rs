@@ -0,0 +1,285 @@
+use crate::{
+    github::GitHub,
+    output::Output,
+    report::{track, IssueReport},
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::File;
+
+/// Declarative branch protection settings for a set of repositories.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct BranchProtectionConfig {
+    pub rules: Vec<Rule>,
+    /// Report drift using a tracking issue.
+    #[serde(default)]
+    pub report: Option<IssueReport>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Rule {
+    /// Repositories (`owner/name`) the rule applies to.
+    pub repositories: Vec<String>,
+    pub branch: String,
+    #[serde(flatten)]
+    pub protection: Protection,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Protection {
+    /// Status checks required to pass, none if missing.
+    #[serde(default)]
+    pub required_checks: Option<Vec<String>>,
+    /// Require branches to be up to date before merging.
+    #[serde(default)]
+    pub strict: bool,
+    /// Number of required approving reviews, no reviews required if missing.
+    #[serde(default)]
+    pub required_reviews: Option<u8>,
+    #[serde(default)]
+    pub dismiss_stale_reviews: bool,
+    #[serde(default)]
+    pub require_code_owner_reviews: bool,
+    #[serde(default)]
+    pub enforce_admins: bool,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ApiProtection {
+    required_status_checks: Option<ApiStatusChecks>,
+    required_pull_request_reviews: Option<ApiReviews>,
+    enforce_admins: Option<ApiEnabled>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ApiStatusChecks {
+    #[serde(default)]
+    strict: bool,
+    #[serde(default)]
+    contexts: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ApiReviews {
+    #[serde(default)]
+    dismiss_stale_reviews: bool,
+    #[serde(default)]
+    require_code_owner_reviews: bool,
+    #[serde(default)]
+    required_approving_review_count: u8,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ApiEnabled {
+    enabled: bool,
+}
+
+impl From<ApiProtection> for Protection {
+    fn from(api: ApiProtection) -> Self {
+        let (required_checks, strict) = match api.required_status_checks {
+            Some(checks) => (Some(checks.contexts), checks.strict),
+            None => (None, false),
+        };
+        let reviews = api.required_pull_request_reviews;
+        Self {
+            required_checks,
+            strict,
+            required_reviews: reviews.as_ref().map(|r| r.required_approving_review_count),
+            dismiss_stale_reviews: reviews.as_ref().is_some_and(|r| r.dismiss_stale_reviews),
+            require_code_owner_reviews: reviews
+                .as_ref()
+                .is_some_and(|r| r.require_code_owner_reviews),
+            enforce_admins: api.enforce_admins.is_some_and(|e| e.enabled),
+        }
+    }
+}
+
+impl Protection {
+    fn to_api(&self) -> serde_json::Value {
+        json!({
+            "required_status_checks": self.required_checks.as_ref().map(|contexts| json!({
+                "strict": self.strict,
+                "contexts": contexts,
+            })),
+            "enforce_admins": self.enforce_admins,
+            "required_pull_request_reviews": self.required_reviews.map(|count| json!({
+                "dismiss_stale_reviews": self.dismiss_stale_reviews,
+                "require_code_owner_reviews": self.require_code_owner_reviews,
+                "required_approving_review_count": count,
+            })),
+            "restrictions": null,
+        })
+    }
+}
+
+/// Compare the current protection settings with the desired ones.
+pub fn drift(desired: &Protection, current: Option<&Protection>) -> Vec<String> {
+    let current = match current {
+        Some(current) => current,
+        None => return vec!["branch is not protected".into()],
+    };
+
+    let mut result = Vec::new();
+
+    let mut compare = |name: &str, desired: String, current: String| {
+        if desired != current {
+            result.push(format!("{}: {} (expected: {})", name, current, desired));
+        }
+    };
+
+    let checks = |checks: &Option<Vec<String>>| match checks {
+        Some(checks) => {
+            let mut checks = checks.clone();
+            checks.sort();
+            format!("{:?}", checks)
+        }
+        None => "none".into(),
+    };
+
+    compare(
+        "required checks",
+        checks(&desired.required_checks),
+        checks(&current.required_checks),
+    );
+    if desired.required_checks.is_some() {
+        compare(
+            "strict",
+            desired.strict.to_string(),
+            current.strict.to_string(),
+        );
+    }
+
+    let reviews = |reviews: Option<u8>| reviews.map_or_else(|| "none".into(), |r| r.to_string());
+
+    compare(
+        "required reviews",
+        reviews(desired.required_reviews),
+        reviews(current.required_reviews),
+    );
+    if desired.required_reviews.is_some() {
+        compare(
+            "dismiss stale reviews",
+            desired.dismiss_stale_reviews.to_string(),
+            current.dismiss_stale_reviews.to_string(),
+        );
+        compare(
+            "require code owner reviews",
+            desired.require_code_owner_reviews.to_string(),
+            current.require_code_owner_reviews.to_string(),
+        );
+    }
+    compare(
+        "enforce admins",
+        desired.enforce_admins.to_string(),
+        current.enforce_admins.to_string(),
+    );
+
+    result
+}
+
+/// Drift of the protection settings of a branch.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct BranchDrift {
+    pub repository: String,
+    pub branch: String,
+    pub drift: String,
+}
+
+/// Apply the branch protection settings, or only detect drift in case of a dry run.
+pub fn sync(
+    github: &GitHub,
+    config: &BranchProtectionConfig,
+    dry_run: bool,
+) -> anyhow::Result<Vec<BranchDrift>> {
+    let mut result = Vec::new();
+    let mut remaining = Vec::new();
+
+    for rule in &config.rules {
+        for repository in &rule.repositories {
+            let path = format!("repos/{}/branches/{}/protection", repository, rule.branch);
+            let current = github
+                .get_optional::<ApiProtection>(&path)?
+                .map(Protection::from);
+
+            let drift = drift(&rule.protection, current.as_ref());
+            if drift.is_empty() {
+                continue;
+            }
+
+            if dry_run {
+                remaining.extend(
+                    drift
+                        .iter()
+                        .map(|d| format!("`{}@{}`: {}", repository, rule.branch, d)),
+                );
+            } else {
+                github.put(&path, &rule.protection.to_api())?;
+            }
+
+            result.extend(drift.into_iter().map(|drift| BranchDrift {
+                repository: repository.clone(),
+                branch: rule.branch.clone(),
+                drift,
+            }));
+        }
+    }
+
+    if let Some(report) = &config.report {
+        track(github, report, &remaining)?;
+    }
+
+    Ok(result)
+}
+
+pub fn sync_command(matches: &clap::ArgMatches) -> anyhow::Result<()> {
+    let from = matches.value_of("from").unwrap_or("branch-protection.yaml");
+    log::debug!("Loading branch protection settings from: {}", from);
+
+    let config: BranchProtectionConfig =
+        serde_yaml::from_reader(File::open(from)?).context("Loading branch protection settings")?;
+
+    let dry_run = matches.is_present("dry-run");
+    let drift = sync(&GitHub::new(), &config, dry_run)?;
+
+    Output::from_matches(matches).print(&json!({ "dry_run": dry_run, "drift": drift }), || {
+        for d in &drift {
+            println!("{}@{}: {}", d.repository, d.branch, d.drift);
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_drift() {
+        let desired = Protection {
+            required_checks: Some(vec!["ci".into(), "lint".into()]),
+            strict: true,
+            required_reviews: Some(1),
+            ..Default::default()
+        };
+
+        assert_eq!(drift(&desired, None), vec!["branch is not protected"]);
+        assert!(drift(&desired, Some(&desired)).is_empty());
+
+        let current = Protection {
+            required_checks: Some(vec!["lint".into(), "ci".into()]),
+            strict: false,
+            required_reviews: None,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            drift(&desired, Some(&current)),
+            vec![
+                "strict: false (expected: true)",
+                "required reviews: none (expected: 1)",
+            ]
+        );
+    }
+}
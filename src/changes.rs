@@ -0,0 +1,375 @@
+use crate::{
+    clock,
+    config::{Glob, Pattern, SizeLimit},
+    event::PullRequest,
+    github::GitHub,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+lazy_static! {
+    static ref HUNK: Regex = Regex::new(r"^@@ -\d+(?:,\d+)? \+(\d+)(?:,\d+)? @@").unwrap();
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct File {
+    pub filename: String,
+    /// The patch of the file, missing for binary or very large files.
+    pub patch: Option<String>,
+    /// The status, like `added`, `modified` or `removed`.
+    #[serde(default)]
+    pub status: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct PullRequestHead {
+    head: Sha,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Sha {
+    sha: String,
+}
+
+/// Fetch the SHA of the head commit of a pull request.
+pub fn head_sha(github: &GitHub, repository: &str, number: u64) -> anyhow::Result<String> {
+    Ok(github
+        .get::<PullRequestHead>(&format!("repos/{}/pulls/{}", repository, number))?
+        .head
+        .sha)
+}
+
+/// Fetch a pull request, for events which only reference it, like `issue_comment`.
+pub fn pull_request(github: &GitHub, repository: &str, number: u64) -> anyhow::Result<PullRequest> {
+    github.get(&format!("repos/{}/pulls/{}", repository, number))
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Review {
+    pub id: u64,
+    /// Missing for deleted accounts.
+    pub user: Option<Login>,
+    /// The state, like `APPROVED`, `CHANGES_REQUESTED` or `COMMENTED`.
+    pub state: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Login {
+    pub login: String,
+}
+
+/// Count the users whose latest review approves the changes.
+///
+/// Only comments don't change the previous state of a user's review.
+pub fn count_approvals(reviews: &[Review]) -> usize {
+    let mut states = BTreeMap::new();
+    for review in reviews {
+        if let Some(user) = &review.user {
+            if review.state != "COMMENTED" && review.state != "PENDING" {
+                states.insert(user.login.as_str(), review.state.as_str());
+            }
+        }
+    }
+    states
+        .values()
+        .filter(|state| **state == "APPROVED")
+        .count()
+}
+
+/// Fetch the number of distinct approvals of a pull request.
+pub fn approvals(github: &GitHub, repository: &str, number: u64) -> anyhow::Result<usize> {
+    let reviews =
+        github.get_all::<Review>(&format!("repos/{}/pulls/{}/reviews", repository, number))?;
+    Ok(count_approvals(&reviews))
+}
+
+/// Check if a `mergeable_state` allows merging, `unstable` meaning non-required checks fail.
+pub fn is_mergeable_state(state: &str) -> bool {
+    matches!(state, "clean" | "unstable" | "has_hooks")
+}
+
+/// Check if the pull request can be merged, waiting for GitHub to compute the state if required.
+///
+/// Returns the pull request which was checked, if it can be merged.
+pub fn is_mergeable(
+    github: &GitHub,
+    repository: &str,
+    number: u64,
+) -> anyhow::Result<Option<PullRequest>> {
+    const ATTEMPTS: u32 = 3;
+
+    for attempt in 1..=ATTEMPTS {
+        let pull_request = pull_request(github, repository, number)?;
+        let state = pull_request.mergeable_state.as_deref().unwrap_or("unknown");
+        if state == "unknown" && attempt < ATTEMPTS {
+            clock::sleep(std::time::Duration::from_secs(2));
+            continue;
+        }
+
+        if !is_mergeable_state(state) {
+            log::info!(
+                "Pull request {}#{} is not mergeable: {}",
+                repository,
+                number,
+                state
+            );
+            return Ok(None);
+        }
+        return Ok(Some(pull_request));
+    }
+
+    Ok(None)
+}
+
+/// Check if the size of a pull request is below the limits.
+pub fn is_smaller_than(pull_request: &PullRequest, limit: &SizeLimit) -> bool {
+    let files = pull_request.changed_files.unwrap_or_default();
+    let lines =
+        pull_request.additions.unwrap_or_default() + pull_request.deletions.unwrap_or_default();
+    limit.files.is_none_or(|max| files < max) && limit.lines.is_none_or(|max| lines < max)
+}
+
+/// Check if the pull request is smaller than the limits, fetching its size.
+pub fn pr_smaller_than(
+    github: &GitHub,
+    repository: &str,
+    number: u64,
+    limit: &SizeLimit,
+) -> anyhow::Result<bool> {
+    let pull_request = pull_request(github, repository, number)?;
+    let result = is_smaller_than(&pull_request, limit);
+    if !result {
+        log::info!(
+            "Pull request {}#{} exceeds the size limit: {} files, {} lines",
+            repository,
+            number,
+            pull_request.changed_files.unwrap_or_default(),
+            pull_request.additions.unwrap_or_default() + pull_request.deletions.unwrap_or_default()
+        );
+    }
+    Ok(result)
+}
+
+/// Fetch the files changed by a pull request.
+pub fn files(github: &GitHub, repository: &str, number: u64) -> anyhow::Result<Vec<File>> {
+    github.get_all(&format!("repos/{}/pulls/{}/files", repository, number))
+}
+
+/// Fetch the names of the files changed by a pull request.
+pub fn changed_files(
+    github: &GitHub,
+    repository: &str,
+    number: u64,
+) -> anyhow::Result<Vec<String>> {
+    Ok(files(github, repository, number)?
+        .into_iter()
+        .map(|f| f.filename)
+        .collect())
+}
+
+/// The lines added by a patch.
+pub fn added_lines(patch: &str) -> impl Iterator<Item = &str> {
+    patch
+        .lines()
+        .filter(|line| !line.starts_with("+++"))
+        .filter_map(|line| line.strip_prefix('+'))
+}
+
+/// The lines added by a patch, along with their line number in the new file.
+pub fn numbered_added_lines(patch: &str) -> Vec<(u64, &str)> {
+    let mut result = Vec::new();
+    let mut line = 0;
+
+    for text in patch.lines() {
+        if let Some(start) = HUNK.captures(text).and_then(|c| c[1].parse::<u64>().ok()) {
+            line = start;
+            continue;
+        }
+
+        if text.starts_with('-') {
+            continue;
+        }
+
+        if let Some(added) = text.strip_prefix('+') {
+            result.push((line, added));
+        }
+
+        line += 1;
+    }
+
+    result
+}
+
+/// Check if any line added by the pull request matches the pattern.
+pub fn added_lines_match(
+    github: &GitHub,
+    repository: &str,
+    number: u64,
+    pattern: &Pattern,
+) -> anyhow::Result<bool> {
+    for file in files(github, repository, number)? {
+        if let Some(line) = file
+            .patch
+            .as_deref()
+            .and_then(|patch| added_lines(patch).find(|line| pattern.is_match(line)))
+        {
+            log::debug!("{}: added line matches: {}", file.filename, line);
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Check if any of the files matches any of the globs.
+pub fn touches<S: AsRef<str>>(globs: &[Glob], files: &[S]) -> bool {
+    files
+        .iter()
+        .any(|file| globs.iter().any(|glob| glob.is_match(file.as_ref())))
+}
+
+/// Evaluate the components affected by the changed files.
+pub fn components(
+    components: &BTreeMap<String, String>,
+    files: &[String],
+) -> anyhow::Result<BTreeSet<String>> {
+    let mut result = BTreeSet::new();
+
+    for (glob, component) in components {
+        let glob = Glob::new(glob)?;
+        if touches(std::slice::from_ref(&glob), files) {
+            result.insert(component.clone());
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        clock::{Clock, FixedClock},
+        mock::MockGitHub,
+    };
+
+    #[test]
+    fn test_is_mergeable_state() {
+        assert!(is_mergeable_state("clean"));
+        assert!(is_mergeable_state("unstable"));
+        assert!(!is_mergeable_state("dirty"));
+        assert!(!is_mergeable_state("blocked"));
+        assert!(!is_mergeable_state("unknown"));
+    }
+
+    #[test]
+    fn test_is_mergeable() -> anyhow::Result<()> {
+        let event: serde_json::Value =
+            serde_json::from_reader(std::fs::File::open("test/pull_request_1.json")?)?;
+        let with_state = |state: &str| {
+            let mut pull_request = event["pull_request"].clone();
+            pull_request["mergeable_state"] = state.into();
+            pull_request
+        };
+
+        let mock = MockGitHub::new().install();
+        let clock = FixedClock::new(1, 0, 1_000).install();
+        let github = GitHub::new();
+
+        // waits while GitHub computes the state
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", with_state("clean"));
+        mock.respond_once("GET", "repos/ctron/rodbot/pulls/1", with_state("unknown"));
+        assert!(is_mergeable(&github, "ctron/rodbot", 1)?.is_some());
+        assert_eq!(clock.epoch(), 1_002);
+        assert_eq!(mock.calls_to("GET", "repos/ctron/rodbot/pulls/1").len(), 2);
+
+        // gives up after the last attempt
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", with_state("unknown"));
+        assert!(is_mergeable(&github, "ctron/rodbot", 1)?.is_none());
+        assert_eq!(clock.epoch(), 1_006);
+        assert_eq!(mock.calls_to("GET", "repos/ctron/rodbot/pulls/1").len(), 5);
+
+        // doesn't wait for a known state
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", with_state("dirty"));
+        assert!(is_mergeable(&github, "ctron/rodbot", 1)?.is_none());
+        assert_eq!(clock.epoch(), 1_006);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_smaller_than() -> anyhow::Result<()> {
+        let event: crate::event::PullRequestEvent =
+            serde_json::from_reader(std::fs::File::open("test/pull_request_1.json")?)?;
+        let mut pull_request = event.pull_request;
+        pull_request.changed_files = Some(10);
+        pull_request.additions = Some(400);
+        pull_request.deletions = Some(100);
+
+        let limit = |files, lines| SizeLimit { files, lines };
+        assert!(is_smaller_than(&pull_request, &limit(None, None)));
+        assert!(is_smaller_than(&pull_request, &limit(Some(11), Some(501))));
+        assert!(!is_smaller_than(&pull_request, &limit(Some(10), None)));
+        assert!(!is_smaller_than(&pull_request, &limit(None, Some(500))));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_approvals() {
+        let review = |login: &str, state: &str| Review {
+            id: 1,
+            user: Some(Login {
+                login: login.into(),
+            }),
+            state: state.into(),
+        };
+
+        let reviews = vec![
+            review("ctron", "APPROVED"),
+            review("foo", "CHANGES_REQUESTED"),
+            review("bar", "APPROVED"),
+            review("ctron", "COMMENTED"),
+            review("bar", "DISMISSED"),
+            review("foo", "APPROVED"),
+            review("ctron", "APPROVED"),
+        ];
+
+        assert_eq!(count_approvals(&reviews), 2);
+    }
+
+    #[test]
+    fn test_added_lines() {
+        let patch = "@@ -1,3 +1,4 @@\n fn main() {\n-    foo();\n+    // TODO: remove\n+    unsafe { foo() };\n }";
+
+        assert_eq!(
+            added_lines(patch).collect::<Vec<_>>(),
+            vec!["    // TODO: remove", "    unsafe { foo() };"]
+        );
+    }
+
+    #[test]
+    fn test_components() -> anyhow::Result<()> {
+        let map = vec![
+            ("console/**", "console"),
+            ("device-management-service/**", "registry"),
+            ("*.md", "docs"),
+        ]
+        .into_iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+
+        let files = vec![
+            "console/src/main.rs".to_string(),
+            "console/README.md".to_string(),
+        ];
+
+        assert_eq!(
+            components(&map, &files)?.into_iter().collect::<Vec<_>>(),
+            vec!["console", "docs"]
+        );
+
+        Ok(())
+    }
+}
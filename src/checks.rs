@@ -0,0 +1,443 @@
+use crate::{
+    changes::{added_lines, files},
+    config::{FilesCheck, LargeFilesCheck},
+    github::GitHub,
+    steps::{add_labels, remove_label, sticky_comment, IssueRef},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+/// The maximum number of annotations GitHub accepts per request.
+const MAX_ANNOTATIONS: usize = 50;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Annotation {
+    pub path: String,
+    pub line: u64,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AddedFile {
+    pub filename: String,
+    /// The patch, missing for binary files.
+    pub patch: Option<String>,
+    /// The size in bytes, only known for binary files.
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Contents {
+    size: u64,
+}
+
+fn file_size(github: &GitHub, repository: &str, path: &str, sha: &str) -> anyhow::Result<u64> {
+    Ok(github
+        .get::<Contents>(&format!(
+            "repos/{}/contents/{}?ref={}",
+            repository, path, sha
+        ))?
+        .size)
+}
+
+/// A completed check run, which fails if there are annotations.
+pub struct CheckRun<'a> {
+    pub name: &'a str,
+    pub head_sha: &'a str,
+    pub title: &'a str,
+    pub summary: &'a str,
+    pub annotations: &'a [Annotation],
+}
+
+/// Report the check run, including up to the first 50 annotations.
+pub fn create_check_run(github: &GitHub, repository: &str, run: &CheckRun) -> anyhow::Result<()> {
+    let conclusion = if run.annotations.is_empty() {
+        "success"
+    } else {
+        "failure"
+    };
+
+    github.post(
+        &format!("repos/{}/check-runs", repository),
+        &json!({
+            "name": run.name,
+            "head_sha": run.head_sha,
+            "status": "completed",
+            "conclusion": conclusion,
+            "output": {
+                "title": run.title,
+                "summary": run.summary,
+                "annotations": run.annotations
+                    .iter()
+                    .take(MAX_ANNOTATIONS)
+                    .map(|a| json!({
+                        "path": a.path,
+                        "start_line": a.line,
+                        "end_line": a.line,
+                        "annotation_level": "failure",
+                        "message": a.message,
+                    }))
+                    .collect::<Vec<_>>(),
+            },
+        }),
+    )?;
+
+    Ok(())
+}
+
+/// Check the added files against the policy.
+pub fn evaluate(check: &FilesCheck, files: &[AddedFile]) -> Vec<Annotation> {
+    let mut result = Vec::new();
+
+    for file in files {
+        let mut annotate = |message: String| {
+            result.push(Annotation {
+                path: file.filename.clone(),
+                line: 1,
+                message,
+            })
+        };
+
+        if check
+            .forbidden_paths
+            .iter()
+            .any(|glob| glob.is_match(&file.filename))
+        {
+            annotate("Adding files to this path is not allowed".into());
+        }
+
+        if let (Some(max), Some(size)) = (check.max_binary_size, file.size) {
+            if size > max * 1024 {
+                annotate(format!(
+                    "Binary file is too large: {} KiB (maximum: {} KiB)",
+                    size / 1024,
+                    max
+                ));
+            }
+        }
+
+        if let Some(header) = &check.license_header {
+            if header
+                .paths
+                .iter()
+                .any(|glob| glob.is_match(&file.filename))
+            {
+                let found = file.patch.as_deref().is_some_and(|patch| {
+                    added_lines(patch)
+                        .take(header.lines)
+                        .any(|line| header.pattern.is_match(line))
+                });
+                if !found {
+                    annotate("Missing license header".into());
+                }
+            }
+        }
+    }
+
+    result
+}
+
+/// Check the files added by a pull request, and report the result as a check run.
+pub fn files_check(
+    github: &GitHub,
+    check: &FilesCheck,
+    repository: &str,
+    number: u64,
+    head_sha: &str,
+) -> anyhow::Result<()> {
+    let mut added = Vec::new();
+
+    for file in files(github, repository, number)? {
+        if file.status != "added" {
+            continue;
+        }
+        let size = match (&file.patch, check.max_binary_size) {
+            (None, Some(_)) => Some(file_size(github, repository, &file.filename, head_sha)?),
+            _ => None,
+        };
+        added.push(AddedFile {
+            filename: file.filename,
+            patch: file.patch,
+            size,
+        });
+    }
+
+    let annotations = evaluate(check, &added);
+    log::info!("File check: {} finding(s)", annotations.len());
+
+    let title = if annotations.is_empty() {
+        "All added files comply with the policy".to_string()
+    } else {
+        format!("{} problem(s) with added files", annotations.len())
+    };
+
+    create_check_run(
+        github,
+        repository,
+        &CheckRun {
+            name: &check.name,
+            head_sha,
+            title: &title,
+            summary: &format!("Checked {} added file(s).", added.len()),
+            annotations: &annotations,
+        },
+    )?;
+
+    Ok(())
+}
+
+/// Find files which are too large, or have a binary file type.
+pub fn large_files(check: &LargeFilesCheck, files: &[AddedFile]) -> Vec<String> {
+    let mut result = Vec::new();
+
+    for file in files {
+        let extension = std::path::Path::new(&file.filename)
+            .extension()
+            .map(|e| e.to_string_lossy().to_lowercase());
+        if let Some(extension) = extension {
+            if check
+                .binary_extensions
+                .iter()
+                .any(|e| e.to_lowercase() == extension)
+            {
+                result.push(format!("`{}`: binary file type", file.filename));
+            }
+        }
+
+        if let (Some(max), Some(size)) = (check.max_size, file.size) {
+            if size > max * 1024 {
+                result.push(format!(
+                    "`{}`: {} KiB (maximum: {} KiB)",
+                    file.filename,
+                    size / 1024,
+                    max
+                ));
+            }
+        }
+    }
+
+    result
+}
+
+/// Flag the pull request with a label and a comment if it adds large or binary files.
+///
+/// Once the files are gone, the label and comment get removed again.
+pub fn large_files_check(
+    github: &GitHub,
+    check: &LargeFilesCheck,
+    issue: &IssueRef,
+    head_sha: &str,
+) -> anyhow::Result<()> {
+    let mut changed = Vec::new();
+
+    for file in files(github, &issue.repository, issue.number)? {
+        if file.status == "removed" {
+            continue;
+        }
+        // GitHub omits the patch of binary and very large files
+        let size = match (&file.patch, check.max_size) {
+            (None, Some(_)) => Some(file_size(
+                github,
+                &issue.repository,
+                &file.filename,
+                head_sha,
+            )?),
+            _ => None,
+        };
+        changed.push(AddedFile {
+            filename: file.filename,
+            patch: None,
+            size,
+        });
+    }
+
+    let findings = large_files(check, &changed);
+
+    if findings.is_empty() {
+        remove_label(github, issue, &check.label)?;
+        sticky_comment(github, issue, "large-files", None)?;
+    } else {
+        log::info!("Pull request adds large files: {:?}", findings);
+        add_labels(github, issue, std::slice::from_ref(&check.label))?;
+        let body = format!(
+            "This pull request adds large or binary files:\n\n{}",
+            findings
+                .iter()
+                .map(|f| format!("* {}", f))
+                .collect::<Vec<_>>()
+                .join("\n")
+        );
+        sticky_comment(github, issue, "large-files", Some(&body))?;
+    }
+
+    Ok(())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CheckRuns {
+    check_runs: Vec<CheckRunStatus>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CheckRunStatus {
+    name: String,
+    status: String,
+    conclusion: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CombinedStatus {
+    statuses: Vec<CommitStatus>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CommitStatus {
+    context: String,
+    state: String,
+}
+
+/// Check if the checks passed, all of them if no checks are required.
+///
+/// The results map the names of check runs and contexts of statuses to their success.
+pub fn passed(results: &BTreeMap<String, bool>, required: &[String]) -> bool {
+    if required.is_empty() {
+        results.values().all(|passed| *passed)
+    } else {
+        required
+            .iter()
+            .all(|name| results.get(name).copied().unwrap_or_default())
+    }
+}
+
+/// Check if the check runs and statuses of a commit passed.
+pub fn checks_passed(
+    github: &GitHub,
+    repository: &str,
+    sha: &str,
+    required: &[String],
+) -> anyhow::Result<bool> {
+    let mut results = BTreeMap::new();
+
+    for run in github
+        .get::<CheckRuns>(&format!(
+            "repos/{}/commits/{}/check-runs?per_page=100",
+            repository, sha
+        ))?
+        .check_runs
+    {
+        let passed = run.status == "completed"
+            && matches!(
+                run.conclusion.as_deref(),
+                Some("success" | "neutral" | "skipped")
+            );
+        results.insert(run.name, passed);
+    }
+
+    for status in github
+        .get::<CombinedStatus>(&format!("repos/{}/commits/{}/status", repository, sha))?
+        .statuses
+    {
+        results.insert(status.context, status.state == "success");
+    }
+
+    log::debug!("Checks of {}: {:?}", sha, results);
+
+    Ok(passed(&results, required))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_passed() {
+        let results = vec![("ci".to_string(), true), ("coverage".to_string(), false)]
+            .into_iter()
+            .collect();
+
+        assert!(!passed(&results, &[]));
+        assert!(passed(&results, &["ci".into()]));
+        assert!(!passed(&results, &["ci".into(), "lint".into()]));
+        assert!(passed(&BTreeMap::new(), &[]));
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let check: FilesCheck = serde_yaml::from_str(
+            r#"
+license_header:
+  pattern: "SPDX-License-Identifier: Apache-2.0"
+  paths: ["*.rs"]
+max_binary_size: 100
+forbidden_paths: ["vendor/**"]
+"#,
+        )
+        .unwrap();
+
+        let files = vec![
+            AddedFile {
+                filename: "src/ok.rs".into(),
+                patch: Some(
+                    "@@ -0,0 +1,2 @@\n+// SPDX-License-Identifier: Apache-2.0\n+fn main() {}"
+                        .into(),
+                ),
+                size: None,
+            },
+            AddedFile {
+                filename: "src/missing.rs".into(),
+                patch: Some("@@ -0,0 +1 @@\n+fn main() {}".into()),
+                size: None,
+            },
+            AddedFile {
+                filename: "vendor/image.png".into(),
+                patch: None,
+                size: Some(200 * 1024),
+            },
+        ];
+
+        assert_eq!(
+            evaluate(&check, &files)
+                .into_iter()
+                .map(|a| format!("{}: {}", a.path, a.message))
+                .collect::<Vec<_>>(),
+            vec![
+                "src/missing.rs: Missing license header",
+                "vendor/image.png: Adding files to this path is not allowed",
+                "vendor/image.png: Binary file is too large: 200 KiB (maximum: 100 KiB)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_large_files() {
+        let check = LargeFilesCheck {
+            max_size: Some(1024),
+            binary_extensions: vec!["jar".into(), "ZIP".into()],
+            label: "large".into(),
+        };
+
+        let file = |filename: &str, size: Option<u64>| AddedFile {
+            filename: filename.into(),
+            patch: None,
+            size,
+        };
+
+        assert_eq!(
+            large_files(
+                &check,
+                &[
+                    file("lib/foo.jar", Some(10 * 1024)),
+                    file("data/dump.bin", Some(2048 * 1024)),
+                    file("src/main.rs", None),
+                    file("dist.zip", None),
+                ]
+            ),
+            vec![
+                "`lib/foo.jar`: binary file type",
+                "`data/dump.bin`: 2048 KiB (maximum: 1024 KiB)",
+                "`dist.zip`: binary file type",
+            ]
+        );
+    }
+}
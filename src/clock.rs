@@ -0,0 +1,225 @@
+use crate::config::Within;
+use anyhow::Context;
+use lazy_static::lazy_static;
+use std::{
+    path::Path,
+    process::Command,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+lazy_static! {
+    static ref CLOCK: RwLock<Arc<dyn Clock>> = RwLock::new(Arc::new(SystemClock));
+}
+
+/// The source of the current time, which tests replace to be deterministic.
+pub trait Clock: Send + Sync {
+    /// The current day of the week (Monday being 1) and minute of the day, in a time zone.
+    fn local(&self, tz: Option<&str>) -> anyhow::Result<(u32, u32)>;
+    /// The seconds since the Unix epoch.
+    fn epoch(&self) -> u64;
+    fn sleep(&self, duration: Duration);
+}
+
+/// The clock of the system.
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    /// This uses `date`, so that time zones are resolved using the time zone database of the
+    /// system.
+    fn local(&self, tz: Option<&str>) -> anyhow::Result<(u32, u32)> {
+        let tz = tz.unwrap_or("UTC");
+        let zoneinfo = std::env::var("TZDIR").unwrap_or_else(|_| "/usr/share/zoneinfo".into());
+        if tz != "UTC" && !Path::new(&zoneinfo).join(tz).is_file() {
+            anyhow::bail!("Unknown time zone: {}", tz);
+        }
+
+        let output = Command::new("date")
+            .arg("+%u %H %M")
+            .env("TZ", tz)
+            .output()
+            .context("Failed to run 'date'")?;
+        if !output.status.success() {
+            anyhow::bail!("Failed to get the current time: {:?}", output.status);
+        }
+
+        let output = String::from_utf8_lossy(&output.stdout);
+        let values = output
+            .split_whitespace()
+            .map(|v| v.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .with_context(|| format!("Unexpected output of 'date': {}", output))?;
+        match values[..] {
+            [day, hour, minute] => Ok((day, hour * 60 + minute)),
+            _ => anyhow::bail!("Unexpected output of 'date': {}", output),
+        }
+    }
+
+    fn epoch(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+}
+
+/// The clock in use.
+pub fn clock() -> Arc<dyn Clock> {
+    CLOCK.read().unwrap().clone()
+}
+
+/// Replace the clock, returning the previous one.
+#[cfg(test)]
+pub fn set_clock(clock: Arc<dyn Clock>) -> Arc<dyn Clock> {
+    std::mem::replace(&mut *CLOCK.write().unwrap(), clock)
+}
+
+/// Wait for the duration, using the clock in use.
+pub fn sleep(duration: Duration) {
+    clock().sleep(duration);
+}
+
+/// Check if now is within the time window.
+pub fn is_within(window: &Within) -> anyhow::Result<bool> {
+    let (day, minute) = clock().local(window.tz.as_deref())?;
+    Ok(window.contains(day, minute))
+}
+
+/// Format seconds since the Unix epoch as UTC time, like `2021-10-22 10:53 UTC`.
+pub fn format_utc(epoch: u64) -> String {
+    let (days, seconds) = (epoch / 86_400, epoch % 86_400);
+
+    // the civil date of the days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        seconds / 3_600,
+        seconds % 3_600 / 60
+    )
+}
+
+/// A clock which stands still, and only advances when sleeping.
+#[cfg(test)]
+pub struct FixedClock {
+    pub day: u32,
+    pub minute: u32,
+    pub epoch: std::sync::atomic::AtomicU64,
+}
+
+#[cfg(test)]
+impl FixedClock {
+    pub fn new(day: u32, minute: u32, epoch: u64) -> Self {
+        Self {
+            day,
+            minute,
+            epoch: epoch.into(),
+        }
+    }
+
+    /// Use this clock instead of the system clock, until the result is dropped.
+    pub fn install(self) -> Installed {
+        let lock = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+        let clock = Arc::new(self);
+        let previous = set_clock(clock.clone());
+        Installed {
+            clock,
+            previous: Some(previous),
+            _lock: lock,
+        }
+    }
+}
+
+#[cfg(test)]
+lazy_static! {
+    /// Only one fixed clock can be installed at a time.
+    static ref INSTALLED: std::sync::Mutex<()> = std::sync::Mutex::new(());
+}
+
+/// An installed fixed clock, which restores the previous clock when dropped.
+#[cfg(test)]
+pub struct Installed {
+    clock: Arc<FixedClock>,
+    previous: Option<Arc<dyn Clock>>,
+    _lock: std::sync::MutexGuard<'static, ()>,
+}
+
+#[cfg(test)]
+impl std::ops::Deref for Installed {
+    type Target = FixedClock;
+
+    fn deref(&self) -> &Self::Target {
+        &self.clock
+    }
+}
+
+#[cfg(test)]
+impl Drop for Installed {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            set_clock(previous);
+        }
+    }
+}
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn local(&self, _: Option<&str>) -> anyhow::Result<(u32, u32)> {
+        Ok((self.day, self.minute))
+    }
+
+    fn epoch(&self) -> u64 {
+        self.epoch.load(std::sync::atomic::Ordering::SeqCst)
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.epoch
+            .fetch_add(duration.as_secs(), std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{Days, Hours};
+
+    #[test]
+    fn test_fixed_clock() -> anyhow::Result<()> {
+        let clock = FixedClock::new(6, 10 * 60, 1_000).install();
+
+        let window = Within {
+            days: vec![Days(1, 5)],
+            hours: Some(Hours(9 * 60, 17 * 60)),
+            tz: Some("Europe/Berlin".into()),
+        };
+        let result = is_within(&window);
+        sleep(Duration::from_secs(60));
+
+        assert!(!result?);
+        assert_eq!(clock.epoch(), 1_060);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_format_utc() {
+        assert_eq!(format_utc(0), "1970-01-01 00:00 UTC");
+        assert_eq!(format_utc(1_634_900_038), "2021-10-22 10:53 UTC");
+        assert_eq!(format_utc(951_782_400), "2000-02-29 00:00 UTC");
+    }
+}
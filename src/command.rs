@@ -0,0 +1,61 @@
+use serde::Serialize;
+
+/// A slash command, like `/backport other-repo release-1.2`.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct SlashCommand {
+    pub name: String,
+    pub args: Vec<String>,
+}
+
+impl SlashCommand {
+    /// Parse the command from the first line of a comment.
+    pub fn parse(body: &str) -> Option<Self> {
+        let line = body.lines().next()?.trim();
+        let mut tokens = line.strip_prefix('/')?.split_whitespace();
+        let name = tokens.next()?.to_string();
+
+        Some(Self {
+            name,
+            args: tokens.map(ToString::to_string).collect(),
+        })
+    }
+}
+
+/// Resolve a repository name, relative to the owner of the current repository.
+pub fn resolve_repository(name: &str, current: &str) -> String {
+    match (name.contains('/'), current.split_once('/')) {
+        (false, Some((owner, _))) => format!("{}/{}", owner, name),
+        _ => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            SlashCommand::parse("/backport other-repo  release-1.2\nPlease!"),
+            Some(SlashCommand {
+                name: "backport".into(),
+                args: vec!["other-repo".into(), "release-1.2".into()],
+            })
+        );
+        assert_eq!(SlashCommand::parse("Please /backport"), None);
+        assert_eq!(SlashCommand::parse("/"), None);
+        assert_eq!(SlashCommand::parse(""), None);
+    }
+
+    #[test]
+    fn test_resolve_repository() {
+        assert_eq!(
+            resolve_repository("other-repo", "drogue-iot/drogue-cloud"),
+            "drogue-iot/other-repo"
+        );
+        assert_eq!(
+            resolve_repository("ctron/rodbot", "drogue-iot/drogue-cloud"),
+            "ctron/rodbot"
+        );
+    }
+}
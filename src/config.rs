@@ -1,54 +1,1892 @@
-use crate::event::AuthorAssociation;
-use serde::Deserialize;
+use crate::{
+    event::{AuthorAssociation, RefType, ReviewState},
+    index::RuleIndex,
+};
+use regex::Regex;
+use serde::{de, Deserialize, Deserializer};
+use std::collections::BTreeMap;
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct Config {
     pub on: On,
+    /// Map of path globs to component names, made available to pull request related rules as
+    /// `components` (and `component`, in case only a single component was changed).
+    #[serde(default)]
+    pub components: BTreeMap<String, String>,
+    #[serde(default)]
+    pub checks: Checks,
+    /// Named sets of conditions, which `issue_comment` rules can use with `ref`.
+    #[serde(default)]
+    pub conditions: BTreeMap<String, Vec<IfIssueComment>>,
+    /// The minimum version of rodbot the configuration requires.
+    #[serde(default)]
+    pub min_version: Option<serde_yaml::Value>,
+    /// Warn when a newer version of rodbot is available.
+    #[serde(default)]
+    pub check_for_updates: bool,
+    /// Report failures of rules in issues. Rules with an owner are always reported.
+    #[serde(default)]
+    pub failures: Option<Failures>,
+    #[serde(default)]
+    pub normalize: Normalize,
+    /// Named sequences of steps with parameters, which steps can use with `use`.
+    #[serde(default)]
+    pub step_templates: BTreeMap<String, StepTemplate>,
+    #[serde(skip)]
+    pub index: RuleIndex,
+}
+
+impl Config {
+    /// Replace the references to named condition sets with their conditions.
+    pub fn resolve_conditions(&mut self) -> anyhow::Result<()> {
+        fn resolve(
+            conditions: &mut [IfIssueComment],
+            sets: &BTreeMap<String, Vec<IfIssueComment>>,
+            stack: &mut Vec<String>,
+        ) -> anyhow::Result<()> {
+            for condition in conditions {
+                match condition {
+                    IfIssueComment::Ref(name) => {
+                        if stack.contains(name) {
+                            anyhow::bail!("Condition set '{}' references itself", name);
+                        }
+                        let mut set = sets
+                            .get(name.as_str())
+                            .cloned()
+                            .ok_or_else(|| anyhow::anyhow!("Unknown condition set '{}'", name))?;
+                        stack.push(name.clone());
+                        resolve(&mut set, sets, stack)?;
+                        stack.pop();
+                        *condition = IfIssueComment::And(set);
+                    }
+                    IfIssueComment::Not(child) => {
+                        resolve(std::slice::from_mut(child.as_mut()), sets, stack)?
+                    }
+                    IfIssueComment::And(children) | IfIssueComment::Or(children) => {
+                        resolve(children, sets, stack)?
+                    }
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+
+        for rule in self.on.issue_comment.iter_mut().flatten() {
+            resolve(&mut rule.r#if, &self.conditions, &mut vec![])?;
+        }
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Resolve the step templates used by steps.
+    pub fn resolve_step_templates(&mut self) -> anyhow::Result<()> {
+        fn resolve(
+            steps: &mut [Step],
+            templates: &BTreeMap<String, StepTemplate>,
+            stack: &mut Vec<String>,
+        ) -> anyhow::Result<()> {
+            for step in steps {
+                match step {
+                    Step::Use(config) => {
+                        let name = &config.template;
+                        if stack.contains(name) {
+                            anyhow::bail!("Step template '{}' uses itself", name);
+                        }
+                        let template = templates
+                            .get(name)
+                            .ok_or_else(|| anyhow::anyhow!("Unknown step template '{}'", name))?;
+                        if let Some(param) = template
+                            .params
+                            .iter()
+                            .find(|p| !config.with.contains_key(*p))
+                        {
+                            anyhow::bail!(
+                                "Missing parameter '{}' of step template '{}'",
+                                param,
+                                name
+                            );
+                        }
+                        if let Some(param) =
+                            config.with.keys().find(|p| !template.params.contains(p))
+                        {
+                            anyhow::bail!(
+                                "Unknown parameter '{}' of step template '{}'",
+                                param,
+                                name
+                            );
+                        }
+
+                        let mut steps = template.steps.clone();
+                        stack.push(name.clone());
+                        resolve(&mut steps, templates, stack)?;
+                        stack.pop();
+                        config.steps = steps;
+                    }
+                    Step::SearchIssues(config) => resolve(&mut config.for_each, templates, stack)?,
+                    _ => {}
+                }
+            }
+            Ok(())
+        }
+
+        for rule in self.on.rules_mut() {
+            resolve(&mut rule.steps, &self.step_templates, &mut vec![])?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A sequence of steps, which may use its parameters as `params`, like `${{ params.env }}`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct StepTemplate {
+    /// The names of the parameters, which are required.
+    #[serde(default)]
+    pub params: Vec<String>,
+    #[serde(deserialize_with = "deserialize_steps")]
+    pub steps: Vec<Step>,
+}
+
+/// Use a step template, like `use: deploy` and `with: {env: staging}`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Use {
+    #[serde(rename = "use")]
+    pub template: String,
+    /// The parameters, strings may contain expressions.
+    #[serde(default)]
+    pub with: BTreeMap<String, serde_json::Value>,
+    /// The steps of the template, once resolved.
+    #[serde(skip)]
+    pub steps: Vec<Step>,
+}
+
+/// Deserialize steps, including the ones using a template.
+///
+/// Using a template takes two keys, `use` and `with`, unlike the other steps.
+fn deserialize_steps<'de, D>(deserializer: D) -> Result<Vec<Step>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Vec::<serde_yaml::Value>::deserialize(deserializer)?
+        .into_iter()
+        .map(|value| match value.get("use") {
+            Some(_) => serde_yaml::from_value(value).map(Step::Use),
+            None => serde_yaml::from_value(value),
+        })
+        .collect::<Result<_, _>>()
+        .map_err(de::Error::custom)
+}
+
+/// Where failures of rules are reported, and when to notify about them.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Failures {
+    /// The repository of the issues (`owner/name`), defaults to the repository of the event.
+    #[serde(default)]
+    pub repository: Option<String>,
+    /// The number of failures after which the issue notifies, mentioning the owner of the rule.
+    #[serde(default = "default_failures_threshold")]
+    pub threshold: usize,
+    /// Only count the failures of this many hours, counts all failures if missing.
+    #[serde(default)]
+    pub window_hours: Option<u64>,
+}
+
+impl Default for Failures {
+    fn default() -> Self {
+        Self {
+            repository: None,
+            threshold: default_failures_threshold(),
+            window_hours: None,
+        }
+    }
+}
+
+fn default_failures_threshold() -> usize {
+    3
+}
+
+/// How the bodies of comments and reviews are normalized, before matching commands and patterns.
+///
+/// Expressions still see the bodies of the event as they are.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Normalize {
+    /// Normalize line endings, and strip HTML comments and zero-width characters.
+    #[serde(default = "default_true")]
+    pub builtin: bool,
+    /// Custom replacements, applied in order after the built-in ones.
+    #[serde(default)]
+    pub replace: Vec<Replace>,
+}
+
+impl Default for Normalize {
+    fn default() -> Self {
+        Self {
+            builtin: true,
+            replace: vec![],
+        }
+    }
+}
+
+/// Replace the matches of a pattern, `with` may refer to groups, like `$1`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Replace {
+    pub pattern: Pattern,
+    #[serde(default)]
+    pub with: String,
+}
+
+/// Built-in checks, run for opened or updated pull requests.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Checks {
+    #[serde(default)]
+    pub files: Option<FilesCheck>,
+    #[serde(default)]
+    pub large_files: Option<LargeFilesCheck>,
+}
+
+/// Flag pull requests adding large or binary files, using a label and a comment.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct LargeFilesCheck {
+    /// Maximum size (in KiB) of files.
+    #[serde(default)]
+    pub max_size: Option<u64>,
+    /// File extensions (without the dot) considered binary, which must not be added.
+    #[serde(default)]
+    pub binary_extensions: Vec<String>,
+    #[serde(default = "default_large_files_label")]
+    pub label: String,
+}
+
+fn default_large_files_label() -> String {
+    "do-not-merge/large-files".into()
+}
+
+/// Policy for files added by a pull request, reported as a check run.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct FilesCheck {
+    #[serde(default = "default_files_check_name")]
+    pub name: String,
+    #[serde(default)]
+    pub license_header: Option<LicenseHeader>,
+    /// Maximum size (in KiB) of binary files.
+    #[serde(default)]
+    pub max_binary_size: Option<u64>,
+    /// Paths which must not be added.
+    #[serde(default)]
+    pub forbidden_paths: Vec<Glob>,
+}
+
+fn default_files_check_name() -> String {
+    "rodbot/files".into()
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct LicenseHeader {
+    /// Pattern the header must match.
+    pub pattern: Pattern,
+    /// Files which require a license header.
+    pub paths: Vec<Glob>,
+    /// Number of lines at the start of the file which must contain the header.
+    #[serde(default = "default_license_header_lines")]
+    pub lines: usize,
+}
+
+fn default_license_header_lines() -> usize {
+    20
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct On {
+    #[serde(default)]
+    pub issue: Option<Vec<OnIssue>>,
+    #[serde(default)]
+    pub issue_comment: Option<Vec<OnIssueComment>>,
+    #[serde(default)]
+    pub pull_request: Option<Vec<OnPullRequest>>,
+    #[serde(default)]
+    pub push: Option<Vec<OnPush>>,
+    #[serde(default)]
+    pub pull_request_review: Option<Vec<OnPullRequestReview>>,
+    #[serde(default)]
+    pub pull_request_review_comment: Option<Vec<OnPullRequestReviewComment>>,
+    #[serde(default)]
+    pub workflow_dispatch: Option<Vec<OnWorkflowDispatch>>,
+    #[serde(default)]
+    pub schedule: Option<Vec<OnSchedule>>,
+    #[serde(default)]
+    pub repository_dispatch: Option<Vec<OnRepositoryDispatch>>,
+    #[serde(default)]
+    pub release: Option<Vec<OnRelease>>,
+    #[serde(default)]
+    pub discussion: Option<Vec<OnDiscussion>>,
+    #[serde(default)]
+    pub discussion_comment: Option<Vec<OnDiscussionComment>>,
+    #[serde(default)]
+    pub label: Option<Vec<OnLabel>>,
+    #[serde(default)]
+    pub milestone: Option<Vec<OnMilestone>>,
+    #[serde(default)]
+    pub check_run: Option<Vec<OnCheckRun>>,
+    #[serde(default)]
+    pub check_suite: Option<Vec<OnCheckSuite>>,
+    #[serde(default)]
+    pub status: Option<Vec<OnStatus>>,
+    #[serde(default)]
+    pub create: Option<Vec<OnCreate>>,
+    #[serde(default)]
+    pub delete: Option<Vec<OnDelete>>,
+    #[serde(default)]
+    pub workflow_run: Option<Vec<OnWorkflowRun>>,
+    /// Organization level events: `organization`, `repository`, `team` and `membership`.
+    #[serde(default)]
+    pub organization: Option<Vec<OnOrganization>>,
+    /// All events, using the raw payload.
+    #[serde(default)]
+    pub any: Option<Vec<OnRaw>>,
+    /// Events which are not modeled, by their name, using the raw payload.
+    #[serde(flatten, deserialize_with = "deserialize_other")]
+    pub other: BTreeMap<String, Vec<OnRaw>>,
+}
+
+/// Events triggering workflows which are not modeled, and can only be used by their name.
+const UNMODELED_EVENTS: &[&str] = &[
+    "branch_protection_rule",
+    "deployment",
+    "deployment_status",
+    "fork",
+    "gollum",
+    "merge_group",
+    "page_build",
+    "project",
+    "project_card",
+    "project_column",
+    "public",
+    "pull_request_target",
+    "registry_package",
+    "watch",
+    "workflow_call",
+];
+
+/// Deserialize the events which are not modeled, rejecting the ones which are, as their rules
+/// would never run.
+fn deserialize_other<'de, D>(deserializer: D) -> Result<BTreeMap<String, Vec<OnRaw>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let other = BTreeMap::<String, Vec<OnRaw>>::deserialize(deserializer)?;
+    for name in other.keys() {
+        let key = match name.as_str() {
+            "issues" => "issue",
+            "repository" | "team" | "membership" => "organization",
+            _ => continue,
+        };
+        return Err(de::Error::custom(format!(
+            "Rules for '{}' events must be configured in 'on.{}'",
+            name, key
+        )));
+    }
+    Ok(other)
+}
+
+impl On {
+    /// The common part of all rules.
+    pub fn rules_mut(&mut self) -> Vec<&mut OnCommon> {
+        let mut result = Vec::new();
+        macro_rules! rules {
+            ($($event:ident),*) => {
+                $(result.extend(self.$event.iter_mut().flatten().map(|r| &mut r.common));)*
+            };
+        }
+        rules!(
+            issue,
+            issue_comment,
+            pull_request,
+            push,
+            pull_request_review,
+            pull_request_review_comment,
+            workflow_dispatch,
+            schedule,
+            repository_dispatch,
+            release,
+            discussion,
+            discussion_comment,
+            label,
+            milestone,
+            check_run,
+            check_suite,
+            status,
+            create,
+            delete,
+            workflow_run,
+            organization,
+            any
+        );
+        result.extend(self.other.values_mut().flatten().map(|r| &mut r.common));
+        result
+    }
+
+    /// The names of other events which GitHub doesn't trigger workflows for, like typos.
+    pub fn unknown_events(&self) -> Vec<&str> {
+        self.other
+            .keys()
+            .map(String::as_str)
+            .filter(|name| !UNMODELED_EVENTS.contains(name))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnIssue {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfIssue>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnIssueComment {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    /// Only run if the pull request changes files matching one of the globs.
+    #[serde(default)]
+    pub paths: Option<Vec<Glob>>,
+
+    pub r#if: Vec<IfIssueComment>,
+
+    /// Allow the command to target a different repository, available as `${{ repository }}`.
+    /// Steps on the issue or pull request still act on the one of the event.
+    #[serde(default)]
+    pub target: Option<Target>,
+
+    /// Mark the comment once the steps ran, showing that the command was executed.
+    #[serde(default)]
+    pub mark: Option<Mark>,
+}
+
+/// How to mark the comment of an executed command.
+///
+/// Edits of marked comments are ignored, so that marking doesn't run the command again.
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mark {
+    /// Append a footer, with the time of running the command.
+    Footer,
+    /// Strike through the command.
+    Strike,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Target {
+    /// Index of the command argument holding the target repository. If the argument is missing,
+    /// the repository of the event is the target.
+    #[serde(default)]
+    pub argument: usize,
+    /// Repositories (`owner/name`) which may be targeted.
+    pub allow: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnPullRequest {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    /// Only run if the pull request changes files matching one of the globs.
+    #[serde(default)]
+    pub paths: Option<Vec<Glob>>,
+
+    #[serde(default)]
+    pub r#if: Vec<IfPullRequest>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnPush {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfPush>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnPullRequestReview {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    /// Only run if the pull request changes files matching one of the globs.
+    #[serde(default)]
+    pub paths: Option<Vec<Glob>>,
+
+    #[serde(default)]
+    pub r#if: Vec<IfPullRequestReview>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnPullRequestReviewComment {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    /// Only run if the pull request changes files matching one of the globs.
+    #[serde(default)]
+    pub paths: Option<Vec<Glob>>,
+
+    #[serde(default)]
+    pub r#if: Vec<IfPullRequestReviewComment>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnWorkflowDispatch {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfWorkflowDispatch>,
+}
+
+/// Scheduled runs have no event information, so there are no conditions either.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnSchedule {
+    #[serde(flatten)]
+    pub common: OnCommon,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnRepositoryDispatch {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfRepositoryDispatch>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnRelease {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfRelease>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnDiscussion {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfDiscussion>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnDiscussionComment {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfDiscussionComment>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnLabel {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfLabel>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnMilestone {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfMilestone>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnCheckRun {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfCheckRun>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnCheckSuite {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfCheckSuite>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnStatus {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfStatus>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnCreate {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfRef>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnDelete {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfRef>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnWorkflowRun {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfWorkflowRun>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnRaw {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfRaw>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnOrganization {
+    #[serde(flatten)]
+    pub common: OnCommon,
+
+    #[serde(default)]
+    pub r#if: Vec<IfOrganization>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OnCommon {
+    /// Name of the rule, shown in the summary of the run.
+    #[serde(default)]
+    pub name: Option<String>,
+    /// Only run for these actions of the event, like `created`. Runs for all actions if empty.
+    #[serde(default, alias = "action")]
+    pub types: Vec<String>,
+    /// The user or team owning the rule, like `@drogue-iot/ops`, which is notified when the
+    /// rule fails repeatedly.
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(deserialize_with = "deserialize_steps")]
+    pub steps: Vec<Step>,
+}
+
+impl OnCommon {
+    /// Check if the action of the event is accepted.
+    pub fn accepts(&self, action: &str) -> bool {
+        self.types.is_empty() || self.types.iter().any(|t| t == action)
+    }
+}
+
+/// Conditions which don't depend on the kind of event.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IfCommon {
+    /// The sender of the event is a bot.
+    IsBot,
+    /// The sender of the event is not a bot.
+    NotBot,
+    /// The event happens within the time window.
+    Within(Within),
+    /// The repository of the event is one of these, like `org/repo`.
+    RepoIn(Vec<String>),
+    /// The full name of the repository of the event matches the pattern.
+    RepoMatches(Pattern),
+}
+
+impl IfCommon {
+    const NAMES: &'static [&'static str] =
+        &["is_bot", "not_bot", "within", "repo_in", "repo_matches"];
+
+    /// Check if the condition is one of these, by the name of a unit variant or the key of a
+    /// variant with a value.
+    fn is_common(value: &serde_yaml::Value) -> bool {
+        let name = match value {
+            serde_yaml::Value::String(name) => Some(name.as_str()),
+            serde_yaml::Value::Mapping(map) if map.len() == 1 => {
+                map.iter().next().and_then(|(key, _)| key.as_str())
+            }
+            _ => None,
+        };
+        name.is_some_and(|name| Self::NAMES.contains(&name))
+    }
+
+    /// Add these conditions to the expected ones of an unknown condition.
+    fn expected(err: serde_yaml::Error) -> serde_yaml::Error {
+        let message = err.to_string();
+        match message.starts_with("unknown variant") {
+            true => de::Error::custom(format!("{}, `{}`", message, Self::NAMES.join("`, `"))),
+            false => err,
+        }
+    }
+}
+
+/// Deserialize the conditions of an event, including the ones of [`IfCommon`].
+macro_rules! common_conditions {
+    ($($name:ident),* $(,)?) => {
+        $(
+            impl<'de> Deserialize<'de> for $name {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    let value = serde_yaml::Value::deserialize(deserializer)?;
+                    match IfCommon::is_common(&value) {
+                        true => serde_yaml::from_value(value).map(Self::Common),
+                        // the derived implementation, using `remote = "Self"`
+                        false => $name::deserialize(value).map_err(IfCommon::expected),
+                    }
+                    .map_err(de::Error::custom)
+                }
+            }
+        )*
+    };
+}
+
+common_conditions!(
+    IfIssueComment,
+    IfIssue,
+    IfPullRequest,
+    IfPullRequestReview,
+    IfPullRequestReviewComment,
+    IfWorkflowDispatch,
+    IfRepositoryDispatch,
+    IfDiscussion,
+    IfDiscussionComment,
+    IfLabel,
+    IfMilestone,
+    IfCheckRun,
+    IfCheckSuite,
+    IfStatus,
+    IfRef,
+    IfWorkflowRun,
+    IfRelease,
+    IfPush,
+);
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfIssueComment {
+    Not(Box<IfIssueComment>),
+    And(Vec<IfIssueComment>),
+    Or(Vec<IfIssueComment>),
+    /// Action of the event, e.g. `created` or `edited`.
+    ActionIs(Vec<String>),
+    IsPr,
+    UserIs(Vec<AuthorAssociation>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    Command(String),
+    /// A line added by the pull request matches the pattern.
+    AddedLinesMatch(Pattern),
+    /// The issue or pull request has at least one of the labels.
+    HasLabel(Vec<String>),
+    /// The issue or pull request has a label matching the pattern.
+    LabelMatches(Pattern),
+    /// The comment body matches the pattern.
+    BodyMatches(Pattern),
+    /// The title of the issue or pull request matches the pattern.
+    TitleMatches(Pattern),
+    /// The body of the issue or pull request matches the pattern.
+    IssueBodyMatches(Pattern),
+    /// The comment is on a draft pull request.
+    IsDraft,
+    /// The comment is on a pull request targeting one of the branches.
+    BaseBranchIs(Vec<String>),
+    /// The comment is on a pull request from a branch matching the pattern.
+    HeadBranchMatches(Pattern),
+    /// The comment is on a pull request changing a file matching one of the globs.
+    FilesMatch(Vec<Glob>),
+    /// The issue or pull request is open.
+    IsOpen,
+    /// The issue or pull request is closed.
+    IsClosed,
+    /// The conversation of the issue or pull request is locked.
+    IsLocked,
+    /// A JSON path into the event payload, e.g. `comment.performed_via_github_app`, which selects
+    /// at least one truthy value.
+    Expr(String),
+    /// The comment is on a pull request with at least this number of distinct approvals.
+    ApprovalsAtLeast(usize),
+    /// The comment is on a pull request which can be merged, without conflicts or blocking checks.
+    IsMergeable,
+    /// The checks of the head commit of the pull request passed, all of them for an empty list.
+    ChecksPassed(Vec<String>),
+    /// The comment has at least this number of reactions of a kind.
+    ReactionsAtLeast(ReactionCount),
+    /// The comment is on a pull request which is smaller than the limits.
+    PrSmallerThan(SizeLimit),
+    /// The issue is assigned to at least one of the users.
+    AssignedTo(Vec<String>),
+    /// The issue is assigned to anyone.
+    HasAssignee,
+    /// The issue belongs to one of the milestones, by title.
+    MilestoneIs(Vec<String>),
+    /// The comment was not edited, so commands can't be issued by editing old comments.
+    NotEdited,
+    /// All conditions of the named set of `conditions`.
+    Ref(String),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfIssue {
+    Not(Box<IfIssue>),
+    And(Vec<IfIssue>),
+    Or(Vec<IfIssue>),
+    /// Action of the event, e.g. `opened`, `edited`, `labeled` or `closed`.
+    ActionIs(Vec<String>),
+    /// The issue has at least one of the labels.
+    HasLabel(Vec<String>),
+    UserIs(Vec<AuthorAssociation>),
+    TitleMatches(Pattern),
+    BodyMatches(Pattern),
+    IsOpen,
+    IsClosed,
+    IsLocked,
+    /// The issue has at least this number of reactions of a kind.
+    ReactionsAtLeast(ReactionCount),
+    /// The issue is assigned to at least one of the users.
+    AssignedTo(Vec<String>),
+    /// The issue is assigned to anyone.
+    HasAssignee,
+    /// The issue belongs to one of the milestones, by title.
+    MilestoneIs(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfPullRequest {
+    Not(Box<IfPullRequest>),
+    And(Vec<IfPullRequest>),
+    Or(Vec<IfPullRequest>),
+    /// Action of the event, e.g. `opened`, `synchronize` or `labeled`.
+    ActionIs(Vec<String>),
+    IsDraft,
+    UserIs(Vec<AuthorAssociation>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    /// A line added by the pull request matches the pattern.
+    AddedLinesMatch(Pattern),
+    /// The pull request has at least this number of distinct approvals.
+    ApprovalsAtLeast(usize),
+    /// The pull request can be merged, without conflicts or blocking checks.
+    IsMergeable,
+    /// The checks of the head commit passed, all of them for an empty list.
+    ChecksPassed(Vec<String>),
+    /// The pull request has at least this number of reactions of a kind.
+    ReactionsAtLeast(ReactionCount),
+    /// The pull request is smaller than the limits.
+    PrSmallerThan(SizeLimit),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfPullRequestReview {
+    Not(Box<IfPullRequestReview>),
+    And(Vec<IfPullRequestReview>),
+    Or(Vec<IfPullRequestReview>),
+    /// Action of the event, e.g. `submitted` or `dismissed`.
+    ActionIs(Vec<String>),
+    ReviewState(Vec<ReviewState>),
+    UserIs(Vec<AuthorAssociation>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    /// The review body starts with the command.
+    Command(String),
+    /// The pull request has at least this number of distinct approvals.
+    ApprovalsAtLeast(usize),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfPullRequestReviewComment {
+    Not(Box<IfPullRequestReviewComment>),
+    And(Vec<IfPullRequestReviewComment>),
+    Or(Vec<IfPullRequestReviewComment>),
+    /// Action of the event, e.g. `created` or `edited`.
+    ActionIs(Vec<String>),
+    UserIs(Vec<AuthorAssociation>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    Command(String),
+    /// The comment was not edited, so commands can't be issued by editing old comments.
+    NotEdited,
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfWorkflowDispatch {
+    Not(Box<IfWorkflowDispatch>),
+    And(Vec<IfWorkflowDispatch>),
+    Or(Vec<IfWorkflowDispatch>),
+    /// All the inputs have the provided values.
+    InputIs(BTreeMap<String, serde_json::Value>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfRepositoryDispatch {
+    Not(Box<IfRepositoryDispatch>),
+    And(Vec<IfRepositoryDispatch>),
+    Or(Vec<IfRepositoryDispatch>),
+    EventTypeIs(Vec<String>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfDiscussion {
+    Not(Box<IfDiscussion>),
+    And(Vec<IfDiscussion>),
+    Or(Vec<IfDiscussion>),
+    /// Action of the event, e.g. `created`, `answered` or `category_changed`.
+    ActionIs(Vec<String>),
+    /// The slug of the discussion category, e.g. `q-a`.
+    CategoryIs(Vec<String>),
+    UserIs(Vec<AuthorAssociation>),
+    TitleMatches(Pattern),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfDiscussionComment {
+    Not(Box<IfDiscussionComment>),
+    And(Vec<IfDiscussionComment>),
+    Or(Vec<IfDiscussionComment>),
+    ActionIs(Vec<String>),
+    CategoryIs(Vec<String>),
+    UserIs(Vec<AuthorAssociation>),
+    UserIn(Vec<String>),
+    /// The user is an active member of the team, e.g. `org/team-slug`.
+    UserInTeam(String),
+    /// The user is a member of the organization.
+    UserInOrg(String),
+    /// The user has at least one of the permissions on the repository, e.g. `write` or `maintain`.
+    UserHasPermission(Vec<String>),
+    Command(String),
+    /// The comment was not edited, so commands can't be issued by editing old comments.
+    NotEdited,
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfLabel {
+    Not(Box<IfLabel>),
+    And(Vec<IfLabel>),
+    Or(Vec<IfLabel>),
+    /// Action of the event, e.g. `created`, `edited` or `deleted`.
+    ActionIs(Vec<String>),
+    NameMatches(Pattern),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfMilestone {
+    Not(Box<IfMilestone>),
+    And(Vec<IfMilestone>),
+    Or(Vec<IfMilestone>),
+    /// Action of the event, e.g. `created`, `closed`, `opened`, `edited` or `deleted`.
+    ActionIs(Vec<String>),
+    TitleMatches(Pattern),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfCheckRun {
+    Not(Box<IfCheckRun>),
+    And(Vec<IfCheckRun>),
+    Or(Vec<IfCheckRun>),
+    /// Action of the event, e.g. `created`, `completed` or `rerequested`.
+    ActionIs(Vec<String>),
+    NameMatches(Pattern),
+    /// Conclusion of the check run, e.g. `failure` or `timed_out`.
+    ConclusionIs(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfCheckSuite {
+    Not(Box<IfCheckSuite>),
+    And(Vec<IfCheckSuite>),
+    Or(Vec<IfCheckSuite>),
+    ActionIs(Vec<String>),
+    ConclusionIs(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfStatus {
+    Not(Box<IfStatus>),
+    And(Vec<IfStatus>),
+    Or(Vec<IfStatus>),
+    /// The name of the status.
+    ContextMatches(Pattern),
+    /// State of the status, e.g. `failure` or `error`.
+    StateIs(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+/// Conditions for the `create` and `delete` events.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfRef {
+    Not(Box<IfRef>),
+    And(Vec<IfRef>),
+    Or(Vec<IfRef>),
+    RefTypeIs(RefType),
+    /// The name of the branch or tag matches.
+    RefMatches(Pattern),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfWorkflowRun {
+    Not(Box<IfWorkflowRun>),
+    And(Vec<IfWorkflowRun>),
+    Or(Vec<IfWorkflowRun>),
+    /// Action of the event, e.g. `requested`, `in_progress` or `completed`.
+    ActionIs(Vec<String>),
+    /// The name of the workflow.
+    WorkflowIs(Vec<String>),
+    ConclusionIs(Vec<String>),
+    /// The head branch of the workflow run.
+    BranchIs(Vec<String>),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+/// Conditions on the raw event payload.
+///
+/// Paths are JSON paths into the payload, like `deployment.environment`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IfRaw {
+    Not(Box<IfRaw>),
+    And(Vec<IfRaw>),
+    Or(Vec<IfRaw>),
+    EventIs(Vec<String>),
+    ActionIs(Vec<String>),
+    /// The path selects a value which is not `null`.
+    Exists(String),
+    /// The path selects a value which is equal to the expected value.
+    Equals {
+        path: String,
+        value: serde_json::Value,
+    },
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfRelease {
+    Not(Box<IfRelease>),
+    And(Vec<IfRelease>),
+    Or(Vec<IfRelease>),
+    /// Action of the event, e.g. `published`, `released` or `prereleased`.
+    ActionIs(Vec<String>),
+    IsDraft,
+    IsPrerelease,
+    TagMatches(Pattern),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case", remote = "Self")]
+pub enum IfPush {
+    Not(Box<IfPush>),
+    And(Vec<IfPush>),
+    Or(Vec<IfPush>),
+    /// A branch with one of the names was pushed.
+    BranchIs(Vec<String>),
+    /// A tag matching the pattern was pushed.
+    TagMatches(Pattern),
+    /// Conditions which are available for all kinds of events.
+    #[serde(skip)]
+    Common(IfCommon),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IfOrganization {
+    Not(Box<IfOrganization>),
+    And(Vec<IfOrganization>),
+    Or(Vec<IfOrganization>),
+    /// Name of the event, e.g. `repository` or `team`.
+    EventIs(Vec<String>),
+    /// Action of the event, e.g. `created` or `member_added`.
+    ActionIs(Vec<String>),
+}
+
+/// A minimum number of reactions of a kind.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ReactionCount {
+    /// The reaction, e.g. `+1`, `heart` or `rocket`.
+    pub content: String,
+    pub count: usize,
+    /// What to count the reactions on, for `issue_comment` rules.
+    #[serde(default)]
+    pub on: ReactionsOn,
+}
+
+/// The subject of an `issue_comment` event which has reactions.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReactionsOn {
+    #[default]
+    Comment,
+    Issue,
+}
+
+/// Limits for the size of a pull request, which must be smaller. No limit if missing.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct SizeLimit {
+    /// The number of changed files.
+    #[serde(default)]
+    pub files: Option<u64>,
+    /// The number of added and deleted lines.
+    #[serde(default)]
+    pub lines: Option<u64>,
+}
+
+/// A time window, e.g. the business hours.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Within {
+    /// Days of the week, like `mon` or `mon-fri`. Any day if empty.
+    #[serde(default)]
+    pub days: Vec<Days>,
+    /// The time of the day, like `09:00-17:00`. Any time if missing.
+    #[serde(default)]
+    pub hours: Option<Hours>,
+    /// The time zone, like `Europe/Berlin`. UTC if missing.
+    #[serde(default)]
+    pub tz: Option<String>,
+}
+
+impl Within {
+    /// Check if the day of the week (Monday being 1) and the minute of the day are in the window.
+    pub fn contains(&self, day: u32, minute: u32) -> bool {
+        (self.days.is_empty() || self.days.iter().any(|days| days.contains(day)))
+            && self
+                .hours
+                .as_ref()
+                .is_none_or(|hours| hours.contains(minute))
+    }
+}
+
+/// A range of days of the week, Monday being 1. The range may wrap around, like `fri-mon`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Days(pub u32, pub u32);
+
+impl Days {
+    pub fn contains(&self, day: u32) -> bool {
+        match self.0 <= self.1 {
+            true => (self.0..=self.1).contains(&day),
+            false => day >= self.0 || day <= self.1,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Days {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        const DAYS: [&str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+        let days = String::deserialize(deserializer)?;
+        let day = |day: &str| {
+            DAYS.iter()
+                .position(|d| d.eq_ignore_ascii_case(day.trim()))
+                .map(|i| i as u32 + 1)
+                .ok_or_else(|| de::Error::custom(format!("Invalid day of the week: {}", day)))
+        };
+        match days.split_once('-') {
+            Some((start, end)) => Ok(Days(day(start)?, day(end)?)),
+            None => day(&days).map(|day| Days(day, day)),
+        }
+    }
+}
+
+/// A range of minutes of the day, excluding the end. The range may wrap around midnight.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Hours(pub u32, pub u32);
+
+impl Hours {
+    pub fn contains(&self, minute: u32) -> bool {
+        match self.0 <= self.1 {
+            true => (self.0..self.1).contains(&minute),
+            false => minute >= self.0 || minute < self.1,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Hours {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let hours = String::deserialize(deserializer)?;
+        let time = |time: &str| {
+            time.trim()
+                .split_once(':')
+                .and_then(|(h, m)| Some((h.parse::<u32>().ok()?, m.parse::<u32>().ok()?)))
+                .filter(|(h, m)| (*h < 24 || (*h == 24 && *m == 0)) && *m < 60)
+                .map(|(h, m)| h * 60 + m)
+                .ok_or_else(|| de::Error::custom(format!("Invalid time, expected HH:MM: {}", time)))
+        };
+        match hours.split_once('-') {
+            Some((start, end)) => Ok(Hours(time(start)?, time(end)?)),
+            None => Err(de::Error::custom(format!(
+                "Invalid hours, expected HH:MM-HH:MM: {}",
+                hours
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    Run(String),
+    Bootstrap(Bootstrap),
+    DependencyReview(DependencyReview),
+    CheckLinks(CheckLinks),
+    SpellCheck(SpellCheck),
+    SuggestChange(SuggestChange),
+    ApplySuggestions(ApplySuggestions),
+    ResolveThread(ResolveThread),
+    /// Add labels to the issue or pull request, the labels may contain expressions.
+    AddLabels(Vec<String>),
+    /// Remove labels from the issue or pull request, the labels may contain expressions.
+    RemoveLabels(Vec<String>),
+    /// Comment on the issue or pull request, the body may contain expressions.
+    Comment(Comment),
+    Close(Close),
+    /// Reopen the issue or pull request.
+    Reopen,
+    /// Assign users to the issue or pull request, the users may contain expressions.
+    Assign(Vec<String>),
+    /// Unassign users from the issue or pull request, the users may contain expressions.
+    Unassign(Vec<String>),
+    RequestReview(RequestReview),
+    Merge(Merge),
+    /// Approve the pull request.
+    Approve(Approve),
+    DismissReviews(DismissReviews),
+    SearchIssues(SearchIssues),
+    /// React to the comment which triggered the run, like `rocket` or `+1`.
+    React(String),
+    /// Set the milestone of the issue or pull request, by title. Clears the milestone if the
+    /// title is empty.
+    SetMilestone(String),
+    Lock(Lock),
+    /// Unlock the conversation of the issue or pull request.
+    Unlock,
+    CreateIssue(CreateIssue),
+    DispatchWorkflow(DispatchWorkflow),
+    RepositoryDispatch(RepositoryDispatch),
+    #[serde(skip_deserializing)]
+    Use(Use),
+}
+
+impl Step {
+    /// The name of the step, as used in the configuration.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Run(_) => "run",
+            Self::Bootstrap(_) => "bootstrap",
+            Self::DependencyReview(_) => "dependency_review",
+            Self::CheckLinks(_) => "check_links",
+            Self::SpellCheck(_) => "spell_check",
+            Self::SuggestChange(_) => "suggest_change",
+            Self::ApplySuggestions(_) => "apply_suggestions",
+            Self::ResolveThread(_) => "resolve_thread",
+            Self::AddLabels(_) => "add_labels",
+            Self::RemoveLabels(_) => "remove_labels",
+            Self::Comment(_) => "comment",
+            Self::Close(_) => "close",
+            Self::Reopen => "reopen",
+            Self::Assign(_) => "assign",
+            Self::Unassign(_) => "unassign",
+            Self::RequestReview(_) => "request_review",
+            Self::Merge(_) => "merge",
+            Self::Approve(_) => "approve",
+            Self::DismissReviews(_) => "dismiss_reviews",
+            Self::SearchIssues(_) => "search_issues",
+            Self::React(_) => "react",
+            Self::SetMilestone(_) => "set_milestone",
+            Self::Lock(_) => "lock",
+            Self::Unlock => "unlock",
+            Self::CreateIssue(_) => "create_issue",
+            Self::DispatchWorkflow(_) => "dispatch_workflow",
+            Self::RepositoryDispatch(_) => "repository_dispatch",
+            Self::Use(_) => "use",
+        }
+    }
+}
+
+/// A comment, either only the body, or with a key for posting it only once.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(untagged)]
+pub enum Comment {
+    Body(String),
+    Keyed {
+        body: String,
+        /// Skip the comment if one with the same key exists, may contain expressions.
+        key: String,
+    },
+}
+
+impl Comment {
+    pub fn body(&self) -> &str {
+        match self {
+            Self::Body(body) | Self::Keyed { body, .. } => body,
+        }
+    }
+
+    pub fn key(&self) -> Option<&str> {
+        match self {
+            Self::Body(_) => None,
+            Self::Keyed { key, .. } => Some(key),
+        }
+    }
+}
+
+/// Bootstrap a repository with files from a template repository, by opening a pull request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Bootstrap {
+    /// The repository to copy the files from (`owner/name`).
+    pub template: String,
+    /// The repository to bootstrap, defaults to the target of a command, or the repository of
+    /// the event.
+    #[serde(default)]
+    pub repository: Option<String>,
+    /// Files or directories to copy. Missing entries in the template are skipped.
+    #[serde(default = "default_bootstrap_files")]
+    pub files: Vec<String>,
+    #[serde(default = "default_bootstrap_branch")]
+    pub branch: String,
+    #[serde(default = "default_bootstrap_title")]
+    pub title: String,
+}
+
+/// Review the dependencies added by a pull request, using the dependency review API.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct DependencyReview {
+    /// Minimum severity of vulnerabilities to report.
+    #[serde(default)]
+    pub min_severity: Severity,
+    /// Label to add when vulnerable dependencies get added.
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Post the summary as a comment.
+    #[serde(default = "default_true")]
+    pub comment: bool,
+}
+
+/// Check the links added to markdown files by a pull request, and report broken ones as a check run.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CheckLinks {
+    #[serde(default = "default_check_links_name")]
+    pub name: String,
+    /// Files to extract links from.
+    #[serde(default = "default_check_links_paths")]
+    pub paths: Vec<Glob>,
+    /// Links matching any of the patterns are not checked.
+    #[serde(default)]
+    pub ignore: Vec<Pattern>,
+    /// Number of links to check in parallel.
+    #[serde(default = "default_check_links_concurrency")]
+    pub concurrency: usize,
+    /// Number of retries for failed requests.
+    #[serde(default = "default_check_links_retries")]
+    pub retries: u32,
+    /// Timeout of a single request, in seconds.
+    #[serde(default = "default_check_links_timeout")]
+    pub timeout: u64,
+}
+
+/// Check the lines added by a pull request for typos, and suggest corrections as review comments.
+///
+/// This uses [typos](https://github.com/crate-ci/typos), which must be installed.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct SpellCheck {
+    /// The `typos` executable.
+    #[serde(default = "default_spell_check_command")]
+    pub command: String,
+    /// Files to check, all files if empty.
+    #[serde(default)]
+    pub paths: Vec<Glob>,
+}
+
+/// Suggest a change to the pull request, using a review comment with a suggestion.
+///
+/// The path, replacement and message may contain expressions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct SuggestChange {
+    pub path: String,
+    /// The (last) line to replace, in the new version of the file.
+    pub line: u64,
+    /// The first line to replace, when replacing multiple lines.
+    #[serde(default)]
+    pub start_line: Option<u64>,
+    pub replacement: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+/// Commit the open suggestions of a pull request, and resolve their review threads.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ApplySuggestions {
+    /// Only apply suggestions of these users, use an empty list to apply all suggestions.
+    #[serde(default = "default_apply_suggestions_authors")]
+    pub authors: Vec<String>,
+    /// The commit message.
+    #[serde(default = "default_apply_suggestions_message")]
+    pub message: String,
+}
+
+/// Resolve the open review threads of a pull request which match.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ResolveThread {
+    #[serde(default)]
+    pub r#match: ThreadMatch,
+}
+
+/// Criteria for review threads, all of which must match.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct ThreadMatch {
+    /// Users who started the thread, any user if empty.
+    #[serde(default)]
+    pub authors: Vec<String>,
+    /// Only threads which are (or are not) outdated.
+    #[serde(default)]
+    pub outdated: Option<bool>,
+    #[serde(default)]
+    pub body_matches: Option<Pattern>,
+    /// Files the thread belongs to, any file if empty.
+    #[serde(default)]
+    pub paths: Vec<Glob>,
+}
+
+/// Close the issue or pull request.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Close {
+    /// The reason for closing an issue.
+    #[serde(default)]
+    pub reason: Option<CloseReason>,
+}
+
+/// Create an issue, all fields may contain expressions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CreateIssue {
+    pub title: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default)]
+    pub assignees: Vec<String>,
+    /// The repository, like `other` or `ctron/other`, defaults to the repository of the event.
+    #[serde(default)]
+    pub repo: Option<String>,
+}
+
+/// Trigger a workflow of the repository, which must have a `workflow_dispatch` trigger. All
+/// fields may contain expressions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct DispatchWorkflow {
+    /// The file name of the workflow, like `e2e.yaml`, or its ID.
+    pub workflow: String,
+    /// The branch or tag to run the workflow on, defaults to the default branch.
+    #[serde(default)]
+    pub r#ref: Option<String>,
+    #[serde(default)]
+    pub inputs: BTreeMap<String, String>,
+}
+
+/// Send a `repository_dispatch` event to a repository. All fields, and the strings of the
+/// payload, may contain expressions.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RepositoryDispatch {
+    /// The repository, like `website` or `ctron/website`.
+    pub repo: String,
+    pub event_type: String,
+    #[serde(default)]
+    pub client_payload: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Lock the conversation of the issue or pull request.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Lock {
+    /// The reason, one of `off-topic`, `too heated`, `resolved` or `spam`. May contain
+    /// expressions, like `${{ command.args[0] }}`.
+    #[serde(default)]
+    pub reason: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-pub struct On {
+/// Request reviews of the pull request, users and teams may contain expressions.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct RequestReview {
     #[serde(default)]
-    pub issue: Option<Vec<OnIssue>>,
+    pub users: Vec<String>,
+    /// The slugs of teams of the organization, like `reviewers` or `@drogue-iot/reviewers`.
     #[serde(default)]
-    pub issue_comment: Option<Vec<OnIssueComment>>,
+    pub teams: Vec<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-pub struct OnIssue {
-    #[serde(flatten)]
-    pub common: OnCommon,
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Approve {
+    /// The body of the review, may contain expressions.
+    #[serde(default)]
+    pub body: Option<String>,
 }
 
+/// Dismiss the reviews which approve or request changes.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-pub struct OnIssueComment {
-    #[serde(flatten)]
-    pub common: OnCommon,
+pub struct DismissReviews {
+    /// The reason for dismissing, may contain expressions.
+    pub message: String,
+    /// Only dismiss the reviews of these users, may contain expressions. All reviews if empty.
+    #[serde(default)]
+    pub users: Vec<String>,
+}
 
-    pub r#if: Vec<IfIssueComment>,
+/// Merge the pull request, if it can be merged.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Merge {
+    #[serde(default)]
+    pub method: MergeMethod,
+    /// The title of the commit, may contain expressions. Defaults to the one of GitHub.
+    #[serde(default)]
+    pub commit_title: Option<String>,
+    /// The message of the commit, may contain expressions. Defaults to the one of GitHub.
+    #[serde(default)]
+    pub commit_message: Option<String>,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-pub struct OnCommon {
-    pub steps: Vec<Step>,
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeMethod {
+    #[default]
+    Merge,
+    Squash,
+    Rebase,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+impl MergeMethod {
+    /// The `merge_method` of the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Merge => "merge",
+            Self::Squash => "squash",
+            Self::Rebase => "rebase",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
-pub enum IfIssueComment {
-    Not(Box<IfIssueComment>),
-    And(Vec<IfIssueComment>),
-    Or(Vec<IfIssueComment>),
-    IsPr,
-    UserIs(Vec<AuthorAssociation>),
-    UserIn(Vec<String>),
-    Command(String),
+pub enum CloseReason {
+    Completed,
+    NotPlanned,
 }
 
+impl CloseReason {
+    /// The `state_reason` of the API.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Completed => "completed",
+            Self::NotPlanned => "not_planned",
+        }
+    }
+}
+
+/// Search issues and pull requests, and run steps for each result.
+///
+/// The result is available as `item` to the steps, and is the target of issue related steps.
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct SearchIssues {
+    /// The search query, which may contain expressions.
+    pub query: String,
+    /// The maximum number of results.
+    #[serde(default = "default_search_issues_limit")]
+    pub limit: usize,
+    /// Seconds to wait between processing results.
+    #[serde(default = "default_search_issues_delay")]
+    pub delay: u64,
+    #[serde(deserialize_with = "deserialize_steps")]
+    pub for_each: Vec<Step>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
 #[serde(rename_all = "snake_case")]
-pub enum Step {
-    Run(String),
+pub enum Severity {
+    #[default]
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_search_issues_limit() -> usize {
+    100
+}
+
+fn default_search_issues_delay() -> u64 {
+    1
+}
+
+fn default_apply_suggestions_authors() -> Vec<String> {
+    vec!["github-actions[bot]".into()]
+}
+
+fn default_apply_suggestions_message() -> String {
+    "Apply suggestions from code review".into()
+}
+
+fn default_spell_check_command() -> String {
+    "typos".into()
+}
+
+fn default_check_links_name() -> String {
+    "rodbot/links".into()
+}
+
+fn default_check_links_paths() -> Vec<Glob> {
+    vec![Glob::new("*.md").unwrap()]
+}
+
+fn default_check_links_concurrency() -> usize {
+    4
+}
+
+fn default_check_links_retries() -> u32 {
+    2
+}
+
+fn default_check_links_timeout() -> u64 {
+    10
+}
+
+fn default_bootstrap_files() -> Vec<String> {
+    vec![
+        "rodbot.yaml".into(),
+        ".github/CODEOWNERS".into(),
+        ".github/ISSUE_TEMPLATE".into(),
+    ]
+}
+
+fn default_bootstrap_branch() -> String {
+    "rodbot/bootstrap".into()
+}
+
+fn default_bootstrap_title() -> String {
+    "Add standard repository files".into()
+}
+
+/// A regular expression, compiled when loading the configuration.
+#[derive(Clone, Debug)]
+pub struct Pattern(pub Regex);
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl<'de> Deserialize<'de> for Pattern {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map(Pattern).map_err(de::Error::custom)
+    }
+}
+
+/// A glob pattern for paths, compiled when loading the configuration.
+///
+/// `**` matches across directories, `*` and `?` only within a path segment. A pattern without
+/// a `/` matches the file name in any directory.
+#[derive(Clone, Debug)]
+pub struct Glob(pub Regex);
+
+impl Glob {
+    pub fn new(glob: &str) -> Result<Self, regex::Error> {
+        Regex::new(&glob_to_regex(glob)).map(Glob)
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        self.0.is_match(path)
+    }
+}
+
+fn glob_to_regex(glob: &str) -> String {
+    let mut result = String::from(if glob.contains('/') { "^" } else { "(^|/)" });
+
+    let mut alternatives = 0;
+    let mut chars = glob.trim_start_matches('/').chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    result.push_str("(.*/)?");
+                } else {
+                    result.push_str(".*");
+                }
+            }
+            '*' => result.push_str("[^/]*"),
+            '?' => result.push_str("[^/]"),
+            '{' => {
+                alternatives += 1;
+                result.push('(');
+            }
+            '}' if alternatives > 0 => {
+                alternatives -= 1;
+                result.push(')');
+            }
+            ',' if alternatives > 0 => result.push('|'),
+            '[' | ']' => result.push(c),
+            c => result.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    result.push('$');
+    result
+}
+
+impl PartialEq for Glob {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for Glob {}
+
+impl<'de> Deserialize<'de> for Glob {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let glob = String::deserialize(deserializer)?;
+        Glob::new(&glob).map_err(de::Error::custom)
+    }
 }
 
 #[cfg(test)]
@@ -66,6 +1904,10 @@ on:
         - user_is: ["OWNER", "MEMBER"]
         - is_pr
         - user_in: ["foo", "bar"]
+        - or:
+            - user_in_team: drogue-iot/maintainers
+            - user_in_org: drogue-iot
+            - user_has_permission: ["write", "admin"]
       steps:
         - run: |
             echo "${{ github.event.issue.number }}"
@@ -77,6 +1919,9 @@ on:
             cfg.on.issue_comment.unwrap()[0],
             OnIssueComment {
                 common: OnCommon {
+                    name: None,
+                    types: vec![],
+                    owner: None,
                     steps: vec![Run("echo \"${{ github.event.issue.number }}\"\n".into())]
                 },
                 r#if: vec![
@@ -86,9 +1931,408 @@ on:
                         AuthorAssociation::Member,
                     ]),
                     IfIssueComment::IsPr,
-                    IfIssueComment::UserIn(vec!["foo".into(), "bar".into()])
+                    IfIssueComment::UserIn(vec!["foo".into(), "bar".into()]),
+                    IfIssueComment::Or(vec![
+                        IfIssueComment::UserInTeam("drogue-iot/maintainers".into()),
+                        IfIssueComment::UserInOrg("drogue-iot".into()),
+                        IfIssueComment::UserHasPermission(vec!["write".into(), "admin".into()]),
+                    ]),
                 ],
+                target: None,
+                paths: None,
+                mark: None,
             }
         )
     }
+
+    #[test]
+    fn test_parse_issue() {
+        let yaml = r#"---
+on:
+  issue:
+    - types: ["opened", "edited"]
+      if:
+        - action_is: ["opened"]
+        - title_matches: "^\\[RFC\\]"
+      steps:
+        - run: echo "RFC"
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+        let issue = &cfg.on.issue.unwrap()[0];
+
+        assert!(issue.common.accepts("edited"));
+        assert!(!issue.common.accepts("closed"));
+
+        match &issue.r#if[1] {
+            IfIssue::TitleMatches(pattern) => assert!(pattern.is_match("[RFC] Foo")),
+            other => panic!("Unexpected condition: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_other() {
+        let yaml = r#"---
+on:
+  deployment:
+    - if:
+        - equals:
+            path: deployment.environment
+            value: production
+      steps:
+        - run: echo "Deploying"
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+
+        assert_eq!(
+            cfg.on.other["deployment"][0].r#if,
+            vec![IfRaw::Equals {
+                path: "deployment.environment".into(),
+                value: "production".into(),
+            }]
+        );
+        assert!(cfg.on.any.is_none());
+        assert!(cfg.on.unknown_events().is_empty());
+    }
+
+    #[test]
+    fn test_parse_other_modeled() {
+        let yaml = r#"---
+on:
+  issues:
+    - steps:
+        - run: echo "Never runs"
+"#;
+
+        let err = serde_yaml::from_str::<Config>(yaml).unwrap_err();
+        assert!(err.to_string().contains("'on.issue'"), "{}", err);
+
+        let yaml = r#"---
+on:
+  team:
+    - steps: []
+"#;
+
+        let err = serde_yaml::from_str::<Config>(yaml).unwrap_err();
+        assert!(err.to_string().contains("'on.organization'"), "{}", err);
+
+        let yaml = r#"---
+on:
+  pull_requests:
+    - steps: []
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+        assert_eq!(cfg.on.unknown_events(), vec!["pull_requests"]);
+    }
+
+    #[test]
+    fn test_parse_comment() {
+        let yaml = r#"---
+on:
+  issue:
+    - steps:
+        - comment: "Thanks!"
+        - comment:
+            body: "Welcome, please sign the CLA"
+            key: cla
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+
+        assert_eq!(
+            cfg.on.issue.unwrap()[0].common.steps,
+            vec![
+                Step::Comment(Comment::Body("Thanks!".into())),
+                Step::Comment(Comment::Keyed {
+                    body: "Welcome, please sign the CLA".into(),
+                    key: "cla".into(),
+                }),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_close() {
+        let yaml = r#"---
+on:
+  issue_comment:
+    - if:
+        - command: close
+      steps:
+        - close: {}
+        - close:
+            reason: not_planned
+        - reopen
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+
+        assert_eq!(
+            cfg.on.issue_comment.unwrap()[0].common.steps,
+            vec![
+                Step::Close(Close { reason: None }),
+                Step::Close(Close {
+                    reason: Some(CloseReason::NotPlanned)
+                }),
+                Step::Reopen,
+            ]
+        );
+
+        let yaml = "on:\n  issue:\n    - steps:\n        - close:\n            reason: done\n";
+        assert!(serde_yaml::from_str::<Config>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_parse_reactions() {
+        let yaml = r#"---
+on:
+  issue:
+    - if:
+        - reactions_at_least:
+            content: "+1"
+            count: 3
+      steps: []
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+
+        assert_eq!(
+            cfg.on.issue.unwrap()[0].r#if,
+            vec![IfIssue::ReactionsAtLeast(ReactionCount {
+                content: "+1".into(),
+                count: 3,
+                on: ReactionsOn::Comment,
+            })]
+        );
+    }
+
+    #[test]
+    fn test_parse_common() {
+        let yaml = r#"---
+on:
+  push:
+    - if:
+        - not_bot
+        - not:
+            repo_in: ["ctron/rodbot"]
+        - branch_is: ["main"]
+      steps: []
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+
+        assert_eq!(
+            cfg.on.push.unwrap()[0].r#if,
+            vec![
+                IfPush::Common(IfCommon::NotBot),
+                IfPush::Not(Box::new(IfPush::Common(IfCommon::RepoIn(vec![
+                    "ctron/rodbot".into()
+                ])))),
+                IfPush::BranchIs(vec!["main".into()]),
+            ]
+        );
+
+        let yaml = r#"---
+on:
+  push:
+    - if:
+        - is_bott
+      steps: []
+"#;
+
+        let err = serde_yaml::from_str::<Config>(yaml).unwrap_err();
+        assert!(err.to_string().contains("`branch_is`"), "{}", err);
+        assert!(err.to_string().contains("`is_bot`"), "{}", err);
+    }
+
+    #[test]
+    fn test_parse_within() {
+        let yaml = r#"---
+on:
+  issue_comment:
+    - if:
+        - within:
+            days: [mon-fri, sun]
+            hours: "09:00-17:00"
+            tz: Europe/Berlin
+      steps: []
+"#;
+
+        let cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+        let within = Within {
+            days: vec![Days(1, 5), Days(7, 7)],
+            hours: Some(Hours(9 * 60, 17 * 60)),
+            tz: Some("Europe/Berlin".into()),
+        };
+
+        assert_eq!(
+            cfg.on.issue_comment.unwrap()[0].r#if,
+            vec![IfIssueComment::Common(IfCommon::Within(within.clone()))]
+        );
+
+        assert!(within.contains(1, 9 * 60));
+        assert!(within.contains(7, 12 * 60));
+        assert!(!within.contains(6, 12 * 60));
+        assert!(!within.contains(3, 17 * 60));
+
+        let night = Hours(22 * 60, 6 * 60);
+        assert!(night.contains(23 * 60) && night.contains(0) && !night.contains(12 * 60));
+        assert!(Days(5, 1).contains(7) && !Days(5, 1).contains(3));
+    }
+
+    #[test]
+    fn test_parse_invalid_within() {
+        for within in [
+            "days: [someday]",
+            "hours: \"9-17\"",
+            "hours: \"09:00-25:00\"",
+        ] {
+            let yaml = format!(
+                "on:\n  issue_comment:\n    - if:\n        - within: {{ {} }}\n      steps: []\n",
+                within
+            );
+            assert!(serde_yaml::from_str::<Config>(&yaml).is_err(), "{}", within);
+        }
+    }
+
+    #[test]
+    fn test_resolve_conditions() {
+        let yaml = r#"---
+conditions:
+  maintainer_pr:
+    - is_pr
+    - ref: maintainer
+  maintainer:
+    - user_is: [OWNER, MEMBER]
+on:
+  issue_comment:
+    - if:
+        - ref: maintainer_pr
+        - command: test
+      steps: []
+"#;
+
+        let mut cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+        cfg.resolve_conditions().expect("Must resolve");
+
+        assert_eq!(
+            cfg.on.issue_comment.unwrap()[0].r#if,
+            vec![
+                IfIssueComment::And(vec![
+                    IfIssueComment::IsPr,
+                    IfIssueComment::And(vec![IfIssueComment::UserIs(vec![
+                        AuthorAssociation::Owner,
+                        AuthorAssociation::Member
+                    ])]),
+                ]),
+                IfIssueComment::Command("test".into()),
+            ]
+        );
+
+        let mut cfg: Config =
+            serde_yaml::from_str(&yaml.replace("ref: maintainer_pr", "ref: other"))
+                .expect("Must parse");
+        assert!(cfg.resolve_conditions().is_err());
+
+        let mut cfg: Config =
+            serde_yaml::from_str(&yaml.replace("ref: maintainer\n", "ref: maintainer_pr\n"))
+                .expect("Must parse");
+        assert!(cfg.resolve_conditions().is_err());
+    }
+
+    #[test]
+    fn test_resolve_step_templates() {
+        let yaml = r#"---
+step_templates:
+  deploy:
+    params: [env]
+    steps:
+      - add_labels: ["deploy/${{ params.env }}"]
+      - use: notify
+        with: {channel: deployments}
+  notify:
+    params: [channel]
+    steps:
+      - comment: "Deployed, see ${{ params.channel }}"
+on:
+  issue_comment:
+    - if:
+        - command: deploy
+      steps:
+        - use: deploy
+          with: {env: staging}
+"#;
+
+        let mut cfg: Config = serde_yaml::from_str(yaml).expect("Must parse");
+        cfg.resolve_step_templates().expect("Must resolve");
+
+        let notify = Step::Use(Use {
+            template: "notify".into(),
+            with: [("channel".to_string(), "deployments".into())].into(),
+            steps: vec![Step::Comment(Comment::Body(
+                "Deployed, see ${{ params.channel }}".into(),
+            ))],
+        });
+        assert_eq!(
+            cfg.on.issue_comment.unwrap()[0].common.steps,
+            vec![Step::Use(Use {
+                template: "deploy".into(),
+                with: [("env".to_string(), "staging".into())].into(),
+                steps: vec![
+                    Step::AddLabels(vec!["deploy/${{ params.env }}".into()]),
+                    notify,
+                ],
+            })]
+        );
+
+        for (from, to) in [
+            ("use: deploy", "use: other"),
+            ("with: {env: staging}", "with: {}"),
+            ("with: {env: staging}", "with: {env: staging, region: eu}"),
+            (
+                "with: {channel: deployments}",
+                "with: {channel: deployments}
+      - use: deploy
+        with: {env: prod}",
+            ),
+        ] {
+            let mut cfg: Config =
+                serde_yaml::from_str(&yaml.replace(from, to)).expect("Must parse");
+            assert!(cfg.resolve_step_templates().is_err(), "{}", to);
+        }
+
+        assert!(serde_yaml::from_str::<Config>(&yaml.replace("with: {env", "wit: {env")).is_err());
+    }
+
+    #[test]
+    fn test_parse_invalid_pattern() {
+        let yaml = r#"---
+on:
+  issue:
+    - if:
+        - title_matches: "[RFC"
+      steps: []
+"#;
+
+        assert!(serde_yaml::from_str::<Config>(yaml).is_err());
+    }
+
+    #[test]
+    fn test_glob() {
+        let glob = |g: &str| Glob::new(g).expect("Must compile");
+
+        assert!(glob("docs/**").is_match("docs/index.md"));
+        assert!(glob("docs/**").is_match("docs/api/index.md"));
+        assert!(!glob("docs/**").is_match("src/docs/index.md"));
+        assert!(glob("*.md").is_match("README.md"));
+        assert!(glob("*.md").is_match("docs/api/index.md"));
+        assert!(glob("src/*.rs").is_match("src/main.rs"));
+        assert!(!glob("src/*.rs").is_match("src/bin/main.rs"));
+        assert!(glob("src/**/*.rs").is_match("src/main.rs"));
+        assert!(glob("src/**/*.rs").is_match("src/bin/main.rs"));
+        assert!(glob("*.{yaml,yml}").is_match("deploy/rodbot.yml"));
+        assert!(!glob("*.md").is_match("README.mdx"));
+    }
 }
@@ -0,0 +1,164 @@
+use crate::{
+    config::{DependencyReview, Severity},
+    github::GitHub,
+    steps::{add_labels, IssueRef},
+};
+use serde::Deserialize;
+use serde_json::json;
+
+#[derive(Clone, Debug, Deserialize)]
+struct PullRequestRefs {
+    base: Sha,
+    head: Sha,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Sha {
+    sha: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Change {
+    pub change_type: String,
+    pub ecosystem: String,
+    pub manifest: String,
+    pub name: String,
+    pub version: String,
+    #[serde(default)]
+    pub vulnerabilities: Vec<Vulnerability>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Vulnerability {
+    pub advisory_ghsa_id: String,
+    pub advisory_summary: String,
+    pub advisory_url: String,
+    pub severity: Severity,
+}
+
+/// Create the summary of added dependencies, and the vulnerabilities at or above the severity.
+///
+/// Returns `None` if no dependencies were added.
+pub fn summary(changes: &[Change], min_severity: Severity) -> Option<(String, bool)> {
+    let added = changes
+        .iter()
+        .filter(|c| c.change_type == "added")
+        .collect::<Vec<_>>();
+
+    if added.is_empty() {
+        return None;
+    }
+
+    let mut vulnerable = false;
+    let mut result = String::from("### Dependency review\n\n");
+    result.push_str("| Dependency | Version | Manifest | Vulnerabilities |\n");
+    result.push_str("| --- | --- | --- | --- |\n");
+
+    for change in added {
+        let vulnerabilities = change
+            .vulnerabilities
+            .iter()
+            .filter(|v| v.severity >= min_severity)
+            .map(|v| {
+                format!(
+                    "[{}]({}) ({:?}): {}",
+                    v.advisory_ghsa_id, v.advisory_url, v.severity, v.advisory_summary
+                )
+            })
+            .collect::<Vec<_>>();
+
+        vulnerable |= !vulnerabilities.is_empty();
+
+        result.push_str(&format!(
+            "| {} ({}) | {} | {} | {} |\n",
+            change.name,
+            change.ecosystem,
+            change.version,
+            change.manifest,
+            if vulnerabilities.is_empty() {
+                "none".to_string()
+            } else {
+                vulnerabilities.join("<br>")
+            }
+        ));
+    }
+
+    Some((result, vulnerable))
+}
+
+/// Review the dependencies added by the pull request of the event.
+pub fn review(github: &GitHub, config: &DependencyReview, issue: &IssueRef) -> anyhow::Result<()> {
+    let pr: PullRequestRefs = github.get(&format!(
+        "repos/{}/pulls/{}",
+        issue.repository, issue.number
+    ))?;
+
+    let changes: Vec<Change> = github.get(&format!(
+        "repos/{}/dependency-graph/compare/{}...{}",
+        issue.repository, pr.base.sha, pr.head.sha
+    ))?;
+
+    let (summary, vulnerable) = match summary(&changes, config.min_severity) {
+        Some(summary) => summary,
+        None => {
+            log::info!("No dependencies added");
+            return Ok(());
+        }
+    };
+
+    if config.comment {
+        github.post(
+            &format!("{}/comments", issue.path()),
+            &json!({ "body": summary }),
+        )?;
+    }
+
+    if vulnerable {
+        log::warn!("Pull request adds vulnerable dependencies");
+        if let Some(label) = &config.label {
+            add_labels(github, issue, std::slice::from_ref(label))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let changes: Vec<Change> = serde_json::from_value(json!([
+            {
+                "change_type": "added",
+                "ecosystem": "cargo",
+                "manifest": "Cargo.lock",
+                "name": "smallvec",
+                "version": "1.6.0",
+                "vulnerabilities": [{
+                    "advisory_ghsa_id": "GHSA-43w2-9j62-hq99",
+                    "advisory_summary": "Buffer overflow in SmallVec::insert_many",
+                    "advisory_url": "https://github.com/advisories/GHSA-43w2-9j62-hq99",
+                    "severity": "critical"
+                }]
+            },
+            {
+                "change_type": "removed",
+                "ecosystem": "cargo",
+                "manifest": "Cargo.lock",
+                "name": "smallvec",
+                "version": "1.6.1",
+                "vulnerabilities": []
+            }
+        ]))
+        .unwrap();
+
+        let (text, vulnerable) = summary(&changes, Severity::High).unwrap();
+        assert!(vulnerable);
+        assert!(text.contains("| smallvec (cargo) | 1.6.0 | Cargo.lock | [GHSA-43w2-9j62-hq99]"));
+        assert!(!text.contains("1.6.1"));
+
+        assert!(summary(&changes[1..], Severity::Low).is_none());
+    }
+}
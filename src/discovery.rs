@@ -0,0 +1,121 @@
+use anyhow::Context;
+use serde_yaml::Value;
+use std::{fs::File, path::Path};
+
+/// Load the configuration from the YAML files of a directory, merging them in order of their
+/// names.
+///
+/// Hidden entries are skipped, so that directories mounted from a Kubernetes `ConfigMap`,
+/// which contain `..data` links, can be used.
+pub fn load_dir(path: &Path) -> anyhow::Result<(Value, usize)> {
+    let mut files = std::fs::read_dir(path)
+        .with_context(|| format!("Failed to read configuration directory: {}", path.display()))?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|p| {
+            !p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with('.'))
+        })
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+        .filter(|p| p.is_file())
+        .collect::<Vec<_>>();
+    files.sort();
+
+    if files.is_empty() {
+        anyhow::bail!("No configuration files found in: {}", path.display());
+    }
+
+    let mut result = Value::Null;
+    for file in &files {
+        log::debug!("Loading configuration from: {}", file.display());
+        let value: Value = serde_yaml::from_reader(File::open(file)?)
+            .with_context(|| format!("Failed to parse: {}", file.display()))?;
+        merge(&mut result, value);
+    }
+
+    Ok((result, files.len()))
+}
+
+/// Merge a configuration into another one.
+///
+/// Mappings are merged, sequences are appended, so that rules of different files add up. Other
+/// values are replaced.
+pub fn merge(value: &mut Value, other: Value) {
+    match (value, other) {
+        (Value::Mapping(value), Value::Mapping(other)) => {
+            for (k, v) in other {
+                match value.get_mut(&k) {
+                    Some(existing) => merge(existing, v),
+                    None => {
+                        value.insert(k, v);
+                    }
+                }
+            }
+        }
+        (Value::Sequence(value), Value::Sequence(other)) => value.extend(other),
+        (_, Value::Null) => {}
+        (value, other) => *value = other,
+    }
+}
+
+/// A hash of the configuration, to tell which configuration is in use.
+///
+/// This uses FNV-1a, which is stable across builds, unlike the hasher of the standard library.
+pub fn hash(value: &Value) -> anyhow::Result<u64> {
+    Ok(fnv1a(serde_yaml::to_string(value)?.as_bytes()))
+}
+
+/// The FNV-1a hash of some data.
+pub fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut value: Value = serde_yaml::from_str(
+            "on:\n  issue_comment:\n    - steps: [close: {}]\nchecks:\n  files: {}\n",
+        )
+        .unwrap();
+        merge(
+            &mut value,
+            serde_yaml::from_str("on:\n  issue_comment:\n    - steps: [reopen]\nchecks: ~\n")
+                .unwrap(),
+        );
+
+        assert_eq!(
+            value,
+            serde_yaml::from_str::<Value>(
+                "on:\n  issue_comment:\n    - steps: [close: {}]\n    - steps: [reopen]\nchecks:\n  files: {}\n"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_load_dir() -> anyhow::Result<()> {
+        let (value, files) = load_dir(Path::new("test/config.d"))?;
+        assert_eq!(files, 2);
+
+        let config: crate::config::Config = serde_yaml::from_value(value.clone())?;
+        assert_eq!(config.on.issue_comment.map(|rules| rules.len()), Some(2));
+        assert!(config.on.issue.is_some());
+
+        // stable, for the same configuration
+        assert_eq!(
+            hash(&value)?,
+            hash(&load_dir(Path::new("test/config.d"))?.0)?
+        );
+
+        assert!(load_dir(Path::new("test/scenarios/missing")).is_err());
+
+        Ok(())
+    }
+}
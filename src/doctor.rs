@@ -0,0 +1,259 @@
+use crate::{config::Config, event::Event, github::GitHub, output::Output, template, version};
+use serde::Serialize;
+use serde_json::json;
+use std::{fs::File, process::Command};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Finding {
+    pub status: Status,
+    pub message: String,
+}
+
+impl Finding {
+    fn new<S: Into<String>>(status: Status, message: S) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+/// Find the executables required by the steps of the configuration.
+pub fn executables(config: &serde_yaml::Value) -> Vec<String> {
+    fn walk(value: &serde_yaml::Value, result: &mut Vec<String>) {
+        match value {
+            serde_yaml::Value::Mapping(map) => {
+                for (key, value) in map {
+                    let command = match key.as_str() {
+                        Some("run") => Some("bash"),
+                        Some("check_links") => Some("curl"),
+                        Some("spell_check") => Some(
+                            value
+                                .get("command")
+                                .and_then(|c| c.as_str())
+                                .unwrap_or("typos"),
+                        ),
+                        _ => None,
+                    };
+                    if let Some(command) = command {
+                        if !result.iter().any(|c| c == command) {
+                            result.push(command.to_string());
+                        }
+                    }
+                    walk(value, result);
+                }
+            }
+            serde_yaml::Value::Sequence(seq) => {
+                for value in seq {
+                    walk(value, result);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut result = Vec::new();
+    walk(config, &mut result);
+    result
+}
+
+fn is_available(command: &str) -> bool {
+    Command::new(command)
+        .arg("--version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+fn check_config(path: &str, findings: &mut Vec<Finding>) -> Option<serde_yaml::Value> {
+    let value = match File::open(path)
+        .map_err(anyhow::Error::from)
+        .and_then(|file| Ok(serde_yaml::from_reader::<_, serde_yaml::Value>(file)?))
+    {
+        Ok(value) => value,
+        Err(err) => {
+            findings.push(Finding::new(
+                Status::Error,
+                format!(
+                    "Unable to read the configuration '{}': {} (use --config to select a different file)",
+                    path, err
+                ),
+            ));
+            return None;
+        }
+    };
+
+    match version::check_min_version(&value)
+        .and_then(|_| Ok(serde_yaml::from_value::<Config>(value.clone())?))
+        .and_then(|mut config| {
+            config.resolve_conditions()?;
+            config.resolve_step_templates()?;
+            template::precompile(&value)?;
+            Ok(config)
+        }) {
+        Ok(config) => {
+            findings.push(Finding::new(
+                Status::Ok,
+                format!("Configuration '{}' is valid", path),
+            ));
+            for name in config.on.unknown_events() {
+                findings.push(Finding::new(
+                    Status::Warning,
+                    format!(
+                        "Rules for '{}' never run, GitHub doesn't trigger workflows for such events",
+                        name
+                    ),
+                ));
+            }
+        }
+        Err(err) => findings.push(Finding::new(
+            Status::Error,
+            format!("Configuration '{}' is invalid: {}", path, err),
+        )),
+    }
+
+    Some(value)
+}
+
+fn check_github(findings: &mut Vec<Finding>) {
+    if !is_available("gh") {
+        findings.push(Finding::new(
+            Status::Error,
+            "The GitHub CLI 'gh' is not available, it is required for all API calls",
+        ));
+        return;
+    }
+
+    if std::env::var_os("GITHUB_TOKEN").is_none() && std::env::var_os("GH_TOKEN").is_none() {
+        findings.push(Finding::new(
+            Status::Warning,
+            "Neither GITHUB_TOKEN nor GH_TOKEN is set, 'gh' must be logged in",
+        ));
+    }
+
+    match GitHub::new().get::<serde_json::Value>("rate_limit") {
+        Ok(limit) => findings.push(Finding::new(
+            Status::Ok,
+            format!(
+                "GitHub API access works, {} requests remaining",
+                limit["resources"]["core"]["remaining"]
+            ),
+        )),
+        Err(err) => findings.push(Finding::new(
+            Status::Error,
+            format!("GitHub API access failed, check the token: {}", err),
+        )),
+    }
+}
+
+fn check_event(findings: &mut Vec<Finding>) {
+    if std::env::var_os("GITHUB_EVENT_NAME").is_none() {
+        findings.push(Finding::new(
+            Status::Warning,
+            "GITHUB_EVENT_NAME is not set, running for events requires GitHub Actions",
+        ));
+        return;
+    }
+
+    match Event::from_env() {
+        Ok(event) => findings.push(Finding::new(
+            Status::Ok,
+            format!("Event '{}' can be processed", event.name()),
+        )),
+        Err(err) => findings.push(Finding::new(
+            Status::Error,
+            format!("Unable to read the event: {:#}", err),
+        )),
+    }
+}
+
+/// Check the prerequisites for running rodbot.
+pub fn doctor(config: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+
+    let value = check_config(config, &mut findings);
+    check_github(&mut findings);
+    check_event(&mut findings);
+
+    for command in value.as_ref().map(executables).unwrap_or_default() {
+        if is_available(&command) {
+            findings.push(Finding::new(
+                Status::Ok,
+                format!("'{}' is available", command),
+            ));
+        } else {
+            findings.push(Finding::new(
+                Status::Error,
+                format!(
+                    "'{}' is required by the configured steps, but not available",
+                    command
+                ),
+            ));
+        }
+    }
+
+    findings
+}
+
+pub fn doctor_command(matches: &clap::ArgMatches) -> anyhow::Result<()> {
+    let config = matches.value_of("config").unwrap_or("rodbot.yaml");
+    let findings = doctor(config);
+
+    let errors = findings
+        .iter()
+        .filter(|f| f.status == Status::Error)
+        .count();
+
+    Output::from_matches(matches).print(&json!({ "findings": findings, "errors": errors }), || {
+        for finding in &findings {
+            let status = match finding.status {
+                Status::Ok => "ok",
+                Status::Warning => "warning",
+                Status::Error => "error",
+            };
+            println!("[{:>7}] {}", status, finding.message);
+        }
+    });
+
+    if errors > 0 {
+        anyhow::bail!("Found {} problem(s)", errors);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_executables() {
+        let config: serde_yaml::Value = serde_yaml::from_str(
+            r#"---
+on:
+  pull_request:
+    - steps:
+        - run: echo "Hello"
+        - spell_check:
+            command: /usr/local/bin/typos
+        - check_links: {}
+  push:
+    - steps:
+        - run: echo "Pushed"
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            executables(&config),
+            vec!["bash", "/usr/local/bin/typos", "curl"]
+        );
+    }
+}
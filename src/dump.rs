@@ -0,0 +1,167 @@
+use crate::{discovery::fnv1a, event::Event};
+use anyhow::Context;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use serde_json::{Map, Value};
+use std::collections::BTreeSet;
+
+lazy_static! {
+    /// Words which may be a login, like in URLs, repository names or mentions.
+    static ref WORD: Regex = Regex::new(r"[A-Za-z0-9][A-Za-z0-9-]*").unwrap();
+}
+
+/// Redact an event payload, so that it can be shared.
+///
+/// Logins are replaced with pseudonyms, wherever they show up, so that the payload stays
+/// consistent. Names, emails and IDs of users are replaced too. Of bodies and commit messages,
+/// only the lines of slash commands are kept, so that command rules still match. Logins of bots
+/// are kept, as rules often check for them.
+pub fn redact(payload: &Value) -> Value {
+    let mut logins = BTreeSet::new();
+    collect_logins(payload, &mut logins);
+
+    let mut result = payload.clone();
+    redact_value(&mut result, &logins);
+    result
+}
+
+fn collect_logins(value: &Value, logins: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (k, v) in map {
+                match (k.as_str(), v) {
+                    ("login" | "username", Value::String(login)) if !login.ends_with("[bot]") => {
+                        logins.insert(login.to_lowercase());
+                    }
+                    _ => collect_logins(v, logins),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|v| collect_logins(v, logins)),
+        _ => {}
+    }
+}
+
+fn pseudonym(prefix: &str, value: &str) -> String {
+    format!("{}-{:08x}", prefix, fnv1a(value.as_bytes()) as u32)
+}
+
+fn is_user(map: &Map<String, Value>) -> bool {
+    ["login", "username", "email"]
+        .iter()
+        .any(|k| map.contains_key(*k))
+}
+
+fn redact_value(value: &mut Value, logins: &BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            let user = is_user(map);
+            for (k, v) in map.iter_mut() {
+                match (k.as_str(), v) {
+                    ("name", Value::String(name)) if user => *name = pseudonym("name", name),
+                    ("email", Value::String(email)) if user => {
+                        *email = format!("{}@example.com", pseudonym("email", email))
+                    }
+                    ("id", Value::Number(id)) if user => {
+                        *id = (fnv1a(id.to_string().as_bytes()) % 1_000_000_000).into()
+                    }
+                    ("node_id" | "gravatar_id" | "avatar_url", Value::String(s)) if user => {
+                        s.clear()
+                    }
+                    ("body" | "message", Value::String(body)) => {
+                        *body = replace_logins(&redact_body(body), logins)
+                    }
+                    (_, v) => redact_value(v, logins),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| redact_value(v, logins)),
+        Value::String(s) => *s = replace_logins(s, logins),
+        _ => {}
+    }
+}
+
+/// Keep the slash commands of a body only.
+fn redact_body(body: &str) -> String {
+    body.lines()
+        .map(|line| {
+            let trimmed = line.trim();
+            match trimmed.is_empty() || trimmed.starts_with('/') {
+                true => line,
+                false => "[redacted]",
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn replace_logins(s: &str, logins: &BTreeSet<String>) -> String {
+    WORD.replace_all(s, |c: &Captures| {
+        let word = &c[0];
+        match logins.contains(&word.to_lowercase()) {
+            true => pseudonym("user", &word.to_lowercase()),
+            false => word.to_string(),
+        }
+    })
+    .into_owned()
+}
+
+pub fn dump_command(matches: &clap::ArgMatches) -> anyhow::Result<()> {
+    let name = std::env::var("GITHUB_EVENT_NAME").context("Missing GITHUB_EVENT_NAME")?;
+    let payload: Value = Event::parse_payload().context("Failed to parse event payload")?;
+    let payload = match matches.is_present("redact") {
+        true => redact(&payload),
+        false => payload,
+    };
+
+    log::info!("Event: {}", name);
+    println!("{}", serde_json::to_string_pretty(&payload)?);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_redact_body() {
+        assert_eq!(
+            redact_body("Please have a look\n\n/assign @ctron\n  /hold"),
+            "[redacted]\n\n/assign @ctron\n  /hold"
+        );
+    }
+
+    #[test]
+    fn test_redact() -> anyhow::Result<()> {
+        let payload: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let redacted = redact(&payload);
+
+        let user = pseudonym("user", "ctron");
+        assert_eq!(redacted["comment"]["user"]["login"], user.as_str());
+        assert_eq!(
+            redacted["repository"]["full_name"],
+            format!("{}/gha-test", user)
+        );
+        assert_eq!(redacted["comment"]["body"], "/test");
+        assert_eq!(redacted["issue"]["body"], "[redacted]");
+        assert!(!redacted.to_string().to_lowercase().contains("ctron"));
+
+        // still a valid payload
+        Event::from_value("issue_comment", &redacted)?;
+
+        let push: Value = serde_json::from_reader(std::fs::File::open("test/push_1.json")?)?;
+        let redacted = redact(&push).to_string();
+        for email in push["commits"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|c| c["author"]["email"].as_str())
+        {
+            assert!(!redacted.contains(email));
+        }
+
+        Ok(())
+    }
+}
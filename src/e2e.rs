@@ -0,0 +1,326 @@
+use crate::{
+    event::Event,
+    mock::{Call, MockGitHub},
+    output::Output,
+    runner::{Context, Runner},
+};
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
+
+/// A scenario, running a sequence of events against the fake GitHub API.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub name: Option<String>,
+    /// The configuration, inline or the path of a file relative to the scenario.
+    pub config: Source<serde_yaml::Value>,
+    /// Responses of the API, in addition to the fixtures.
+    #[serde(default)]
+    pub responses: Vec<Response>,
+    pub events: Vec<ScenarioEvent>,
+}
+
+/// A value, either inline or the path of a file relative to the scenario.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+pub enum Source<T> {
+    Path(String),
+    Inline(T),
+}
+
+impl<T> Source<T>
+where
+    for<'de> T: Clone + Deserialize<'de>,
+{
+    fn load(&self, base: &Path) -> anyhow::Result<T> {
+        match self {
+            Self::Inline(value) => Ok(value.clone()),
+            Self::Path(path) => {
+                let path = base.join(path);
+                serde_yaml::from_reader(
+                    File::open(&path)
+                        .with_context(|| format!("Failed to open: {}", path.display()))?,
+                )
+                .with_context(|| format!("Failed to parse: {}", path.display()))
+            }
+        }
+    }
+}
+
+/// A response of the fake API, either a body or a failure status.
+#[derive(Clone, Debug, Deserialize)]
+pub struct Response {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub body: Value,
+    #[serde(default)]
+    pub status: Option<u16>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ScenarioEvent {
+    /// The name of the event, like `issue_comment`.
+    pub event: String,
+    pub payload: Source<Value>,
+    /// Merged into the payload, so that similar events can share a payload file.
+    #[serde(default)]
+    pub patch: Option<Value>,
+    /// Responses of the API, from this event on.
+    #[serde(default)]
+    pub responses: Vec<Response>,
+    /// Calls expected for this event, in order.
+    #[serde(default)]
+    pub expect: Vec<ExpectedCall>,
+    /// Calls which must not happen for this event.
+    #[serde(default)]
+    pub reject: Vec<ExpectedCall>,
+}
+
+/// A call of the API, the body only needs to contain the expected fields.
+#[derive(Clone, Debug, Deserialize)]
+pub struct ExpectedCall {
+    pub method: String,
+    pub path: String,
+    #[serde(default)]
+    pub body: Option<Value>,
+}
+
+impl ExpectedCall {
+    fn matches(&self, call: &Call) -> bool {
+        call.method == self.method
+            && crate::mock::matches(&self.path, &call.path)
+            && self
+                .body
+                .as_ref()
+                .is_none_or(|body| call.body.as_ref().is_some_and(|c| contains(c, body)))
+    }
+}
+
+impl std::fmt::Display for ExpectedCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.method, self.path)?;
+        if let Some(body) = &self.body {
+            write!(f, " {}", body)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Outcome {
+    pub path: String,
+    pub name: Option<String>,
+    pub failures: Vec<String>,
+}
+
+/// Check if a value contains the expected fields, other values must be equal.
+fn contains(value: &Value, expected: &Value) -> bool {
+    match (value, expected) {
+        (Value::Object(value), Value::Object(expected)) => expected
+            .iter()
+            .all(|(k, e)| value.get(k).is_some_and(|v| contains(v, e))),
+        _ => value == expected,
+    }
+}
+
+/// Merge a patch into a value, like a JSON merge patch.
+fn merge(value: &mut Value, patch: &Value) {
+    match (value, patch) {
+        (Value::Object(value), Value::Object(patch)) => {
+            for (k, p) in patch {
+                match p {
+                    Value::Null => {
+                        value.remove(k);
+                    }
+                    _ => merge(value.entry(k.clone()).or_insert(Value::Null), p),
+                }
+            }
+        }
+        (value, patch) => *value = patch.clone(),
+    }
+}
+
+/// Run a scenario, returning the failed expectations.
+pub fn run_scenario(path: &Path) -> anyhow::Result<Outcome> {
+    let scenario: Scenario = serde_yaml::from_reader(
+        File::open(path).with_context(|| format!("Failed to open: {}", path.display()))?,
+    )
+    .with_context(|| format!("Failed to parse: {}", path.display()))?;
+    let base = path.parent().unwrap_or_else(|| Path::new("."));
+
+    let config = crate::parse_config(scenario.config.load(base)?)?;
+
+    let mock = MockGitHub::with_fixtures();
+    let add = |mock: &MockGitHub, responses: &[Response]| {
+        for response in responses {
+            match response.status {
+                Some(status) => mock.fail(&response.method, &response.path, status),
+                None => mock.respond(&response.method, &response.path, response.body.clone()),
+            };
+        }
+    };
+    add(&mock, &scenario.responses);
+    let mock = mock.install();
+
+    let mut failures = Vec::new();
+    for (n, step) in scenario.events.iter().enumerate() {
+        let n = n + 1;
+        add(&mock, &step.responses);
+
+        let mut payload = step.payload.load(base)?;
+        if let Some(patch) = &step.patch {
+            merge(&mut payload, patch);
+        }
+        let event = Event::from_value(&step.event, &payload)
+            .with_context(|| format!("Event {} ({})", n, step.event))?;
+
+        let start = mock.calls().len();
+        let result = config.run(&Context {
+            payload: &event,
+            context: &json!({ "github": { "event": payload } }),
+        });
+        crate::summary::take();
+        if let Err(err) = result {
+            failures.push(format!("Event {} ({}): failed: {:#}", n, step.event, err));
+        }
+
+        let calls = &mock.calls()[start..];
+        let mut remaining = calls.iter();
+        for expected in &step.expect {
+            if !remaining.any(|call| expected.matches(call)) {
+                failures.push(format!(
+                    "Event {} ({}): missing call: {}",
+                    n, step.event, expected
+                ));
+            }
+        }
+        for rejected in &step.reject {
+            if calls.iter().any(|call| rejected.matches(call)) {
+                failures.push(format!(
+                    "Event {} ({}): unexpected call: {}",
+                    n, step.event, rejected
+                ));
+            }
+        }
+    }
+
+    Ok(Outcome {
+        path: path.display().to_string(),
+        name: scenario.name,
+        failures,
+    })
+}
+
+/// The scenarios of a path, either a single file or the YAML files of a directory.
+fn scenarios(path: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    if !path.is_dir() {
+        return Ok(vec![path.to_path_buf()]);
+    }
+
+    let mut result = std::fs::read_dir(path)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<anyhow::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|p| matches!(p.extension().and_then(|e| e.to_str()), Some("yaml" | "yml")))
+        .collect::<Vec<_>>();
+    result.sort();
+    Ok(result)
+}
+
+#[cfg_attr(not(feature = "mock"), allow(dead_code))]
+pub fn e2e_command(matches: &clap::ArgMatches) -> anyhow::Result<()> {
+    let path = Path::new(matches.value_of("scenarios").unwrap_or("scenarios"));
+
+    let mut outcomes = Vec::new();
+    for path in scenarios(path)? {
+        outcomes.push(run_scenario(&path).unwrap_or_else(|err| Outcome {
+            path: path.display().to_string(),
+            name: None,
+            failures: vec![format!("{:#}", err)],
+        }));
+    }
+
+    let failed = outcomes.iter().filter(|o| !o.failures.is_empty()).count();
+
+    Output::from_matches(matches).print(
+        &json!({ "scenarios": outcomes, "failed": failed }),
+        || {
+            for outcome in &outcomes {
+                let scenario = outcome.name.as_deref().unwrap_or(&outcome.path);
+                match outcome.failures.is_empty() {
+                    true => println!("[   ok] {}", scenario),
+                    false => {
+                        println!("[ fail] {}", scenario);
+                        for failure in &outcome.failures {
+                            println!("         {}", failure);
+                        }
+                    }
+                }
+            }
+        },
+    );
+
+    if failed > 0 {
+        anyhow::bail!("{} of {} scenario(s) failed", failed, outcomes.len());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let mut value = json!({"comment": {"body": "/hold", "id": 1}, "action": "created"});
+        merge(
+            &mut value,
+            &json!({"comment": {"body": "/unhold"}, "action": null}),
+        );
+        assert_eq!(value, json!({"comment": {"body": "/unhold", "id": 1}}));
+    }
+
+    #[test]
+    fn test_contains() {
+        let value = json!({"labels": ["hold"], "state": "open"});
+        assert!(contains(&value, &json!({"labels": ["hold"]})));
+        assert!(!contains(&value, &json!({"labels": ["hold", "lgtm"]})));
+        assert!(!contains(&value, &json!({"milestone": 1})));
+    }
+
+    #[test]
+    fn test_scenarios() -> anyhow::Result<()> {
+        assert_eq!(
+            scenarios(Path::new("test/scenarios"))?,
+            vec![
+                PathBuf::from("test/scenarios/failing.yaml"),
+                PathBuf::from("test/scenarios/hold.yaml"),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_scenario() -> anyhow::Result<()> {
+        let outcome = run_scenario(Path::new("test/scenarios/hold.yaml"))?;
+        assert!(outcome.failures.is_empty(), "{:?}", outcome.failures);
+
+        let outcome = run_scenario(Path::new("test/scenarios/failing.yaml"))?;
+        assert_eq!(
+            outcome.failures,
+            vec![
+                "Event 1 (issue_comment): missing call: POST repos/*/*/issues/*/labels {\"labels\":[\"lgtm\"]}",
+                "Event 1 (issue_comment): unexpected call: POST repos/*/*/issues/*/labels",
+            ]
+        );
+
+        Ok(())
+    }
+}
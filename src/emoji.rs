@@ -0,0 +1,143 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::borrow::Cow;
+
+lazy_static! {
+    static ref SHORTCODE: Regex = Regex::new(r":([a-z0-9_+-]+):").unwrap();
+}
+
+/// Common GitHub shortcodes, sorted by name.
+const EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("alarm_clock", "⏰"),
+    ("arrow_down", "⬇️"),
+    ("arrow_left", "⬅️"),
+    ("arrow_right", "➡️"),
+    ("arrow_up", "⬆️"),
+    ("art", "🎨"),
+    ("bangbang", "‼️"),
+    ("beers", "🍻"),
+    ("bell", "🔔"),
+    ("bomb", "💣"),
+    ("book", "📖"),
+    ("books", "📚"),
+    ("boom", "💥"),
+    ("bug", "🐛"),
+    ("bulb", "💡"),
+    ("calendar", "📆"),
+    ("clap", "👏"),
+    ("clipboard", "📋"),
+    ("closed_lock_with_key", "🔐"),
+    ("cloud", "☁️"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cop", "👮"),
+    ("crossed_fingers", "🤞"),
+    ("cry", "😢"),
+    ("dart", "🎯"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("gear", "⚙️"),
+    ("ghost", "👻"),
+    ("gift", "🎁"),
+    ("globe_with_meridians", "🌐"),
+    ("green_heart", "💚"),
+    ("hammer", "🔨"),
+    ("hammer_and_wrench", "🛠️"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("heavy_minus_sign", "➖"),
+    ("heavy_plus_sign", "➕"),
+    ("hourglass", "⌛"),
+    ("hourglass_flowing_sand", "⏳"),
+    ("hugs", "🤗"),
+    ("information_source", "ℹ️"),
+    ("key", "🔑"),
+    ("label", "🏷️"),
+    ("laughing", "😆"),
+    ("link", "🔗"),
+    ("lock", "🔒"),
+    ("loudspeaker", "📢"),
+    ("mag", "🔍"),
+    ("memo", "📝"),
+    ("no_entry", "⛔"),
+    ("no_entry_sign", "🚫"),
+    ("ok_hand", "👌"),
+    ("package", "📦"),
+    ("page_facing_up", "📄"),
+    ("paperclip", "📎"),
+    ("partying_face", "🥳"),
+    ("pencil2", "✏️"),
+    ("point_right", "👉"),
+    ("pray", "🙏"),
+    ("pushpin", "📌"),
+    ("question", "❓"),
+    ("raised_hands", "🙌"),
+    ("recycle", "♻️"),
+    ("red_circle", "🔴"),
+    ("robot", "🤖"),
+    ("rocket", "🚀"),
+    ("rotating_light", "🚨"),
+    ("see_no_evil", "🙈"),
+    ("shield", "🛡️"),
+    ("shipit", "🐿️"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sparkles", "✨"),
+    ("speech_balloon", "💬"),
+    ("star", "⭐"),
+    ("stop_sign", "🛑"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("truck", "🚚"),
+    ("unlock", "🔓"),
+    ("warning", "⚠️"),
+    ("wastebasket", "🗑️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wrench", "🔧"),
+    ("x", "❌"),
+    ("yellow_heart", "💛"),
+    ("zap", "⚡"),
+];
+
+/// Look up the emoji of a shortcode, without the colons.
+pub fn emoji(name: &str) -> Option<&'static str> {
+    EMOJI
+        .binary_search_by_key(&name, |(name, _)| name)
+        .ok()
+        .map(|i| EMOJI[i].1)
+}
+
+/// Replace known `:shortcode:`s with their emoji, leaving unknown ones untouched.
+pub fn render(text: &str) -> Cow<'_, str> {
+    SHORTCODE.replace_all(text, |caps: &Captures| {
+        emoji(&caps[1])
+            .map(ToString::to_string)
+            .unwrap_or_else(|| caps[0].to_string())
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_sorted() {
+        assert!(EMOJI.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_render() {
+        assert_eq!(emoji("tada"), Some("🎉"));
+        assert_eq!(emoji("unknown"), None);
+        assert_eq!(
+            render(":wave: Thanks! :+1: Build at 12:30:00, :not_an_emoji:"),
+            "👋 Thanks! 👍 Build at 12:30:00, :not_an_emoji:"
+        );
+    }
+}
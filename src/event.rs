@@ -1,25 +1,278 @@
 use anyhow::Context;
 use derefable::Derefable;
-use serde::Deserialize;
-use std::fs::File;
+use serde::{Deserialize, Deserializer};
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// The maximum size of an event payload, GitHub doesn't deliver larger ones either.
+const MAX_PAYLOAD_SIZE: u64 = 25 * 1024 * 1024;
+
+/// Fail on values of enums which are unknown, instead of accepting them as `Other`.
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+/// Handle a value unknown to an enum, failing in strict mode.
+fn unknown(what: &str, value: &str, strict: bool) -> anyhow::Result<()> {
+    if strict {
+        anyhow::bail!("Unknown {} '{}'", what, value);
+    }
+    log::warn!("Unknown {} '{}', treating it as other", what, value);
+    Ok(())
+}
+
+/// Compare values ignoring case, and the style of separating words.
+fn normalize(value: &str) -> String {
+    value.trim().to_lowercase().replace(['-', ' '], "_")
+}
+
+/// An enum of the payload which accepts values added by GitHub later on, instead of failing to
+/// parse the whole event.
+macro_rules! open_enum {
+    ($(#[$meta:meta])* pub enum $name:ident ($what:literal) { $($(#[$variant_meta:meta])* $variant:ident => $value:literal,)* }) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, Eq, PartialEq)]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value unknown to this version.
+            Other(String),
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                let normalized = normalize(&value);
+                $(if normalized == normalize($value) {
+                    return Ok(Self::$variant);
+                })*
+                unknown($what, &value, STRICT.load(Ordering::Relaxed))
+                    .map_err(serde::de::Error::custom)?;
+                Ok(Self::Other(value))
+            }
+        }
+    };
+}
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 #[serde(rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
 pub enum Event {
     IssueComment(IssueCommentEvent),
+    Issues(IssuesEvent),
+    PullRequest(PullRequestEvent),
+    Push(PushEvent),
+    Organization(OrganizationEvent),
+    Repository(RepositoryEvent),
+    Team(TeamEvent),
+    Membership(MembershipEvent),
+    PullRequestReview(PullRequestReviewEvent),
+    PullRequestReviewComment(PullRequestReviewCommentEvent),
+    WorkflowDispatch(WorkflowDispatchEvent),
+    /// Scheduled runs, which carry no payload.
+    Schedule,
+    RepositoryDispatch(RepositoryDispatchEvent),
+    Release(ReleaseEvent),
+    Discussion(DiscussionEvent),
+    DiscussionComment(DiscussionCommentEvent),
+    Label(LabelEvent),
+    Milestone(MilestoneEvent),
+    CheckRun(CheckRunEvent),
+    CheckSuite(CheckSuiteEvent),
+    Status(StatusEvent),
+    Create(RefEvent),
+    Delete(RefEvent),
+    WorkflowRun(WorkflowRunEvent),
+    /// Events which are not modeled, with their raw payload.
+    Other(RawEvent),
 }
 
 impl Event {
     pub fn from_env() -> anyhow::Result<Self> {
-        match std::env::var("GITHUB_EVENT_NAME").as_deref() {
-            Ok("issue_comment") => Ok(Event::IssueComment(
-                Self::parse_payload().context("Failed to parse event payload")?,
+        let name = std::env::var("GITHUB_EVENT_NAME").context("Missing GITHUB_EVENT_NAME")?;
+        Self::parse(&name, &EnvPayload)
+    }
+
+    /// Create an event from its name and payload.
+    pub fn from_value(name: &str, payload: &serde_json::Value) -> anyhow::Result<Self> {
+        Self::parse(name, payload)
+    }
+
+    fn parse(name: &str, source: &impl PayloadSource) -> anyhow::Result<Self> {
+        match name {
+            "issue_comment" => Ok(Event::IssueComment(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "issues" => Ok(Event::Issues(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "pull_request" => Ok(Event::PullRequest(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "push" => Ok(Event::Push(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "organization" => Ok(Event::Organization(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "repository" => Ok(Event::Repository(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "team" => Ok(Event::Team(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "membership" => Ok(Event::Membership(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "pull_request_review" => Ok(Event::PullRequestReview(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "pull_request_review_comment" => Ok(Event::PullRequestReviewComment(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "workflow_dispatch" => Ok(Event::WorkflowDispatch(
+                source.parse().context("Failed to parse event payload")?,
             )),
-            Ok(name) => Err(anyhow::anyhow!(
-                "Unknown or unsupported event type: {}",
-                name
+            "schedule" => Ok(Event::Schedule),
+            "repository_dispatch" => Ok(Event::RepositoryDispatch(
+                source.parse().context("Failed to parse event payload")?,
             )),
-            Err(_) => Err(anyhow::anyhow!("Missing GITHUB_EVENT_NAME")),
+            "release" => Ok(Event::Release(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "discussion" => Ok(Event::Discussion(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "discussion_comment" => Ok(Event::DiscussionComment(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "label" => Ok(Event::Label(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "milestone" => Ok(Event::Milestone(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "check_run" => Ok(Event::CheckRun(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "check_suite" => Ok(Event::CheckSuite(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "status" => Ok(Event::Status(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "create" => Ok(Event::Create(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "delete" => Ok(Event::Delete(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            "workflow_run" => Ok(Event::WorkflowRun(
+                source.parse().context("Failed to parse event payload")?,
+            )),
+            name => Ok(Event::Other(RawEvent {
+                name: name.to_string(),
+                payload: source.parse().context("Failed to parse event payload")?,
+            })),
+        }
+    }
+
+    /// The name of the event, as used by `GITHUB_EVENT_NAME`.
+    pub fn name(&self) -> &str {
+        match self {
+            Self::IssueComment(_) => "issue_comment",
+            Self::Issues(_) => "issues",
+            Self::PullRequest(_) => "pull_request",
+            Self::Push(_) => "push",
+            Self::Organization(_) => "organization",
+            Self::Repository(_) => "repository",
+            Self::Team(_) => "team",
+            Self::Membership(_) => "membership",
+            Self::PullRequestReview(_) => "pull_request_review",
+            Self::PullRequestReviewComment(_) => "pull_request_review_comment",
+            Self::WorkflowDispatch(_) => "workflow_dispatch",
+            Self::Schedule => "schedule",
+            Self::RepositoryDispatch(_) => "repository_dispatch",
+            Self::Release(_) => "release",
+            Self::Discussion(_) => "discussion",
+            Self::DiscussionComment(_) => "discussion_comment",
+            Self::Label(_) => "label",
+            Self::Milestone(_) => "milestone",
+            Self::CheckRun(_) => "check_run",
+            Self::CheckSuite(_) => "check_suite",
+            Self::Status(_) => "status",
+            Self::Create(_) => "create",
+            Self::Delete(_) => "delete",
+            Self::WorkflowRun(_) => "workflow_run",
+            Self::Other(event) => &event.name,
+        }
+    }
+
+    pub fn common(&self) -> Option<&CommonEvent> {
+        Some(match self {
+            Self::IssueComment(event) => &event.common,
+            Self::Issues(event) => &event.common,
+            Self::PullRequest(event) => &event.common,
+            Self::Push(event) => &event.common,
+            Self::Organization(event) => &event.common,
+            Self::Repository(event) => &event.common,
+            Self::Team(event) => &event.common,
+            Self::Membership(event) => &event.common,
+            Self::PullRequestReview(event) => &event.common,
+            Self::PullRequestReviewComment(event) => &event.common,
+            Self::WorkflowDispatch(event) => &event.common,
+            Self::RepositoryDispatch(event) => &event.common,
+            Self::Release(event) => &event.common,
+            Self::Discussion(event) => &event.common,
+            Self::DiscussionComment(event) => &event.common,
+            Self::Label(event) => &event.common,
+            Self::Milestone(event) => &event.common,
+            Self::CheckRun(event) => &event.common,
+            Self::CheckSuite(event) => &event.common,
+            Self::Status(event) => &event.common,
+            Self::Create(event) => &event.common,
+            Self::Delete(event) => &event.common,
+            Self::WorkflowRun(event) => &event.common,
+            Self::Schedule | Self::Other(_) => return None,
+        })
+    }
+
+    /// The repository and number of the pull request the event belongs to, if any.
+    pub fn pull_request(&self) -> Option<(&str, u64)> {
+        match self {
+            Self::IssueComment(event) => event
+                .issue
+                .pull_request
+                .as_ref()
+                .map(|_| (event.repository.full_name.as_str(), event.issue.number)),
+            Self::PullRequest(event) => Some((&event.repository.full_name, event.number)),
+            Self::PullRequestReview(event) => {
+                Some((&event.repository.full_name, event.pull_request.number))
+            }
+            Self::PullRequestReviewComment(event) => {
+                Some((&event.repository.full_name, event.pull_request.number))
+            }
+            Self::CheckRun(event) => event
+                .check_run
+                .pull_requests
+                .first()
+                .map(|pr| (event.repository.full_name.as_str(), pr.number)),
+            Self::CheckSuite(event) => event
+                .check_suite
+                .pull_requests
+                .first()
+                .map(|pr| (event.repository.full_name.as_str(), pr.number)),
+            Self::WorkflowRun(event) => event
+                .workflow_run
+                .pull_requests
+                .first()
+                .map(|pr| (event.repository.full_name.as_str(), pr.number)),
+            _ => None,
         }
     }
 
@@ -27,14 +280,72 @@ impl Event {
     where
         for<'de> T: Deserialize<'de>,
     {
-        Ok(serde_json::from_reader(File::open(
-            std::env::var("GITHUB_EVENT_PATH").context("Failed getting GITHUB_EVENT_PATH")?,
+        let path =
+            std::env::var("GITHUB_EVENT_PATH").context("Failed getting GITHUB_EVENT_PATH")?;
+        Ok(serde_json::from_reader(open_payload(
+            Path::new(&path),
+            MAX_PAYLOAD_SIZE,
         )?)?)
     }
 }
 
+/// Where the payload of an event is read from.
+trait PayloadSource {
+    fn parse<T>(&self) -> anyhow::Result<T>
+    where
+        for<'de> T: Deserialize<'de>;
+}
+
+/// The payload of the current workflow run, from `GITHUB_EVENT_PATH`.
+struct EnvPayload;
+
+impl PayloadSource for EnvPayload {
+    fn parse<T>(&self) -> anyhow::Result<T>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        Event::parse_payload()
+    }
+}
+
+impl PayloadSource for serde_json::Value {
+    fn parse<T>(&self) -> anyhow::Result<T>
+    where
+        for<'de> T: Deserialize<'de>,
+    {
+        Ok(T::deserialize(self)?)
+    }
+}
+
+/// Open the payload for reading, refusing payloads larger than `max` bytes.
+///
+/// This only guards the size, the whole payload is still read into memory, as it is also used
+/// as context.
+fn open_payload(path: &Path, max: u64) -> anyhow::Result<BufReader<File>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open event payload: {}", path.display()))?;
+    let size = file.metadata()?.len();
+    if size > max {
+        anyhow::bail!(
+            "Event payload is too large: {} bytes, the maximum is {} bytes",
+            size,
+            max
+        );
+    }
+    Ok(BufReader::new(file))
+}
+
+/// An event with its name and raw payload.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RawEvent {
+    pub name: String,
+    pub payload: serde_json::Value,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct CommonEvent {
+    /// Empty for events without actions, like `push`.
+    #[serde(default)]
     pub action: String,
     pub sender: Sender,
 }
@@ -46,34 +357,541 @@ pub struct IssueCommentEvent {
     pub common: CommonEvent,
     pub comment: Comment,
     pub issue: Issue,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct IssuesEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub issue: Issue,
+    pub repository: Repository,
+    /// The label which was added or removed, for `labeled` and `unlabeled`.
+    pub label: Option<Label>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct PullRequestEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub number: u64,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct PullRequestReviewEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+    pub review: Review,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct PullRequestReviewCommentEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub comment: ReviewComment,
+    pub pull_request: PullRequest,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct WorkflowDispatchEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    /// The inputs of the workflow, `null` if the workflow has no inputs.
+    #[serde(default)]
+    pub inputs: Option<serde_json::Map<String, serde_json::Value>>,
+    pub r#ref: String,
+    pub repository: Repository,
+    pub workflow: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct RepositoryDispatchEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub branch: Option<String>,
+    /// Arbitrary data provided by the sender of the event.
+    #[serde(default)]
+    pub client_payload: serde_json::Value,
+    pub repository: Repository,
+}
+
+impl RepositoryDispatchEvent {
+    /// The event type, which GitHub reports as the action.
+    pub fn event_type(&self) -> &str {
+        &self.common.action
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct DiscussionEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub discussion: Discussion,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct DiscussionCommentEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub comment: Comment,
+    pub discussion: Discussion,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Discussion {
+    pub author_association: AuthorAssociation,
+    pub body: Option<String>,
+    pub category: DiscussionCategory,
+    pub html_url: String,
+    pub id: u64,
+    pub number: u64,
+    pub title: String,
+    pub user: User,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct DiscussionCategory {
+    pub id: u64,
+    pub name: String,
+    pub slug: String,
+    /// Answers can be marked in this category.
+    #[serde(default)]
+    pub is_answerable: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct LabelEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub label: Label,
+    pub repository: Repository,
+    /// The previous values, for `edited`.
+    #[serde(default)]
+    pub changes: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct MilestoneEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub milestone: Milestone,
+    pub repository: Repository,
+    /// The previous values, for `edited`.
+    #[serde(default)]
+    pub changes: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Milestone {
+    pub closed_issues: u64,
+    pub description: Option<String>,
+    pub due_on: Option<String>,
+    pub html_url: String,
+    pub id: u64,
+    pub number: u64,
+    pub open_issues: u64,
+    /// Either `open` or `closed`.
+    pub state: String,
+    pub title: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct CheckRunEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub check_run: CheckRun,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CheckRun {
+    pub id: u64,
+    pub name: String,
+    pub head_sha: String,
+    pub html_url: Option<String>,
+    /// The status, like `queued`, `in_progress` or `completed`.
+    pub status: String,
+    /// The conclusion once completed, like `success`, `failure` or `timed_out`.
+    pub conclusion: Option<String>,
+    pub check_suite: Option<CheckSuite>,
+    #[serde(default)]
+    pub pull_requests: Vec<CheckPullRequest>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct CheckSuiteEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub check_suite: CheckSuite,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CheckSuite {
+    pub id: u64,
+    pub head_branch: Option<String>,
+    pub head_sha: String,
+    pub status: Option<String>,
+    pub conclusion: Option<String>,
+    #[serde(default)]
+    pub pull_requests: Vec<CheckPullRequest>,
+}
+
+/// A pull request a check belongs to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CheckPullRequest {
+    pub id: u64,
+    pub number: u64,
+}
+
+/// A commit status, the event has no action.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct StatusEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub id: u64,
+    pub sha: String,
+    /// The name of the status.
+    pub context: String,
+    pub description: Option<String>,
+    /// The state, like `pending`, `success`, `failure` or `error`.
+    pub state: String,
+    pub target_url: Option<String>,
+    pub repository: Repository,
+}
+
+/// A branch or tag was created or deleted, the events have no action.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct RefEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    /// The name of the branch or tag, without the `refs/heads/` or `refs/tags/` prefix.
+    pub r#ref: String,
+    pub ref_type: RefType,
+    pub repository: Repository,
+}
+
+open_enum! {
+    pub enum RefType ("ref type") {
+        Branch => "branch",
+        Tag => "tag",
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct WorkflowRunEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub workflow_run: WorkflowRun,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct WorkflowRun {
+    pub id: u64,
+    /// The name of the workflow.
+    pub name: String,
+    pub run_number: u64,
+    /// The event which triggered the workflow.
+    pub event: String,
+    pub status: String,
+    pub conclusion: Option<String>,
+    pub head_branch: Option<String>,
+    pub head_sha: String,
+    pub html_url: String,
+    /// Empty for pull requests from forks.
+    #[serde(default)]
+    pub pull_requests: Vec<CheckPullRequest>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct ReleaseEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub release: Release,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Release {
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+    pub body: Option<String>,
+    pub draft: bool,
+    pub html_url: String,
+    pub id: u64,
+    pub name: Option<String>,
+    pub prerelease: bool,
+    pub tag_name: String,
+    pub target_commitish: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Asset {
+    pub browser_download_url: String,
+    pub content_type: String,
+    pub name: String,
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct PushEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub after: String,
+    pub before: String,
+    pub commits: Vec<Commit>,
+    #[serde(default)]
+    pub created: bool,
+    #[serde(default)]
+    pub deleted: bool,
+    #[serde(default)]
+    pub forced: bool,
+    pub pusher: Pusher,
+    pub r#ref: String,
+    pub repository: Repository,
+}
+
+impl PushEvent {
+    /// The name of the branch, if a branch was pushed.
+    pub fn branch(&self) -> Option<&str> {
+        self.r#ref.strip_prefix("refs/heads/")
+    }
+
+    /// The name of the tag, if a tag was pushed.
+    pub fn tag(&self) -> Option<&str> {
+        self.r#ref.strip_prefix("refs/tags/")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Commit {
+    #[serde(default)]
+    pub added: Vec<String>,
+    pub author: CommitAuthor,
+    pub id: String,
+    pub message: String,
+    #[serde(default)]
+    pub modified: Vec<String>,
+    #[serde(default)]
+    pub removed: Vec<String>,
+    pub url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct CommitAuthor {
+    pub email: Option<String>,
+    pub name: String,
+    pub username: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Pusher {
+    pub email: Option<String>,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct OrganizationEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub membership: Option<OrganizationMembership>,
+    pub organization: Organization,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct RepositoryEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub organization: Option<Organization>,
+    pub repository: Repository,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct TeamEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub organization: Organization,
+    pub repository: Option<Repository>,
+    pub team: Team,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Derefable)]
+pub struct MembershipEvent {
+    #[deref(mutable)]
+    #[serde(flatten)]
+    pub common: CommonEvent,
+    pub member: User,
+    pub organization: Organization,
+    pub scope: String,
+    pub team: Team,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Organization {
+    pub id: u64,
+    pub login: String,
+    pub url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct OrganizationMembership {
+    pub role: String,
+    pub state: String,
+    pub user: User,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Repository {
+    pub default_branch: String,
+    pub full_name: String,
+    pub id: u64,
+    pub name: String,
+    pub owner: User,
+    pub private: bool,
+    pub url: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Team {
+    pub id: u64,
+    pub name: String,
+    pub slug: String,
+    pub url: String,
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct Comment {
     pub author_association: AuthorAssociation,
     pub body: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
     pub id: u64,
+    #[serde(default)]
+    pub updated_at: Option<String>,
     pub user: User,
 }
 
+impl Comment {
+    /// Check if the comment was changed after it was created.
+    pub fn is_edited(&self) -> bool {
+        self.updated_at.is_some() && self.updated_at != self.created_at
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Review {
+    pub author_association: AuthorAssociation,
+    pub body: Option<String>,
+    pub commit_id: String,
+    pub id: u64,
+    pub state: ReviewState,
+    pub user: User,
+}
+
+open_enum! {
+    pub enum ReviewState ("review state") {
+        Approved => "approved",
+        ChangesRequested => "changes_requested",
+        Commented => "commented",
+        Dismissed => "dismissed",
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ReviewComment {
+    pub author_association: AuthorAssociation,
+    pub body: String,
+    pub commit_id: String,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    pub id: u64,
+    pub line: Option<u64>,
+    pub path: String,
+    pub pull_request_review_id: Option<u64>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    pub user: User,
+}
+
+impl ReviewComment {
+    /// Check if the comment was changed after it was created.
+    pub fn is_edited(&self) -> bool {
+        self.updated_at.is_some() && self.updated_at != self.created_at
+    }
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct User {
     pub login: String,
+    #[serde(default)]
+    pub r#type: UserType,
+}
+
+open_enum! {
+    #[derive(Default)]
+    pub enum UserType ("user type") {
+        #[default]
+        User => "User",
+        Bot => "Bot",
+        Organization => "Organization",
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct Issue {
+    #[serde(default)]
+    pub assignees: Vec<User>,
     pub author_association: AuthorAssociation,
     pub body: Option<String>,
     pub comments: u64,
     pub id: u64,
     pub labels: Vec<Label>,
     pub locked: bool,
+    #[serde(default)]
+    pub milestone: Option<Milestone>,
     pub number: u64,
 
-    pub pull_request: Option<PullRequest>,
+    pub pull_request: Option<IssuePullRequest>,
 
+    pub state: IssueState,
+    pub title: String,
     pub url: String,
+    pub user: User,
+}
+
+open_enum! {
+    pub enum IssueState ("issue state") {
+        Open => "open",
+        Closed => "closed",
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
@@ -89,28 +907,72 @@ pub struct Label {
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct PullRequest {
+    #[serde(default)]
+    pub additions: Option<u64>,
+    pub author_association: AuthorAssociation,
+    pub base: Branch,
+    pub body: Option<String>,
+    pub changed_files: Option<u64>,
+    #[serde(default)]
+    pub deletions: Option<u64>,
+    #[serde(default)]
+    pub draft: bool,
+    pub head: Branch,
+    pub html_url: String,
+    pub id: u64,
+    pub labels: Vec<Label>,
+    pub locked: bool,
+    pub merged: Option<bool>,
+    /// Only present when fetching the pull request, `unknown` while GitHub computes it.
+    #[serde(default)]
+    pub mergeable_state: Option<String>,
+    pub number: u64,
+    pub state: String,
+    pub title: String,
+    pub url: String,
+    pub user: User,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Branch {
+    pub label: String,
+    pub r#ref: String,
+    pub repo: Option<Repository>,
+    pub sha: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct IssuePullRequest {
     pub diff_url: String,
     pub html_url: String,
     pub patch_url: String,
     pub url: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
-#[serde(rename_all = "UPPERCASE")]
-pub enum AuthorAssociation {
-    Collaborator,
-    Contributor,
-    FirstTimer,
-    FirstTimeContributor,
-    Mannequin,
-    Member,
-    None,
-    Owner,
+open_enum! {
+    pub enum AuthorAssociation ("author association") {
+        Collaborator => "COLLABORATOR",
+        Contributor => "CONTRIBUTOR",
+        FirstTimer => "FIRST_TIMER",
+        FirstTimeContributor => "FIRST_TIME_CONTRIBUTOR",
+        Mannequin => "MANNEQUIN",
+        Member => "MEMBER",
+        None => "NONE",
+        Owner => "OWNER",
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
 pub struct Sender {
     pub login: String,
+    #[serde(default)]
+    pub r#type: UserType,
+}
+
+impl Sender {
+    pub fn is_bot(&self) -> bool {
+        self.r#type == UserType::Bot
+    }
 }
 
 #[cfg(test)]
@@ -118,6 +980,39 @@ mod test {
     use super::*;
     use std::fs::File;
 
+    #[test]
+    fn test_open_payload() {
+        let path = Path::new("test/push_1.json");
+        assert!(open_payload(path, MAX_PAYLOAD_SIZE).is_ok());
+        assert!(open_payload(path, 16).is_err());
+    }
+
+    #[test]
+    fn test_open_enum() -> anyhow::Result<()> {
+        let parse = |value: &str| serde_json::from_value::<AuthorAssociation>(value.into());
+
+        assert_eq!(parse("OWNER")?, AuthorAssociation::Owner);
+        assert_eq!(parse("FIRST_TIMER")?, AuthorAssociation::FirstTimer);
+        assert_eq!(
+            parse("first-time contributor")?,
+            AuthorAssociation::FirstTimeContributor
+        );
+        assert_eq!(
+            parse("SPONSOR")?,
+            AuthorAssociation::Other("SPONSOR".into())
+        );
+
+        let parse = |value: &str| serde_json::from_value::<UserType>(value.into());
+
+        assert_eq!(parse("Bot")?, UserType::Bot);
+        assert_eq!(parse("Mannequin")?, UserType::Other("Mannequin".into()));
+
+        assert!(unknown("author association", "SPONSOR", false).is_ok());
+        assert!(unknown("author association", "SPONSOR", true).is_err());
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse() -> anyhow::Result<()> {
         let event: IssueCommentEvent =
@@ -127,4 +1022,171 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_issues() -> anyhow::Result<()> {
+        let event: IssuesEvent = serde_json::from_reader(File::open("test/issues_1.json")?)?;
+
+        assert_eq!(event.action, "labeled");
+        assert_eq!(event.issue.title, "[RFC] Support more events");
+        assert_eq!(event.label.map(|l| l.name), Some("enhancement".into()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pull_request() -> anyhow::Result<()> {
+        let event: PullRequestEvent =
+            serde_json::from_reader(File::open("test/pull_request_1.json")?)?;
+
+        assert_eq!(event.action, "labeled");
+        assert_eq!(event.pull_request.base.r#ref, "main");
+        assert_eq!(event.pull_request.head.r#ref, "feature/foo");
+        assert_eq!(event.pull_request.changed_files, Some(3));
+        assert!(!event.pull_request.draft);
+        assert_eq!(event.pull_request.labels[0].name, "enhancement");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_pull_request_review() -> anyhow::Result<()> {
+        let event: PullRequestReviewEvent =
+            serde_json::from_reader(File::open("test/pull_request_review_1.json")?)?;
+
+        assert_eq!(event.action, "submitted");
+        assert_eq!(event.review.state, ReviewState::Approved);
+        assert_eq!(event.review.body.as_deref(), Some("/lgtm"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_workflow_dispatch() -> anyhow::Result<()> {
+        let event: WorkflowDispatchEvent =
+            serde_json::from_reader(File::open("test/workflow_dispatch_1.json")?)?;
+
+        let inputs = event.inputs.unwrap_or_default();
+        assert_eq!(inputs["prNr"], "42");
+        assert_eq!(inputs["dryRun"], true);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_repository_dispatch() -> anyhow::Result<()> {
+        let event: RepositoryDispatchEvent =
+            serde_json::from_reader(File::open("test/repository_dispatch_1.json")?)?;
+
+        assert_eq!(event.event_type(), "system-test");
+        assert_eq!(event.client_payload["prNr"], 42);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_release() -> anyhow::Result<()> {
+        let event: ReleaseEvent = serde_json::from_reader(File::open("test/release_1.json")?)?;
+
+        assert_eq!(event.action, "published");
+        assert_eq!(event.release.tag_name, "v0.1.6");
+        assert!(!event.release.prerelease);
+        assert_eq!(event.release.assets[0].name, "rodbot-linux-amd64");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_discussion_comment() -> anyhow::Result<()> {
+        let event: DiscussionCommentEvent =
+            serde_json::from_reader(File::open("test/discussion_comment_1.json")?)?;
+
+        assert_eq!(event.action, "created");
+        assert_eq!(event.comment.body, "/answer");
+        assert_eq!(event.discussion.number, 42);
+        assert_eq!(event.discussion.category.slug, "q-a");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_label() -> anyhow::Result<()> {
+        let event: LabelEvent = serde_json::from_reader(File::open("test/label_1.json")?)?;
+
+        assert_eq!(event.action, "edited");
+        assert_eq!(event.label.name, "kind/bug");
+        assert_eq!(event.changes.unwrap()["name"]["from"], "bug");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_check_run() -> anyhow::Result<()> {
+        let event: CheckRunEvent = serde_json::from_reader(File::open("test/check_run_1.json")?)?;
+
+        assert_eq!(event.action, "completed");
+        assert_eq!(event.check_run.name, "ci");
+        assert_eq!(event.check_run.conclusion.as_deref(), Some("failure"));
+        assert_eq!(
+            Event::CheckRun(event).pull_request(),
+            Some(("drogue-iot/drogue-cloud", 123))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_create() -> anyhow::Result<()> {
+        let event: RefEvent = serde_json::from_reader(File::open("test/create_1.json")?)?;
+
+        assert_eq!(event.r#ref, "feature/foo");
+        assert_eq!(event.ref_type, RefType::Branch);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_workflow_run() -> anyhow::Result<()> {
+        let event: WorkflowRunEvent =
+            serde_json::from_reader(File::open("test/workflow_run_1.json")?)?;
+
+        assert_eq!(event.action, "completed");
+        assert_eq!(event.workflow_run.name, "CI");
+        assert_eq!(event.workflow_run.conclusion.as_deref(), Some("success"));
+        assert_eq!(
+            event.workflow_run.head_branch.as_deref(),
+            Some("feature/foo")
+        );
+        assert_eq!(event.workflow_run.pull_requests[0].number, 123);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_push() -> anyhow::Result<()> {
+        let event: PushEvent = serde_json::from_reader(File::open("test/push_1.json")?)?;
+
+        assert_eq!(event.action, "");
+        assert_eq!(event.branch(), Some("main"));
+        assert_eq!(event.tag(), None);
+        assert_eq!(event.commits.len(), 1);
+        assert_eq!(event.pusher.name, "ctron");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_repository() -> anyhow::Result<()> {
+        let event: RepositoryEvent =
+            serde_json::from_reader(File::open("test/repository_created_1.json")?)?;
+
+        assert_eq!(event.action, "created");
+        assert_eq!(event.repository.full_name, "drogue-iot/new-repo");
+        assert_eq!(
+            event.organization.map(|org| org.login),
+            Some("drogue-iot".into())
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,118 @@
+use crate::summary;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::{fmt::Write, sync::Mutex};
+
+lazy_static! {
+    /// The evaluated rules, when explaining the decisions.
+    static ref EXPLAIN: Mutex<Option<Vec<Explanation>>> = Mutex::new(None);
+}
+
+/// The conditions of a rule, and what they evaluated to.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Explanation {
+    pub rule: String,
+    pub matched: bool,
+    pub conditions: Vec<Condition>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Condition {
+    pub condition: String,
+    pub result: bool,
+    /// The error, if the condition failed to evaluate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Record the conditions of the rules, evaluating all of them instead of stopping at the first
+/// rejecting one.
+pub fn start() {
+    *EXPLAIN.lock().unwrap() = Some(Vec::new());
+}
+
+pub fn is_active() -> bool {
+    EXPLAIN.lock().unwrap().is_some()
+}
+
+/// Record the conditions of a rule, unnamed rules are named after the event.
+pub fn record(rule: Option<&str>, conditions: Vec<Condition>) {
+    let rule = match rule {
+        Some(rule) => rule.to_string(),
+        None => summary::current().event,
+    };
+    if let Some(explanations) = EXPLAIN.lock().unwrap().as_mut() {
+        explanations.push(Explanation {
+            rule,
+            matched: conditions.iter().all(|c| c.result),
+            conditions,
+        });
+    }
+}
+
+/// Take the explanations, and stop explaining.
+pub fn take() -> Vec<Explanation> {
+    EXPLAIN.lock().unwrap().take().unwrap_or_default()
+}
+
+/// Render the explanations as text.
+pub fn render(explanations: &[Explanation]) -> String {
+    let mut out = String::new();
+    for explanation in explanations {
+        let _ = writeln!(
+            out,
+            "{}: {}",
+            explanation.rule,
+            match explanation.matched {
+                true => "matched",
+                false => "rejected",
+            }
+        );
+        for condition in &explanation.conditions {
+            let _ = write!(
+                out,
+                "  {} {}",
+                match condition.result {
+                    true => "✓",
+                    false => "✗",
+                },
+                condition.condition
+            );
+            if let Some(err) = &condition.error {
+                let _ = write!(out, " (failed: {})", err);
+            }
+            let _ = writeln!(out);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let explanations = vec![Explanation {
+            rule: "release".into(),
+            matched: false,
+            conditions: vec![
+                Condition {
+                    condition: "IsBot".into(),
+                    result: true,
+                    error: None,
+                },
+                Condition {
+                    condition: "UserInTeam(\"org/team\")".into(),
+                    result: false,
+                    error: Some("Not found".into()),
+                },
+            ],
+        }];
+
+        assert_eq!(
+            render(&explanations),
+            "release: rejected\n  ✓ IsBot\n  ✗ UserInTeam(\"org/team\") (failed: Not found)\n"
+        );
+    }
+}
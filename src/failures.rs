@@ -0,0 +1,277 @@
+use crate::{
+    clock::clock,
+    config::{Failures, OnCommon},
+    github::{encode, GitHub},
+    scope::Scope,
+    steps::{all_comments, IssueRef},
+    summary,
+};
+use anyhow::Context;
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::sync::RwLock;
+
+lazy_static! {
+    /// The policy of the configuration being run.
+    static ref POLICY: RwLock<Option<Failures>> = RwLock::new(None);
+}
+
+/// The label of issues tracking failures of rules.
+const LABEL: &str = "rodbot/failure";
+/// Marks the comments reporting a failure, followed by the time of the failure.
+const FAILURE_MARKER: &str = "<!-- rodbot:failed:";
+
+/// Set the policy for reporting failures, of the configuration being run.
+pub fn set_policy(policy: Option<Failures>) {
+    *POLICY.write().unwrap() = policy;
+}
+
+/// Report the failure of a rule, if the configuration or the rule asks for it.
+///
+/// Reporting is best effort, failing to report is only logged.
+pub fn report(rule: &OnCommon, context: &Scope, error: &anyhow::Error) {
+    let policy = match (POLICY.read().unwrap().clone(), &rule.owner) {
+        (Some(policy), _) => policy,
+        (None, Some(_)) => Failures::default(),
+        (None, None) => return,
+    };
+
+    let name = rule.name.as_deref().unwrap_or("unnamed");
+    let repository = policy
+        .repository
+        .clone()
+        .or_else(|| {
+            context["github"]["event"]["repository"]["full_name"]
+                .as_str()
+                .map(ToString::to_string)
+        })
+        .or_else(|| std::env::var("GITHUB_REPOSITORY").ok());
+    let repository = match repository {
+        Some(repository) => repository,
+        None => {
+            log::warn!("Unable to report the failure of rule '{}'", name);
+            return;
+        }
+    };
+
+    let report = summary::current().render(false);
+    if let Err(err) = record(
+        &GitHub::new(),
+        &policy,
+        &repository,
+        name,
+        rule.owner.as_deref(),
+        error,
+        &report,
+    ) {
+        log::warn!("Failed to report the failure of rule '{}': {}", name, err);
+    }
+}
+
+/// Record the failure of a rule in an issue, notifying if it fails repeatedly.
+///
+/// There is one open issue for each failing rule, which reports each failure as a comment,
+/// including the report of the run. Runs don't keep any state, so the comments are what counts
+/// the failures. Closing the issue starts counting again.
+pub fn record(
+    github: &GitHub,
+    policy: &Failures,
+    repository: &str,
+    rule: &str,
+    owner: Option<&str>,
+    error: &anyhow::Error,
+    report: &str,
+) -> anyhow::Result<()> {
+    let owner = owner.map(|owner| match owner.starts_with('@') {
+        true => owner.to_string(),
+        false => format!("@{}", owner),
+    });
+    let marker = format!("<!-- rodbot:failure:{} -->", rule);
+
+    let issues: Vec<Value> = github.get_all(&format!(
+        "repos/{}/issues?state=open&labels={}",
+        repository,
+        encode(LABEL)
+    ))?;
+    let number = match issues
+        .iter()
+        .find(|issue| issue["body"].as_str().is_some_and(|b| b.contains(&marker)))
+    {
+        Some(issue) => issue["number"].as_u64(),
+        None => github.post(
+            &format!("repos/{}/issues", repository),
+            &json!({
+                "title": format!("Rule '{}' is failing", rule),
+                "body": format!(
+                    "{}\nThe rule '{}'{} failed. Failures are reported as comments, \
+                     close this issue once the problem is resolved.",
+                    marker,
+                    rule,
+                    owner
+                        .as_deref()
+                        .map(|owner| format!(", owned by {},", owner))
+                        .unwrap_or_default()
+                ),
+                "labels": [LABEL],
+            }),
+        )?["number"]
+            .as_u64(),
+    }
+    .context("Missing number of the failure issue")?;
+    let issue = IssueRef {
+        repository: repository.to_string(),
+        number,
+    };
+
+    let now = clock().epoch();
+    let since = policy
+        .window_hours
+        .map(|hours| now.saturating_sub(hours * 60 * 60))
+        .unwrap_or_default();
+    let count = all_comments(github, &issue)?
+        .iter()
+        .filter_map(|c| failed_at(c["body"].as_str().unwrap_or_default()))
+        .filter(|at| *at >= since)
+        .count()
+        + 1;
+
+    let mut body = format!(
+        "{}{} -->\nFailure {} of rule '{}':\n\n```\n{:#}\n```\n\n\
+         <details><summary>Report of the run</summary>\n\n```\n{}```\n\n</details>",
+        FAILURE_MARKER,
+        now,
+        count,
+        rule,
+        error,
+        report.replace("```", "'''"),
+    );
+    if count == policy.threshold {
+        let window = policy
+            .window_hours
+            .map(|hours| format!(" in the last {} hour(s)", hours))
+            .unwrap_or_default();
+        body.push_str(&match owner {
+            Some(owner) => format!("\n\n{}, this rule failed {} times{}.", owner, count, window),
+            None => format!("\n\nThis rule failed {} times{}.", count, window),
+        });
+    }
+    github.post(
+        &format!("{}/comments", issue.path()),
+        &json!({ "body": body }),
+    )?;
+
+    Ok(())
+}
+
+/// The time of a failure, if the comment reports one.
+fn failed_at(body: &str) -> Option<u64> {
+    let start = body.find(FAILURE_MARKER)? + FAILURE_MARKER.len();
+    body[start..].split(' ').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::MockGitHub;
+
+    #[test]
+    fn test_failed_at() {
+        assert_eq!(
+            failed_at("<!-- rodbot:failed:1000 -->\nFailure 1"),
+            Some(1000)
+        );
+        assert_eq!(failed_at("<!-- rodbot:failure:release -->"), None);
+        assert_eq!(failed_at("Looking into it"), None);
+    }
+
+    #[test]
+    fn test_record() -> anyhow::Result<()> {
+        let github = GitHub::new();
+        let error = anyhow::anyhow!("Failed to run");
+        let policy = Failures::default();
+
+        // the first failure opens an issue
+        let mock = MockGitHub::with_fixtures().install();
+        mock.respond("POST", "repos/*/*/issues", json!({"number": 5}));
+        record(
+            &github,
+            &policy,
+            "ctron/rodbot",
+            "release",
+            Some("ctron"),
+            &error,
+            "rodbot: 1 rule(s) matched",
+        )?;
+
+        let issues = mock.calls_to("POST", "repos/ctron/rodbot/issues");
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            issues[0].body.as_ref().map(|b| &b["labels"]),
+            Some(&json!(["rodbot/failure"]))
+        );
+        let comments = mock.calls_to("POST", "repos/ctron/rodbot/issues/5/comments");
+        assert_eq!(comments.len(), 1);
+        let body = comments[0].body.as_ref().unwrap()["body"].as_str().unwrap();
+        assert!(body.contains("Failure 1 of rule 'release'"));
+        assert!(body.contains("rodbot: 1 rule(s) matched"));
+        drop(mock);
+
+        // further failures are added to the issue, mentioning the owner when failing repeatedly
+        let now = clock().epoch();
+        let mock = MockGitHub::with_fixtures().install();
+        mock.respond(
+            "GET",
+            "repos/ctron/rodbot/issues",
+            json!([
+                {"number": 4, "body": "<!-- rodbot:failure:other -->"},
+                {"number": 5, "body": "<!-- rodbot:failure:release -->"},
+            ]),
+        );
+        mock.respond(
+            "GET",
+            "repos/ctron/rodbot/issues/5/comments",
+            json!([
+                {"body": format!("<!-- rodbot:failed:{} -->\nFailure 1", now - 48 * 60 * 60)},
+                {"body": "Looking into it"},
+                {"body": format!("<!-- rodbot:failed:{} -->\nFailure 2", now - 60 * 60)},
+            ]),
+        );
+        record(
+            &github,
+            &policy,
+            "ctron/rodbot",
+            "release",
+            Some("@drogue-iot/ops"),
+            &error,
+            "",
+        )?;
+
+        assert!(mock.calls_to("POST", "repos/*/*/issues").is_empty());
+        let comments = mock.calls_to("POST", "repos/ctron/rodbot/issues/5/comments");
+        let body = comments[0].body.as_ref().unwrap()["body"].as_str().unwrap();
+        assert!(body.contains("Failure 3 of rule 'release'"));
+        assert!(body.ends_with("@drogue-iot/ops, this rule failed 3 times."));
+
+        // only failures within the window are counted
+        record(
+            &github,
+            &Failures {
+                repository: Some("ctron/rodbot".into()),
+                threshold: 2,
+                window_hours: Some(24),
+            },
+            "ctron/rodbot",
+            "release",
+            None,
+            &error,
+            "",
+        )?;
+
+        let comments = mock.calls_to("POST", "repos/ctron/rodbot/issues/5/comments");
+        let body = comments[1].body.as_ref().unwrap()["body"].as_str().unwrap();
+        assert!(body.contains("Failure 2 of rule 'release'"));
+        assert!(body.ends_with("\n\nThis rule failed 2 times in the last 24 hour(s)."));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::Context;
+use lazy_static::lazy_static;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::{Arc, RwLock},
+};
+
+lazy_static! {
+    static ref API: RwLock<Arc<dyn Api>> = RwLock::new(Arc::new(Gh));
+}
+
+/// The transport of API calls, which tests replace to run without access to GitHub.
+pub trait Api: Send + Sync {
+    fn execute(
+        &self,
+        method: &str,
+        path: &str,
+        accept: &str,
+        body: Option<&Value>,
+    ) -> anyhow::Result<Vec<u8>>;
+}
+
+/// Replace the transport of API calls, returning the previous one.
+#[cfg(any(test, feature = "mock"))]
+pub fn set_api(api: Arc<dyn Api>) -> Arc<dyn Api> {
+    std::mem::replace(&mut *API.write().unwrap(), api)
+}
+
+/// Access to the GitHub API.
+///
+/// This uses the `gh` command line tool, which is available on the GitHub Actions runners, and
+/// picks up the token from `GITHUB_TOKEN` or `GH_TOKEN`.
+#[derive(Clone, Debug, Default)]
+pub struct GitHub {}
+
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "GitHub API call failed ({}): {}", status, self.message),
+            None => write!(f, "GitHub API call failed: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+impl GitHub {
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    pub fn get<T>(&self, path: &str) -> anyhow::Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        Ok(serde_json::from_value(self.request("GET", path, None)?)?)
+    }
+
+    /// Get a resource, returning `None` if it could not be found.
+    pub fn get_optional<T>(&self, path: &str) -> anyhow::Result<Option<T>>
+    where
+        T: DeserializeOwned,
+    {
+        match self.request("GET", path, None) {
+            Ok(value) => Ok(Some(serde_json::from_value(value)?)),
+            Err(err) => match err.downcast_ref::<ApiError>() {
+                Some(ApiError {
+                    status: Some(404), ..
+                }) => Ok(None),
+                _ => Err(err),
+            },
+        }
+    }
+
+    /// Get all items of a paginated list.
+    pub fn get_all<T>(&self, path: &str) -> anyhow::Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        const PER_PAGE: usize = 100;
+
+        let separator = if path.contains('?') { '&' } else { '?' };
+        let mut result = Vec::new();
+
+        for page in 1.. {
+            let items: Vec<T> = self.get(&format!(
+                "{}{}per_page={}&page={}",
+                path, separator, PER_PAGE, page
+            ))?;
+            let len = items.len();
+            result.extend(items);
+            if len < PER_PAGE {
+                break;
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn post(&self, path: &str, body: &Value) -> anyhow::Result<Value> {
+        self.request("POST", path, Some(body))
+    }
+
+    pub fn put(&self, path: &str, body: &Value) -> anyhow::Result<Value> {
+        self.request("PUT", path, Some(body))
+    }
+
+    pub fn patch(&self, path: &str, body: &Value) -> anyhow::Result<Value> {
+        self.request("PATCH", path, Some(body))
+    }
+
+    pub fn delete(&self, path: &str) -> anyhow::Result<Value> {
+        self.request("DELETE", path, None)
+    }
+
+    /// Get the raw content of a file, using the contents API.
+    pub fn get_raw(&self, path: &str) -> anyhow::Result<String> {
+        let output = self.execute("GET", path, "application/vnd.github.v3.raw", None)?;
+        Ok(String::from_utf8(output)?)
+    }
+
+    /// Run a GraphQL query, returning the data.
+    pub fn graphql(&self, query: &str, variables: Value) -> anyhow::Result<Value> {
+        let mut response = self.post(
+            "graphql",
+            &json!({ "query": query, "variables": variables }),
+        )?;
+
+        if let Some(errors) = response.get("errors") {
+            anyhow::bail!("GraphQL query failed: {}", errors);
+        }
+
+        Ok(response["data"].take())
+    }
+
+    pub fn request(&self, method: &str, path: &str, body: Option<&Value>) -> anyhow::Result<Value> {
+        let output = self.execute(method, path, "application/vnd.github.v3+json", body)?;
+
+        if output.iter().all(|b| b.is_ascii_whitespace()) {
+            Ok(Value::Null)
+        } else {
+            Ok(serde_json::from_slice(&output)?)
+        }
+    }
+
+    fn execute(
+        &self,
+        method: &str,
+        path: &str,
+        accept: &str,
+        body: Option<&Value>,
+    ) -> anyhow::Result<Vec<u8>> {
+        log::debug!("API: {} {}", method, path);
+        crate::summary::api_call();
+
+        let api = API.read().unwrap().clone();
+        api.execute(method, path, accept, body)
+    }
+}
+
+/// Calls the API using the `gh` command line tool.
+struct Gh;
+
+impl Api for Gh {
+    fn execute(
+        &self,
+        method: &str,
+        path: &str,
+        accept: &str,
+        body: Option<&Value>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let mut cmd = Command::new("gh");
+        cmd.arg("api")
+            .arg("--method")
+            .arg(method)
+            .arg("-H")
+            .arg(format!("Accept: {}", accept))
+            .arg(path)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        if body.is_some() {
+            cmd.arg("--input").arg("-").stdin(Stdio::piped());
+        } else {
+            cmd.stdin(Stdio::null());
+        }
+
+        let mut child = cmd.spawn().context("Failed to run 'gh'")?;
+        if let Some(body) = body {
+            let mut stdin = child.stdin.take().context("Missing stdin")?;
+            stdin.write_all(&serde_json::to_vec(body)?)?;
+        }
+
+        let output = child.wait_with_output()?;
+
+        if !output.status.success() {
+            let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            log::warn!("API call failed: {} {} = {}", method, path, message);
+            return Err(ApiError {
+                status: parse_status(&message),
+                message,
+            }
+            .into());
+        }
+
+        Ok(output.stdout)
+    }
+}
+
+/// Percent-encode a query parameter value.
+pub fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Extract the HTTP status code from the error output of `gh`, like `gh: Not Found (HTTP 404)`.
+fn parse_status(message: &str) -> Option<u16> {
+    let start = message.rfind("(HTTP ")? + 6;
+    message[start..].split(')').next()?.parse().ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_status() {
+        assert_eq!(parse_status("gh: Not Found (HTTP 404)"), Some(404));
+        assert_eq!(
+            parse_status("gh: Git Repository is empty. (HTTP 409)"),
+            Some(409)
+        );
+        assert_eq!(parse_status("error connecting to api.github.com"), None);
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(
+            encode("repo:ctron/rodbot label:\"good first issue\""),
+            "repo%3Actron%2Frodbot%20label%3A%22good%20first%20issue%22"
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::config::{Config, IfDiscussionComment, IfIssueComment, IfPullRequestReviewComment, On};
+use std::{collections::HashMap, sync::OnceLock};
+
+/// The rules of comment events, indexed by the command they require.
+///
+/// This is built once, so that only the rules which may match a command need to be evaluated.
+#[derive(Debug, Default)]
+pub struct RuleIndex(OnceLock<Indexes>);
+
+#[derive(Debug, Default)]
+pub struct Indexes {
+    pub issue_comment: CommandIndex,
+    pub pull_request_review_comment: CommandIndex,
+    pub discussion_comment: CommandIndex,
+}
+
+impl Clone for RuleIndex {
+    fn clone(&self) -> Self {
+        // rebuilt when used
+        Self::default()
+    }
+}
+
+/// The index is derived from the rules, so it doesn't take part in comparing configurations.
+impl PartialEq for RuleIndex {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for RuleIndex {}
+
+impl Config {
+    /// Get the index of the rules, building it on first use.
+    pub fn index(&self) -> &Indexes {
+        self.index.0.get_or_init(|| Indexes::new(&self.on))
+    }
+}
+
+impl Indexes {
+    fn new(on: &On) -> Self {
+        Self {
+            issue_comment: CommandIndex::new(
+                on.issue_comment
+                    .iter()
+                    .flatten()
+                    .map(|rule| required(&rule.r#if)),
+            ),
+            pull_request_review_comment: CommandIndex::new(
+                on.pull_request_review_comment
+                    .iter()
+                    .flatten()
+                    .map(|rule| required(&rule.r#if)),
+            ),
+            discussion_comment: CommandIndex::new(
+                on.discussion_comment
+                    .iter()
+                    .flatten()
+                    .map(|rule| required(&rule.r#if)),
+            ),
+        }
+    }
+}
+
+/// Conditions which may require a command.
+trait RequiresCommand {
+    fn command(&self) -> Option<&str>;
+}
+
+macro_rules! requires_command {
+    ($($t:ty),*) => {
+        $(
+            impl RequiresCommand for $t {
+                fn command(&self) -> Option<&str> {
+                    match self {
+                        Self::Command(command) => Some(command),
+                        Self::And(children) => children.iter().find_map(Self::command),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+requires_command!(
+    IfIssueComment,
+    IfPullRequestReviewComment,
+    IfDiscussionComment
+);
+
+/// The command all conditions of a rule require, if any.
+///
+/// Commands are matched as a prefix of the first word, commands containing whitespace are not
+/// indexed.
+fn required<T: RequiresCommand>(conditions: &[T]) -> Option<&str> {
+    conditions
+        .iter()
+        .find_map(T::command)
+        .filter(|command| !command.is_empty() && !command.contains(char::is_whitespace))
+}
+
+/// Positions of rules, by the command they require.
+#[derive(Debug, Default)]
+pub struct CommandIndex {
+    commands: HashMap<String, Vec<usize>>,
+    /// Rules which don't require a command.
+    others: Vec<usize>,
+}
+
+impl CommandIndex {
+    fn new<'a>(commands: impl Iterator<Item = Option<&'a str>>) -> Self {
+        let mut index = Self::default();
+        for (i, command) in commands.enumerate() {
+            match command {
+                Some(command) => index
+                    .commands
+                    .entry(command.to_string())
+                    .or_default()
+                    .push(i),
+                None => index.others.push(i),
+            }
+        }
+        index
+    }
+
+    /// The positions of the rules which may match the comment, in order.
+    pub fn candidates(&self, body: &str) -> Vec<usize> {
+        let mut result = self.others.clone();
+
+        let word = body
+            .lines()
+            .next()
+            .and_then(|line| line.trim().strip_prefix('/'))
+            .and_then(|line| line.split(char::is_whitespace).next())
+            .unwrap_or_default();
+        // a rule's command may be any prefix of the word
+        for end in word
+            .char_indices()
+            .map(|(i, _)| i)
+            .skip(1)
+            .chain(std::iter::once(word.len()))
+            .filter(|end| *end > 0)
+        {
+            if let Some(rules) = self.commands.get(&word[..end]) {
+                result.extend(rules);
+            }
+        }
+
+        result.sort_unstable();
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_candidates() {
+        let rules = [
+            vec![IfIssueComment::Command("test".into())],
+            vec![IfIssueComment::IsPr],
+            vec![IfIssueComment::And(vec![
+                IfIssueComment::IsPr,
+                IfIssueComment::Command("te".into()),
+            ])],
+            vec![IfIssueComment::Command("release".into())],
+            vec![IfIssueComment::Not(Box::new(IfIssueComment::Command(
+                "release".into(),
+            )))],
+        ];
+        let index = CommandIndex::new(rules.iter().map(|rule| required(rule)));
+
+        assert_eq!(index.candidates("/test all\nPlease"), vec![0, 1, 2, 4]);
+        assert_eq!(index.candidates("/release-notes"), vec![1, 3, 4]);
+        assert_eq!(index.candidates("Thanks! /test"), vec![1, 4]);
+        assert_eq!(index.candidates("/ test"), vec![1, 4]);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::{
+    changes::{files, head_sha, numbered_added_lines},
+    checks::{create_check_run, Annotation, CheckRun},
+    clock,
+    config::CheckLinks,
+    github::GitHub,
+    steps::IssueRef,
+};
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::{process::Command, sync::Mutex, thread, time::Duration};
+
+lazy_static! {
+    static ref URL: Regex = Regex::new(r#"https?://[^\s<>()\[\]"'`]+"#).unwrap();
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Link {
+    pub path: String,
+    pub line: u64,
+    pub url: String,
+}
+
+/// Extract the links of the lines added by a patch, along with their line number in the new file.
+pub fn extract_links(path: &str, patch: &str) -> Vec<Link> {
+    let mut result = Vec::new();
+
+    for (line, added) in numbered_added_lines(patch) {
+        for url in URL.find_iter(added) {
+            result.push(Link {
+                path: path.to_string(),
+                line,
+                url: url
+                    .as_str()
+                    .trim_end_matches(&['.', ',', ';', ':', '!', '?'][..])
+                    .to_string(),
+            });
+        }
+    }
+
+    result
+}
+
+/// Request the URL, returning an error message if the link is broken.
+fn check_url(config: &CheckLinks, url: &str) -> Option<String> {
+    let mut message = String::new();
+
+    for attempt in 0..=config.retries {
+        if attempt > 0 {
+            clock::sleep(Duration::from_secs(attempt as u64));
+        }
+
+        let output = Command::new("curl")
+            .args(["--silent", "--location", "--output", "/dev/null"])
+            // the URLs come from untrusted content, don't expand globs or follow other protocols
+            .args([
+                "--globoff",
+                "--proto",
+                "=http,https",
+                "--proto-redir",
+                "=http,https",
+            ])
+            .args(["--max-time", &config.timeout.to_string()])
+            .args(["--write-out", "%{http_code}"])
+            .arg(url)
+            .output();
+
+        let status = match output {
+            Ok(output) => String::from_utf8_lossy(&output.stdout)
+                .trim()
+                .parse::<u16>()
+                .unwrap_or_default(),
+            Err(err) => return Some(format!("Failed to run curl: {}", err)),
+        };
+
+        match status {
+            200..=399 => return None,
+            // retry on server errors, rate limiting, and connection failures
+            0 => message = "Connection failed".into(),
+            429 | 500..=599 => message = format!("HTTP {}", status),
+            _ => return Some(format!("HTTP {}", status)),
+        }
+    }
+
+    Some(message)
+}
+
+/// Check the links, using a number of threads.
+fn check_all(config: &CheckLinks, links: &[Link]) -> Vec<Annotation> {
+    let next = Mutex::new(links.iter());
+    let result = Mutex::new(Vec::new());
+
+    thread::scope(|s| {
+        for _ in 0..config.concurrency.max(1) {
+            s.spawn(|| loop {
+                let link = match next.lock().unwrap().next() {
+                    Some(link) => link,
+                    None => break,
+                };
+                log::debug!("Checking link: {}", link.url);
+                if let Some(message) = check_url(config, &link.url) {
+                    result.lock().unwrap().push(Annotation {
+                        path: link.path.clone(),
+                        line: link.line,
+                        message: format!("Broken link {}: {}", link.url, message),
+                    });
+                }
+            });
+        }
+    });
+
+    let mut result = result.into_inner().unwrap();
+    result.sort_by(|a, b| (&a.path, a.line).cmp(&(&b.path, b.line)));
+    result
+}
+
+/// Check the links added by the pull request, and report broken ones as a check run.
+pub fn check_links(github: &GitHub, config: &CheckLinks, issue: &IssueRef) -> anyhow::Result<()> {
+    let head_sha = head_sha(github, &issue.repository, issue.number)?;
+
+    let mut links = Vec::new();
+    for file in files(github, &issue.repository, issue.number)? {
+        if file.status == "removed" || !config.paths.iter().any(|p| p.is_match(&file.filename)) {
+            continue;
+        }
+        if let Some(patch) = &file.patch {
+            links.extend(
+                extract_links(&file.filename, patch)
+                    .into_iter()
+                    .filter(|link| !config.ignore.iter().any(|p| p.is_match(&link.url))),
+            );
+        }
+    }
+
+    let annotations = check_all(config, &links);
+    log::info!(
+        "Link check: {} of {} link(s) broken",
+        annotations.len(),
+        links.len()
+    );
+
+    let title = if annotations.is_empty() {
+        "All links are valid".to_string()
+    } else {
+        format!("{} broken link(s)", annotations.len())
+    };
+
+    create_check_run(
+        github,
+        &issue.repository,
+        &CheckRun {
+            name: &config.name,
+            head_sha: &head_sha,
+            title: &title,
+            summary: &format!("Checked {} link(s).", links.len()),
+            annotations: &annotations,
+        },
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extract_links() {
+        let patch = "@@ -3,3 +3,5 @@\n See the [docs](https://book.drogue.io).\n-Old: http://example.com\n+New: https://drogue.io/blog, and <https://github.com/drogue-iot>.\n+\n@@ -20,1 +22,2 @@\n context\n+* https://example.com/foo?bar=1";
+
+        assert_eq!(
+            extract_links("README.md", patch)
+                .into_iter()
+                .map(|l| (l.line, l.url))
+                .collect::<Vec<_>>(),
+            vec![
+                (4, "https://drogue.io/blog".to_string()),
+                (4, "https://github.com/drogue-iot".to_string()),
+                (23, "https://example.com/foo?bar=1".to_string()),
+            ]
+        );
+    }
+}
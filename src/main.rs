@@ -1,49 +1,216 @@
+mod bootstrap;
+mod branch_protection;
+mod changes;
+mod checks;
+mod clock;
+mod command;
 mod config;
+mod dependencies;
+mod discovery;
+mod doctor;
+mod dump;
+#[cfg(any(test, feature = "mock"))]
+mod e2e;
+mod emoji;
 mod event;
+mod explain;
+mod failures;
+mod github;
+mod index;
+mod links;
+#[cfg(any(test, feature = "mock"))]
+#[cfg_attr(not(test), allow(dead_code))]
+mod mock;
+mod normalize;
+mod output;
+mod plan;
+mod repo_settings;
+mod report;
 mod runner;
+mod scope;
+mod search;
+mod spelling;
+mod steps;
+mod suggestions;
+mod summary;
+mod teams;
+mod template;
+mod threads;
+mod version;
 
 use crate::{
     config::Config,
+    output::Output,
     runner::{Context, Runner},
 };
 use anyhow::Context as _;
-use clap::{crate_version, Arg};
+use clap::{crate_version, AppSettings, Arg, ArgMatches, SubCommand};
 use event::Event;
 use log::LevelFilter;
 use serde_json::json;
 use simplelog::{ColorChoice, TermLogger, TerminalMode};
-use std::fs::File;
+use std::{fs::File, path::Path};
 
 fn main() -> anyhow::Result<()> {
-    let matches = clap::App::new("Rodney Bot")
+    let app = clap::App::new("Rodney Bot")
         .author("Jens Reimann <ctron@dentrassi.de>")
         .version(crate_version!())
         .arg(
             Arg::with_name("config")
+                .global(true)
                 .long("config")
                 .short("C")
                 .takes_value(true)
                 .env("RODBOT_CONFIG"),
         )
+        .arg(
+            Arg::with_name("config-dir")
+                .global(true)
+                .long("config-dir")
+                .takes_value(true)
+                .env("RODBOT_CONFIG_DIR")
+                .help("Merge the configuration from the YAML files of a directory"),
+        )
         .arg(
             Arg::with_name("verbose")
+                .global(true)
                 .long("verbose")
                 .short("v")
                 .multiple(true),
         )
         .arg(
             Arg::with_name("debug")
+                .global(true)
                 .long("debug")
                 .short("d")
                 .conflicts_with("verbose"),
         )
         .arg(
             Arg::with_name("quiet")
+                .global(true)
                 .long("quiet")
                 .short("q")
                 .conflicts_with_all(&["debug", "verbose"]),
         )
-        .get_matches();
+        .arg(
+            Arg::with_name("output")
+                .global(true)
+                .long("output")
+                .short("o")
+                .takes_value(true)
+                .possible_values(&["text", "json"])
+                .help("The format of the results"),
+        )
+        .arg(
+            Arg::with_name("strict")
+                .global(true)
+                .long("strict")
+                .help("Fail on unknown values of events, instead of ignoring them (or set RODBOT_STRICT)"),
+        )
+        .arg(
+            Arg::with_name("no-color")
+                .global(true)
+                .long("no-color")
+                .help("Disable colored output"),
+        )
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Run the configuration for the current event")
+                .arg(Arg::with_name("plan").long("plan").help(
+                    "Only decide which steps to run, and write the decisions to the outputs of the action",
+                )),
+        )
+        .subcommand(SubCommand::with_name("check").about("Validate the configuration"))
+        .subcommand(
+            event_args(SubCommand::with_name("explain"))
+                .about("Show the results of the conditions of the rules for an event, without running steps"),
+        )
+        .subcommand(
+            SubCommand::with_name("teams")
+                .about("Manage the teams of an organization")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("sync")
+                        .about("Reconcile teams, members and repository permissions")
+                        .arg(
+                            Arg::with_name("from")
+                                .long("from")
+                                .takes_value(true)
+                                .help("The teams definition file"),
+                        )
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .help("Only show the changes, don't apply them"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("branch-protection")
+                .about("Manage branch protection settings")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("sync")
+                        .about("Apply branch protection settings and detect drift")
+                        .arg(
+                            Arg::with_name("from")
+                                .long("from")
+                                .takes_value(true)
+                                .help("The branch protection settings file"),
+                        )
+                        .arg(
+                            Arg::with_name("dry-run")
+                                .long("dry-run")
+                                .help("Only report the drift, don't apply the settings"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("repo-settings")
+                .about("Manage repository settings")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    SubCommand::with_name("check")
+                        .about("Detect drift of repository settings")
+                        .arg(
+                            Arg::with_name("from")
+                                .long("from")
+                                .takes_value(true)
+                                .help("The repository settings file"),
+                        ),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("doctor")
+                .about("Check the configuration and the prerequisites for running"),
+        )
+        .subcommand(
+            SubCommand::with_name("dump")
+                .about("Print the payload of the current event")
+                .arg(
+                    Arg::with_name("redact")
+                        .long("redact")
+                        .help("Redact logins, emails and bodies, for sharing the payload"),
+                ),
+        );
+
+    #[cfg(feature = "mock")]
+    let app = app
+        .subcommand(
+            SubCommand::with_name("e2e")
+                .about("Run scenarios of events against a fake GitHub API")
+                .arg(
+                    Arg::with_name("scenarios")
+                        .default_value("scenarios")
+                        .help("A scenario file, or a directory of scenario files"),
+                ),
+        )
+        .subcommand(
+            event_args(SubCommand::with_name("simulate"))
+                .about("Decide which steps would run for an event, against a fake GitHub API"),
+        );
+
+    let matches = app.get_matches();
 
     let filter = match (
         matches.is_present("quiet"),
@@ -59,30 +226,243 @@ fn main() -> anyhow::Result<()> {
         (_, false, _) => LevelFilter::Trace,
     };
 
+    let output = Output::from_matches(&matches);
+    output.init();
+    let color = !matches.is_present("no-color") && std::env::var_os("NO_COLOR").is_none();
+
     TermLogger::init(
         filter,
         Default::default(),
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
+        // keep stdout for the results
+        match output {
+            Output::Text => TerminalMode::Mixed,
+            Output::Json => TerminalMode::Stderr,
+        },
+        if color {
+            ColorChoice::Auto
+        } else {
+            ColorChoice::Never
+        },
     )?;
 
+    event::set_strict(
+        matches.is_present("strict")
+            || std::env::var_os("RODBOT_STRICT").is_some_and(|s| s != "false"),
+    );
+
+    match matches.subcommand() {
+        ("teams", Some(matches)) => match matches.subcommand() {
+            ("sync", Some(matches)) => teams::sync_command(matches),
+            _ => unreachable!("subcommand is required"),
+        },
+        ("branch-protection", Some(matches)) => match matches.subcommand() {
+            ("sync", Some(matches)) => branch_protection::sync_command(matches),
+            _ => unreachable!("subcommand is required"),
+        },
+        ("repo-settings", Some(matches)) => match matches.subcommand() {
+            ("check", Some(matches)) => repo_settings::check_command(matches),
+            _ => unreachable!("subcommand is required"),
+        },
+        ("run", Some(matches)) => run(matches, color),
+        ("check", Some(matches)) => check(matches),
+        ("explain", Some(matches)) => explain(matches),
+        ("doctor", Some(matches)) => doctor::doctor_command(matches),
+        ("dump", Some(matches)) => dump::dump_command(matches),
+        #[cfg(feature = "mock")]
+        ("e2e", Some(matches)) => e2e::e2e_command(matches),
+        #[cfg(feature = "mock")]
+        ("simulate", Some(matches)) => simulate(matches),
+        // running is the default, as used by the action
+        _ => run(&matches, color),
+    }
+}
+
+/// The arguments of commands processing an event from a file.
+fn event_args<'a, 'b>(command: clap::App<'a, 'b>) -> clap::App<'a, 'b> {
+    command
+        .arg(
+            Arg::with_name("event")
+                .long("event")
+                .short("e")
+                .takes_value(true)
+                .required(true)
+                .help("The name of the event, like 'issue_comment'"),
+        )
+        .arg(
+            Arg::with_name("payload")
+                .required(true)
+                .help("The payload of the event, a JSON file"),
+        )
+}
+
+fn load_config(matches: &ArgMatches) -> anyhow::Result<Config> {
+    // an explicit configuration file takes precedence over the directory
+    if let (None, Some(dir)) = (matches.value_of("config"), matches.value_of("config-dir")) {
+        let (value, files) =
+            discovery::load_dir(Path::new(dir)).context("Loading configuration")?;
+        log::info!(
+            "Loaded configuration from {} file(s) in {}, hash: {:016x}",
+            files,
+            dir,
+            discovery::hash(&value)?
+        );
+        return parse_config(value);
+    }
+
     let config = matches.value_of("config").unwrap_or("rodbot.yaml");
     log::debug!("Loading configuration from: {}", config);
 
-    let event = Event::from_env().context("Failed getting event information")?;
-    let config: Config =
+    let value: serde_yaml::Value =
         serde_yaml::from_reader(File::open(config)?).context("Loading configuration")?;
+    parse_config(value)
+}
+
+/// Parse and prepare the configuration.
+fn parse_config(value: serde_yaml::Value) -> anyhow::Result<Config> {
+    version::check_min_version(&value)?;
+    template::precompile(&value).context("Compiling expressions")?;
+    let mut config: Config = serde_yaml::from_value(value).context("Loading configuration")?;
+    config.resolve_conditions()?;
+    config.resolve_step_templates()?;
+    config.index();
+    Ok(config)
+}
+
+fn check(matches: &ArgMatches) -> anyhow::Result<()> {
+    let result = load_config(matches);
+
+    Output::from_matches(matches).print(
+        &json!({
+            "valid": result.is_ok(),
+            "error": result.as_ref().err().map(|err| format!("{:#}", err)),
+        }),
+        || {
+            if result.is_ok() {
+                println!("Configuration is valid");
+            }
+        },
+    );
+
+    result.map(|_| ())
+}
+
+fn run(matches: &ArgMatches, color: bool) -> anyhow::Result<()> {
+    // parse the payload once, it is used for the event and as context
+    let name = std::env::var("GITHUB_EVENT_NAME").context("Missing GITHUB_EVENT_NAME")?;
+    let payload: serde_json::Value =
+        Event::parse_payload().context("Failed to parse event payload")?;
+    let event = Event::from_value(&name, &payload).context("Failed getting event information")?;
+    let config = load_config(matches)?;
     log::debug!("Event: {:#?}", event);
     log::debug!("Config: {:#?}", config);
 
-    config.run(&Context {
+    if config.check_for_updates {
+        match version::check_for_update(&github::GitHub::new()) {
+            Ok(Some(latest)) => log::warn!(
+                "rodbot {} is outdated, the latest version is {}",
+                version::VERSION,
+                latest
+            ),
+            Ok(None) => {}
+            Err(err) => log::info!("Failed to check for updates: {}", err),
+        }
+    }
+
+    if matches.is_present("plan") {
+        plan::start();
+    }
+
+    let result = config.run(&Context {
         payload: &event,
         context: &json!({
             "github": {
-                "event": Event::parse_payload::<serde_json::Value>()?
+                "event": payload
             }
         }),
-    })?;
+    });
+
+    if matches.is_present("plan") {
+        let decisions = plan::take();
+        if let Some(path) = std::env::var_os("GITHUB_OUTPUT") {
+            plan::write_outputs(Path::new(&path), &plan::outputs(&decisions))?;
+        }
+        Output::from_matches(matches).print(&json!({ "decisions": decisions }), || {
+            print_decisions(&decisions)
+        });
+        return result;
+    }
+
+    let summary = summary::take();
+    Output::from_matches(matches).print(&summary.to_json(), || print!("{}", summary.render(color)));
+
+    result
+}
+
+fn print_decisions(decisions: &[plan::Decision]) {
+    for decision in decisions {
+        println!("{}: {}", decision.rule, decision.step);
+        if let Some(command) = &decision.command {
+            println!("{}", command);
+        }
+    }
+}
+
+/// Load an event from a payload file, instead of the current event.
+fn load_event(matches: &ArgMatches) -> anyhow::Result<(Event, serde_json::Value)> {
+    let name = matches.value_of("event").unwrap_or_default();
+    let path = matches.value_of("payload").unwrap_or_default();
+    let payload: serde_json::Value = serde_json::from_reader(
+        File::open(path).with_context(|| format!("Failed to open: {}", path))?,
+    )
+    .context("Failed to parse event payload")?;
+    let event = Event::from_value(name, &payload).context("Failed getting event information")?;
+    Ok((event, payload))
+}
+
+/// Show the results of the conditions of the rules for an event, without running the steps.
+fn explain(matches: &ArgMatches) -> anyhow::Result<()> {
+    let (event, payload) = load_event(matches)?;
+    let config = load_config(matches)?;
+
+    plan::start();
+    explain::start();
+    let result = config.run(&Context {
+        payload: &event,
+        context: &json!({ "github": { "event": payload } }),
+    });
+    let explanations = explain::take();
+    let decisions = plan::take();
+    summary::take();
+
+    Output::from_matches(matches).print(
+        &json!({ "rules": explanations, "decisions": decisions }),
+        || {
+            print!("{}", explain::render(&explanations));
+            print_decisions(&decisions);
+        },
+    );
+
+    result
+}
+
+/// Decide which steps would run for an event, using a fake GitHub API.
+#[cfg(feature = "mock")]
+fn simulate(matches: &ArgMatches) -> anyhow::Result<()> {
+    let (event, payload) = load_event(matches)?;
+    let config = load_config(matches)?;
+
+    let _mock = mock::MockGitHub::with_fixtures().install();
+    plan::start();
+    let result = config.run(&Context {
+        payload: &event,
+        context: &json!({ "github": { "event": payload } }),
+    });
+    let decisions = plan::take();
+    summary::take();
+
+    Output::from_matches(matches).print(&json!({ "decisions": decisions }), || {
+        print_decisions(&decisions)
+    });
 
-    Ok(())
+    result
 }
@@ -0,0 +1,270 @@
+use crate::github::{set_api, Api, ApiError};
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+lazy_static! {
+    /// Only one fake API can be installed at a time.
+    static ref INSTALLED: Mutex<()> = Mutex::new(());
+}
+
+/// A fake GitHub API, for running rules and configurations without access to GitHub.
+///
+/// Paths are matched without their query, and a `*` matches any single segment. Responses
+/// registered later take precedence, unknown paths result in a "not found" error.
+#[derive(Default)]
+pub struct MockGitHub {
+    routes: Mutex<Vec<Route>>,
+    calls: Mutex<Vec<Call>>,
+}
+
+struct Route {
+    method: String,
+    path: String,
+    response: Result<Value, u16>,
+    /// Only respond to the next call.
+    once: bool,
+}
+
+/// A call to the fake API.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub method: String,
+    pub path: String,
+    pub body: Option<Value>,
+}
+
+impl MockGitHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Canned responses for the endpoints used by the conditions and steps.
+    ///
+    /// This describes issues and pull requests without comments, labels, reactions, files and
+    /// reviews, and users without membership in organizations or teams, and only `read`
+    /// permission on repositories.
+    pub fn with_fixtures() -> Self {
+        let mock = Self::new();
+
+        mock.respond(
+            "GET",
+            "rate_limit",
+            json!({"resources": {"core": {"limit": 5000, "remaining": 5000, "reset": 0}}}),
+        );
+        for path in [
+            "repos/*/*/issues",
+            "repos/*/*/issues/*/comments",
+            "repos/*/*/issues/*/labels",
+            "repos/*/*/issues/*/reactions",
+            "repos/*/*/issues/comments/*/reactions",
+            "repos/*/*/pulls/*/files",
+            "repos/*/*/pulls/*/reviews",
+            "repos/*/*/pulls/*/comments",
+        ] {
+            mock.respond("GET", path, json!([]));
+        }
+        mock.respond(
+            "GET",
+            "repos/*/*/collaborators/*/permission",
+            json!({"permission": "read"}),
+        );
+        mock.fail("GET", "orgs/*/members/*", 404);
+        mock.fail("GET", "orgs/*/teams/*/memberships/*", 404);
+
+        mock.respond("POST", "repos/*/*/issues/*/comments", json!({"id": 1}));
+        mock.respond("POST", "repos/*/*/issues/*/labels", json!([]));
+        mock.respond("DELETE", "repos/*/*/issues/*/labels/*", Value::Null);
+        mock.respond("PATCH", "repos/*/*/issues/*", json!({}));
+        mock.respond("POST", "repos/*/*/issues/*/assignees", json!({}));
+        mock.respond("DELETE", "repos/*/*/issues/*/assignees", json!({}));
+
+        mock
+    }
+
+    /// Respond to calls of the path with a value.
+    pub fn respond(&self, method: &str, path: &str, body: Value) -> &Self {
+        self.route(method, path, Ok(body), false)
+    }
+
+    /// Respond to the next call of the path with a value, like when the value changes after it.
+    pub fn respond_once(&self, method: &str, path: &str, body: Value) -> &Self {
+        self.route(method, path, Ok(body), true)
+    }
+
+    /// Fail calls of the path with an HTTP status.
+    pub fn fail(&self, method: &str, path: &str, status: u16) -> &Self {
+        self.route(method, path, Err(status), false)
+    }
+
+    fn route(&self, method: &str, path: &str, response: Result<Value, u16>, once: bool) -> &Self {
+        self.routes.lock().unwrap().push(Route {
+            method: method.into(),
+            path: path.into(),
+            response,
+            once,
+        });
+        self
+    }
+
+    /// The calls so far, in order.
+    pub fn calls(&self) -> Vec<Call> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// The calls so far, which match the method and path.
+    pub fn calls_to(&self, method: &str, path: &str) -> Vec<Call> {
+        self.calls()
+            .into_iter()
+            .filter(|call| call.method == method && matches(path, &call.path))
+            .collect()
+    }
+
+    /// Use this instead of the GitHub API, until the result is dropped.
+    pub fn install(self) -> Installed {
+        let lock = INSTALLED.lock().unwrap_or_else(|err| err.into_inner());
+        let mock = Arc::new(self);
+        let previous = set_api(mock.clone());
+        Installed {
+            mock,
+            previous: Some(previous),
+            _lock: lock,
+        }
+    }
+}
+
+impl Api for MockGitHub {
+    fn execute(
+        &self,
+        method: &str,
+        path: &str,
+        _: &str,
+        body: Option<&Value>,
+    ) -> anyhow::Result<Vec<u8>> {
+        self.calls.lock().unwrap().push(Call {
+            method: method.into(),
+            path: path.into(),
+            body: body.cloned(),
+        });
+
+        let mut routes = self.routes.lock().unwrap();
+        let response = match routes
+            .iter()
+            .rposition(|route| route.method == method && matches(&route.path, path))
+        {
+            Some(i) if routes[i].once => Some(routes.remove(i).response),
+            Some(i) => Some(routes[i].response.clone()),
+            None => None,
+        };
+        drop(routes);
+
+        match response {
+            Some(Ok(Value::Null)) => Ok(Vec::new()),
+            Some(Ok(value)) => Ok(serde_json::to_vec(&value)?),
+            Some(Err(status)) => Err(error(status, method, path)),
+            None => Err(error(404, method, path)),
+        }
+    }
+}
+
+fn error(status: u16, method: &str, path: &str) -> anyhow::Error {
+    ApiError {
+        status: Some(status),
+        message: format!("{} {} (HTTP {})", method, path, status),
+    }
+    .into()
+}
+
+/// Check if a path, ignoring its query, matches a pattern.
+pub fn matches(pattern: &str, path: &str) -> bool {
+    let path = path.split('?').next().unwrap_or_default();
+    let mut segments = path.split('/');
+    pattern.split('/').all(|expected| match segments.next() {
+        Some(segment) => expected == "*" || expected == segment,
+        None => false,
+    }) && segments.next().is_none()
+}
+
+/// An installed fake API, which restores the previous API when dropped.
+pub struct Installed {
+    mock: Arc<MockGitHub>,
+    previous: Option<Arc<dyn Api>>,
+    _lock: MutexGuard<'static, ()>,
+}
+
+impl Deref for Installed {
+    type Target = MockGitHub;
+
+    fn deref(&self) -> &Self::Target {
+        &self.mock
+    }
+}
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            set_api(previous);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::github::GitHub;
+
+    #[test]
+    fn test_matches() {
+        assert!(matches("repos/*/*/issues/*", "repos/ctron/rodbot/issues/1"));
+        assert!(matches(
+            "repos/*/*/issues/*/comments",
+            "repos/ctron/rodbot/issues/1/comments?per_page=100&page=1"
+        ));
+        assert!(!matches(
+            "repos/*/*/issues/*",
+            "repos/ctron/rodbot/issues/1/comments"
+        ));
+        assert!(!matches(
+            "repos/*/*/issues/*/comments",
+            "repos/ctron/rodbot/issues/1"
+        ));
+    }
+
+    #[test]
+    fn test_mock() -> anyhow::Result<()> {
+        let mock = MockGitHub::with_fixtures().install();
+        mock.respond(
+            "GET",
+            "repos/*/*/collaborators/ctron/permission",
+            json!({"permission": "admin"}),
+        );
+
+        let github = GitHub::new();
+        let permission: Value = github.get("repos/ctron/rodbot/collaborators/ctron/permission")?;
+        assert_eq!(permission["permission"], "admin");
+        let permission: Value = github.get("repos/ctron/rodbot/collaborators/other/permission")?;
+        assert_eq!(permission["permission"], "read");
+        assert!(github
+            .get_optional::<Value>("orgs/ctron/members/other")?
+            .is_none());
+        assert!(github.get::<Value>("repos/ctron/rodbot").is_err());
+
+        github.post(
+            "repos/ctron/rodbot/issues/1/comments",
+            &json!({"body": "Hello"}),
+        )?;
+        assert_eq!(
+            mock.calls_to("POST", "repos/*/*/issues/*/comments"),
+            vec![Call {
+                method: "POST".into(),
+                path: "repos/ctron/rodbot/issues/1/comments".into(),
+                body: Some(json!({"body": "Hello"})),
+            }]
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use crate::{config::Normalize, event::Event};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    static ref HTML_COMMENT: Regex = Regex::new(r"(?s)<!--.*?-->").unwrap();
+}
+
+/// Characters without width, which some clients add, like the byte order mark.
+const ZERO_WIDTH: &[char] = &['\u{200b}', '\u{200c}', '\u{200d}', '\u{2060}', '\u{feff}'];
+
+/// Normalize a body, with the built-in normalizers followed by the custom ones.
+pub fn normalize(body: &str, config: &Normalize) -> String {
+    let mut body = body.to_string();
+    if config.builtin {
+        body = body.replace("\r\n", "\n").replace('\r', "\n");
+        body = HTML_COMMENT.replace_all(&body, "").into_owned();
+        body.retain(|c| !ZERO_WIDTH.contains(&c));
+    }
+    for replace in &config.replace {
+        body = replace
+            .pattern
+            .0
+            .replace_all(&body, replace.with.as_str())
+            .into_owned();
+    }
+    body
+}
+
+/// Normalize the bodies of comments and reviews of an event, `None` if nothing changed.
+///
+/// The event is only copied when the body changes.
+pub fn event(event: &Event, config: &Normalize) -> Option<Event> {
+    let body = match event {
+        Event::IssueComment(payload) => &payload.comment.body,
+        Event::PullRequestReviewComment(payload) => &payload.comment.body,
+        Event::DiscussionComment(payload) => &payload.comment.body,
+        Event::PullRequestReview(payload) => payload.review.body.as_ref()?,
+        _ => return None,
+    };
+
+    let normalized = normalize(body, config);
+    if normalized == *body {
+        return None;
+    }
+    log::debug!("Normalized body: {:?}", normalized);
+
+    let mut event = event.clone();
+    match &mut event {
+        Event::IssueComment(payload) => payload.comment.body = normalized,
+        Event::PullRequestReviewComment(payload) => payload.comment.body = normalized,
+        Event::DiscussionComment(payload) => payload.comment.body = normalized,
+        Event::PullRequestReview(payload) => payload.review.body = Some(normalized),
+        _ => unreachable!("only events with a body are normalized"),
+    }
+    Some(event)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{Pattern, Replace};
+
+    #[test]
+    fn test_normalize() -> anyhow::Result<()> {
+        let config = Normalize::default();
+        assert_eq!(
+            normalize(
+                "<!-- template -->\r\n\u{200b}/retest\r\n<!--\nhidden\n-->done",
+                &config
+            ),
+            "\n/retest\ndone"
+        );
+
+        let config = Normalize {
+            builtin: false,
+            replace: vec![Replace {
+                pattern: Pattern(Regex::new(r"^> .*\n")?),
+                with: "".into(),
+            }],
+        };
+        assert_eq!(
+            normalize("> quoted\n/hold\u{200b}", &config),
+            "/hold\u{200b}"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_event() -> anyhow::Result<()> {
+        let mut payload: crate::event::IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let config = Normalize::default();
+
+        assert!(event(&Event::IssueComment(payload.clone()), &config).is_none());
+
+        payload.comment.body = "\u{feff}/test".into();
+        match event(&Event::IssueComment(payload), &config) {
+            Some(Event::IssueComment(payload)) => assert_eq!(payload.comment.body, "/test"),
+            other => panic!("Unexpected event: {:?}", other),
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,49 @@
+use serde_json::Value;
+use std::{
+    io::Write,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+/// Send the output of commands to stderr, keeping stdout for JSON results.
+static COMMANDS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Where the output of commands run by steps goes.
+pub fn commands() -> Box<dyn Write> {
+    match COMMANDS_TO_STDERR.load(Ordering::Relaxed) {
+        true => Box::new(std::io::stderr()),
+        false => Box::new(std::io::stdout()),
+    }
+}
+
+/// The format of the results of a command.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Output {
+    #[default]
+    Text,
+    Json,
+}
+
+impl Output {
+    pub fn from_matches(matches: &clap::ArgMatches) -> Self {
+        match matches.value_of("output") {
+            Some("json") => Self::Json,
+            _ => Self::Text,
+        }
+    }
+
+    /// Use this format for the results of the current process.
+    pub fn init(self) {
+        COMMANDS_TO_STDERR.store(self == Self::Json, Ordering::Relaxed);
+    }
+
+    /// Print the result, either as JSON or using the text renderer.
+    pub fn print<F>(self, json: &Value, text: F)
+    where
+        F: FnOnce(),
+    {
+        match self {
+            Self::Text => text(),
+            Self::Json => println!("{}", serde_json::to_string_pretty(json).unwrap_or_default()),
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use crate::summary;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use serde_json::json;
+use std::{fs::OpenOptions, io::Write, path::Path, sync::Mutex};
+
+lazy_static! {
+    /// The decisions, when only planning instead of running the steps.
+    static ref PLAN: Mutex<Option<Vec<Decision>>> = Mutex::new(None);
+}
+
+/// A step rodbot decided to run.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct Decision {
+    pub rule: String,
+    pub step: String,
+    /// The rendered command, of `run` steps.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// Only plan, recording the steps instead of running them.
+pub fn start() {
+    *PLAN.lock().unwrap() = Some(Vec::new());
+}
+
+pub fn is_active() -> bool {
+    PLAN.lock().unwrap().is_some()
+}
+
+/// Record a step of the current rule.
+pub fn record(step: &str, command: Option<String>) {
+    let rule = summary::current()
+        .rules
+        .last()
+        .map(|rule| rule.name.clone())
+        .unwrap_or_default();
+    if let Some(plan) = PLAN.lock().unwrap().as_mut() {
+        plan.push(Decision {
+            rule,
+            step: step.to_string(),
+            command,
+        });
+    }
+}
+
+/// Take the decisions, and stop planning.
+pub fn take() -> Vec<Decision> {
+    PLAN.lock().unwrap().take().unwrap_or_default()
+}
+
+/// The decisions as outputs of an action.
+pub fn outputs(decisions: &[Decision]) -> Vec<(&'static str, String)> {
+    let mut rules = Vec::<&str>::new();
+    for decision in decisions {
+        if !rules.contains(&decision.rule.as_str()) {
+            rules.push(&decision.rule);
+        }
+    }
+
+    vec![
+        ("matched", (!decisions.is_empty()).to_string()),
+        ("rules", json!(rules).to_string()),
+        ("decisions", json!(decisions).to_string()),
+        (
+            "commands",
+            decisions
+                .iter()
+                .filter_map(|d| d.command.as_deref())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        ),
+    ]
+}
+
+/// Append outputs to a file, in the format of `GITHUB_OUTPUT`.
+pub fn write_outputs(path: &Path, outputs: &[(&str, String)]) -> anyhow::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for (name, value) in outputs {
+        // values may span multiple lines, so use a delimiter which is not part of the value
+        let mut delimiter = "RODBOT_EOF".to_string();
+        while value.lines().any(|line| line == delimiter) {
+            delimiter.push('_');
+        }
+        writeln!(file, "{}<<{}\n{}\n{}", name, delimiter, value, delimiter)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_outputs() -> anyhow::Result<()> {
+        let decisions = vec![
+            Decision {
+                rule: "release".into(),
+                step: "run".into(),
+                command: Some("echo 1\nRODBOT_EOF".into()),
+            },
+            Decision {
+                rule: "release".into(),
+                step: "comment".into(),
+                command: None,
+            },
+        ];
+        let outputs = outputs(&decisions);
+        assert_eq!(outputs[0], ("matched", "true".into()));
+        assert_eq!(outputs[1], ("rules", r#"["release"]"#.into()));
+
+        let path = std::env::temp_dir().join(format!("rodbot-outputs-{}", std::process::id()));
+        write_outputs(&path, &outputs[3..])?;
+        let written = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path)?;
+        assert_eq!(
+            written?,
+            "commands<<RODBOT_EOF_\necho 1\nRODBOT_EOF\nRODBOT_EOF_\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,216 @@
+use crate::{
+    github::GitHub,
+    output::Output,
+    report::{track, IssueReport},
+};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::File;
+
+/// Declared repository settings for a set of repositories.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct RepoSettingsConfig {
+    pub rules: Vec<Rule>,
+    /// Report drift using a tracking issue.
+    #[serde(default)]
+    pub report: Option<IssueReport>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Rule {
+    /// Repositories (`owner/name`) the rule applies to.
+    pub repositories: Vec<String>,
+    #[serde(flatten)]
+    pub settings: Settings,
+}
+
+/// Repository settings. Settings which are missing are not checked.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq)]
+pub struct Settings {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub topics: Option<Vec<String>>,
+    #[serde(default)]
+    pub allow_merge_commit: Option<bool>,
+    #[serde(default)]
+    pub allow_squash_merge: Option<bool>,
+    #[serde(default)]
+    pub allow_rebase_merge: Option<bool>,
+    #[serde(default)]
+    pub delete_branch_on_merge: Option<bool>,
+    #[serde(default)]
+    pub vulnerability_alerts: Option<bool>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct ApiRepository {
+    description: Option<String>,
+    #[serde(default)]
+    topics: Vec<String>,
+    allow_merge_commit: Option<bool>,
+    allow_squash_merge: Option<bool>,
+    allow_rebase_merge: Option<bool>,
+    delete_branch_on_merge: Option<bool>,
+}
+
+/// Compare the current settings with the desired ones.
+pub fn drift(desired: &Settings, current: &Settings) -> Vec<String> {
+    let mut result = Vec::new();
+
+    let mut compare = |name: &str, desired: Option<String>, current: Option<String>| {
+        if let Some(desired) = desired {
+            let current = current.unwrap_or_else(|| "unknown".into());
+            if desired != current {
+                result.push(format!("{}: {} (expected: {})", name, current, desired));
+            }
+        }
+    };
+
+    let string = |value: &Option<String>| value.clone().map(|v| format!("{:?}", v));
+    let flag = |value: &Option<bool>| value.map(|v| v.to_string());
+    let topics = |value: &Option<Vec<String>>| {
+        value.as_ref().map(|topics| {
+            let mut topics = topics.clone();
+            topics.sort();
+            format!("{:?}", topics)
+        })
+    };
+
+    compare(
+        "description",
+        string(&desired.description),
+        string(&current.description).or_else(|| Some("none".into())),
+    );
+    compare("topics", topics(&desired.topics), topics(&current.topics));
+    compare(
+        "allow merge commit",
+        flag(&desired.allow_merge_commit),
+        flag(&current.allow_merge_commit),
+    );
+    compare(
+        "allow squash merge",
+        flag(&desired.allow_squash_merge),
+        flag(&current.allow_squash_merge),
+    );
+    compare(
+        "allow rebase merge",
+        flag(&desired.allow_rebase_merge),
+        flag(&current.allow_rebase_merge),
+    );
+    compare(
+        "delete branch on merge",
+        flag(&desired.delete_branch_on_merge),
+        flag(&current.delete_branch_on_merge),
+    );
+    compare(
+        "vulnerability alerts",
+        flag(&desired.vulnerability_alerts),
+        flag(&current.vulnerability_alerts),
+    );
+
+    result
+}
+
+fn fetch(github: &GitHub, repository: &str) -> anyhow::Result<Settings> {
+    let repo: ApiRepository = github.get(&format!("repos/{}", repository))?;
+    // responds with "204 No Content" if enabled, and "404 Not Found" otherwise
+    let vulnerability_alerts = github
+        .get_optional::<serde_json::Value>(&format!("repos/{}/vulnerability-alerts", repository))?
+        .is_some();
+
+    Ok(Settings {
+        description: repo.description,
+        topics: Some(repo.topics),
+        allow_merge_commit: repo.allow_merge_commit,
+        allow_squash_merge: repo.allow_squash_merge,
+        allow_rebase_merge: repo.allow_rebase_merge,
+        delete_branch_on_merge: repo.delete_branch_on_merge,
+        vulnerability_alerts: Some(vulnerability_alerts),
+    })
+}
+
+/// Drift of the settings of a repository.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct RepositoryDrift {
+    pub repository: String,
+    pub drift: String,
+}
+
+/// Check the repositories for drift, and report it.
+pub fn check(github: &GitHub, config: &RepoSettingsConfig) -> anyhow::Result<Vec<RepositoryDrift>> {
+    let mut result = Vec::new();
+
+    for rule in &config.rules {
+        for repository in &rule.repositories {
+            let current = fetch(github, repository)?;
+            result.extend(drift(&rule.settings, &current).into_iter().map(|drift| {
+                RepositoryDrift {
+                    repository: repository.clone(),
+                    drift,
+                }
+            }));
+        }
+    }
+
+    if let Some(report) = &config.report {
+        let findings = result
+            .iter()
+            .map(|d| format!("`{}`: {}", d.repository, d.drift))
+            .collect::<Vec<_>>();
+        track(github, report, &findings)?;
+    }
+
+    Ok(result)
+}
+
+pub fn check_command(matches: &clap::ArgMatches) -> anyhow::Result<()> {
+    let from = matches.value_of("from").unwrap_or("repo-settings.yaml");
+    log::debug!("Loading repository settings from: {}", from);
+
+    let config: RepoSettingsConfig =
+        serde_yaml::from_reader(File::open(from)?).context("Loading repository settings")?;
+
+    let drift = check(&GitHub::new(), &config)?;
+
+    Output::from_matches(matches).print(&json!({ "drift": drift }), || {
+        for d in &drift {
+            println!("{}: {}", d.repository, d.drift);
+        }
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_drift() {
+        let desired = Settings {
+            topics: Some(vec!["iot".into(), "rust".into()]),
+            allow_merge_commit: Some(false),
+            vulnerability_alerts: Some(true),
+            ..Default::default()
+        };
+
+        let current = Settings {
+            description: Some("Some repository".into()),
+            topics: Some(vec!["rust".into(), "iot".into()]),
+            allow_merge_commit: Some(true),
+            allow_squash_merge: Some(true),
+            vulnerability_alerts: Some(false),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            drift(&desired, &current),
+            vec![
+                "allow merge commit: true (expected: false)",
+                "vulnerability alerts: false (expected: true)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,69 @@
+use crate::github::GitHub;
+use serde::Deserialize;
+use serde_json::json;
+
+/// Where to report drift to.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct IssueReport {
+    /// The repository (`owner/name`) to open the tracking issue in.
+    pub repository: String,
+    /// The title of the tracking issue, which is also used to find an existing issue.
+    pub title: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TrackingIssue {
+    number: u64,
+    title: String,
+    pull_request: Option<serde_json::Value>,
+}
+
+/// Create, update or close the tracking issue.
+///
+/// If there are findings, the open issue with the same title gets updated, or a new one gets
+/// created. Without findings, an open issue gets closed.
+pub fn track(github: &GitHub, report: &IssueReport, findings: &[String]) -> anyhow::Result<()> {
+    let existing = github
+        .get_all::<TrackingIssue>(&format!("repos/{}/issues?state=open", report.repository))?
+        .into_iter()
+        .find(|issue| issue.pull_request.is_none() && issue.title == report.title);
+
+    let body = findings
+        .iter()
+        .map(|f| format!("* {}", f))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    match (existing, findings.is_empty()) {
+        (Some(issue), true) => {
+            log::info!("Closing tracking issue #{}", issue.number);
+            github.patch(
+                &format!("repos/{}/issues/{}", report.repository, issue.number),
+                &json!({"state": "closed"}),
+            )?;
+        }
+        (Some(issue), false) => {
+            log::info!("Updating tracking issue #{}", issue.number);
+            github.patch(
+                &format!("repos/{}/issues/{}", report.repository, issue.number),
+                &json!({ "body": body }),
+            )?;
+        }
+        (None, false) => {
+            log::info!("Creating tracking issue: {}", report.title);
+            github.post(
+                &format!("repos/{}/issues", report.repository),
+                &json!({
+                    "title": report.title,
+                    "body": body,
+                    "labels": report.labels,
+                }),
+            )?;
+        }
+        (None, true) => {}
+    }
+
+    Ok(())
+}
@@ -1,16 +1,58 @@
 use crate::{
-    config::{Config, IfIssueComment, OnCommon, OnIssueComment, Step},
-    event::{CommonEvent, Event, IssueCommentEvent},
+    bootstrap::bootstrap,
+    changes::{
+        added_lines_match, approvals, changed_files, components, head_sha, is_mergeable,
+        pr_smaller_than, pull_request, touches,
+    },
+    checks::{checks_passed, files_check, large_files_check},
+    clock::is_within,
+    command::{resolve_repository, SlashCommand},
+    config::{
+        Config, Glob, IfCheckRun, IfCheckSuite, IfCommon, IfDiscussion, IfDiscussionComment,
+        IfIssue, IfIssueComment, IfLabel, IfMilestone, IfOrganization, IfPullRequest,
+        IfPullRequestReview, IfPullRequestReviewComment, IfPush, IfRaw, IfRef, IfRelease,
+        IfRepositoryDispatch, IfStatus, IfWorkflowDispatch, IfWorkflowRun, OnCheckRun,
+        OnCheckSuite, OnCommon, OnCreate, OnDelete, OnDiscussion, OnDiscussionComment, OnIssue,
+        OnIssueComment, OnLabel, OnMilestone, OnOrganization, OnPullRequest, OnPullRequestReview,
+        OnPullRequestReviewComment, OnPush, OnRaw, OnRelease, OnRepositoryDispatch, OnSchedule,
+        OnStatus, OnWorkflowDispatch, OnWorkflowRun, ReactionsOn, Step, SuggestChange, Target, Use,
+    },
+    dependencies::review,
+    emoji::{self, emoji},
+    event::{
+        CheckRunEvent, CheckSuiteEvent, CommonEvent, DiscussionCommentEvent, DiscussionEvent,
+        Event, IssueCommentEvent, IssueState, IssuesEvent, LabelEvent, MilestoneEvent, PullRequest,
+        PullRequestEvent, PullRequestReviewCommentEvent, PullRequestReviewEvent, PushEvent,
+        RawEvent, RefEvent, ReleaseEvent, Repository, RepositoryDispatchEvent, Sender, StatusEvent,
+        WorkflowDispatchEvent, WorkflowRunEvent,
+    },
+    explain, failures,
+    github::GitHub,
+    links::check_links,
+    normalize, output, plan,
+    scope::Scope,
+    search::{pace, search_issues},
+    spelling::spell_check,
+    steps::{
+        add_labels, all_comments, approve, assign, close, comment_once, create_issue,
+        dismiss_reviews, dispatch_workflow, lock, mark_comment, merge, react, reactions,
+        remove_label, reopen, repository, repository_dispatch, request_review, set_milestone,
+        suggest_change, unassign, unlock, IssueRef, EXECUTED_MARKER,
+    },
+    suggestions::apply_suggestions,
+    summary,
+    teams::{has_permission, is_org_member, is_team_member},
+    template,
+    threads::resolve_threads,
+};
+use serde_json::{json, Value};
+use std::{
+    collections::BTreeMap,
+    fmt::Debug,
+    ops::Deref,
+    process::{Command, Stdio},
+    time::{Duration, Instant},
 };
-use jsonpath::Selector;
-use lazy_static::lazy_static;
-use regex::{Captures, Regex};
-use serde_json::{Map, Value};
-use std::{borrow::Cow, process::Command};
-
-lazy_static! {
-    static ref RE: Regex = Regex::new(r#"\$\{\{(.*?)\}\}"#).unwrap();
-}
 
 pub trait Runner<'c> {
     type Payload;
@@ -18,35 +60,361 @@ pub trait Runner<'c> {
     fn run(&self, payload: &Self::Payload) -> anyhow::Result<()>;
 }
 
-pub trait Eval {
+pub trait Eval<'p> {
     type Payload;
 
     fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool>;
+
+    /// The cost of evaluating, for checking the cheap conditions first.
+    fn cost(&self) -> Cost {
+        Cost::Local
+    }
+}
+
+/// The cost of evaluating a condition, ordered from cheap to expensive.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Cost {
+    /// Only uses the event payload.
+    Local,
+    /// Makes a single call to the GitHub API.
+    Api,
+    /// Fetches lists, which may take several calls to the GitHub API.
+    Pages,
 }
 
-pub struct Context<'c, T> {
+/// An event, and the context of the expressions of its rules.
+pub struct Context<'c, T, C = Value> {
     pub payload: &'c T,
-    pub context: &'c serde_json::Value,
+    pub context: &'c C,
+}
+
+impl<T, C> Deref for Context<'_, T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.payload
+    }
 }
 
 impl<'c> Runner<'c> for Config {
     type Payload = Context<'c, Event>;
 
     fn run(&self, context: &Self::Payload) -> anyhow::Result<()> {
+        summary::event(context.payload.name());
+        failures::set_policy(self.failures.clone());
+
+        let normalized = normalize::event(context.payload, &self.normalize);
+        let payload = normalized.as_ref().unwrap_or(context.payload);
+
+        let mut scope = Scope::new(context.context);
+        self.prepare(payload, &mut scope)?;
+        let context = &Context {
+            payload,
+            context: &scope,
+        };
+
         match &context.payload {
             Event::IssueComment(payload) => {
-                if let Some(runner) = &self.on.issue_comment {
+                if let Some(rules) = &self.on.issue_comment {
+                    // explain all rules, including the ones with a different command
+                    let candidates = match explain::is_active() {
+                        true => (0..rules.len()).collect(),
+                        false => self.index().issue_comment.candidates(&payload.comment.body),
+                    };
+                    log::debug!("Evaluating {} of {} rule(s)", candidates.len(), rules.len());
+                    for i in candidates {
+                        rules[i].run(&Context {
+                            context: context.context,
+                            payload,
+                        })?;
+                    }
+                }
+            }
+            Event::Issues(payload) => {
+                if let Some(runner) = &self.on.issue {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::PullRequest(payload) => {
+                // the checks are not steps, so they can't be planned
+                if let Some(check) = &self.checks.files {
+                    if matches!(
+                        payload.action.as_str(),
+                        "opened" | "synchronize" | "reopened"
+                    ) && !plan::is_active()
+                    {
+                        files_check(
+                            &GitHub::new(),
+                            check,
+                            &payload.repository.full_name,
+                            payload.number,
+                            &payload.pull_request.head.sha,
+                        )?;
+                    }
+                }
+                if let Some(check) = &self.checks.large_files {
+                    if matches!(
+                        payload.action.as_str(),
+                        "opened" | "synchronize" | "reopened"
+                    ) && !plan::is_active()
+                    {
+                        large_files_check(
+                            &GitHub::new(),
+                            check,
+                            &IssueRef {
+                                repository: payload.repository.full_name.clone(),
+                                number: payload.number,
+                            },
+                            &payload.pull_request.head.sha,
+                        )?;
+                    }
+                }
+                if let Some(runner) = &self.on.pull_request {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Push(payload) => {
+                if let Some(runner) = &self.on.push {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::PullRequestReview(payload) => {
+                if let Some(runner) = &self.on.pull_request_review {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::PullRequestReviewComment(payload) => {
+                if let Some(rules) = &self.on.pull_request_review_comment {
+                    let candidates = self
+                        .index()
+                        .pull_request_review_comment
+                        .candidates(&payload.comment.body);
+                    log::debug!("Evaluating {} of {} rule(s)", candidates.len(), rules.len());
+                    for i in candidates {
+                        rules[i].run(&Context {
+                            context: context.context,
+                            payload,
+                        })?;
+                    }
+                }
+            }
+            Event::WorkflowDispatch(payload) => {
+                if let Some(runner) = &self.on.workflow_dispatch {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Schedule => {
+                if let Some(runner) = &self.on.schedule {
+                    runner.run(context.context)?;
+                }
+            }
+            Event::RepositoryDispatch(payload) => {
+                if let Some(runner) = &self.on.repository_dispatch {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Release(payload) => {
+                if let Some(runner) = &self.on.release {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Discussion(payload) => {
+                if let Some(runner) = &self.on.discussion {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::DiscussionComment(payload) => {
+                if let Some(rules) = &self.on.discussion_comment {
+                    let candidates = self
+                        .index()
+                        .discussion_comment
+                        .candidates(&payload.comment.body);
+                    log::debug!("Evaluating {} of {} rule(s)", candidates.len(), rules.len());
+                    for i in candidates {
+                        rules[i].run(&Context {
+                            context: context.context,
+                            payload,
+                        })?;
+                    }
+                }
+            }
+            Event::Label(payload) => {
+                if let Some(runner) = &self.on.label {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Milestone(payload) => {
+                if let Some(runner) = &self.on.milestone {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::CheckRun(payload) => {
+                if let Some(runner) = &self.on.check_run {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::CheckSuite(payload) => {
+                if let Some(runner) = &self.on.check_suite {
                     runner.run(&Context {
                         context: context.context,
                         payload,
                     })?;
                 }
             }
+            Event::Status(payload) => {
+                if let Some(runner) = &self.on.status {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Create(payload) => {
+                if let Some(runner) = &self.on.create {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Delete(payload) => {
+                if let Some(runner) = &self.on.delete {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::WorkflowRun(payload) => {
+                if let Some(runner) = &self.on.workflow_run {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Other(payload) => {
+                if let Some(runner) = self.on.other.get(&payload.name) {
+                    runner.run(&Context {
+                        context: context.context,
+                        payload,
+                    })?;
+                }
+            }
+            Event::Organization(_)
+            | Event::Repository(_)
+            | Event::Team(_)
+            | Event::Membership(_) => {
+                if let Some(runner) = &self.on.organization {
+                    runner.run(context)?;
+                }
+            }
         }
+
+        if let Some(runner) = &self.on.any {
+            runner.run(&Context {
+                context: context.context,
+                payload: &RawEvent {
+                    name: context.payload.name().to_string(),
+                    payload: context.context["github"]["event"].clone(),
+                },
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Config {
+    /// Add the changed files and components of a pull request to the context, if required.
+    fn prepare(&self, event: &Event, scope: &mut Scope) -> anyhow::Result<()> {
+        fn has_paths<T>(rules: &Option<Vec<T>>, paths: impl Fn(&T) -> bool) -> bool {
+            rules.iter().flatten().any(paths)
+        }
+
+        let paths = match event {
+            Event::IssueComment(_) => has_paths(&self.on.issue_comment, |r| r.paths.is_some()),
+            Event::PullRequest(_) => has_paths(&self.on.pull_request, |r| r.paths.is_some()),
+            Event::PullRequestReview(_) => {
+                has_paths(&self.on.pull_request_review, |r| r.paths.is_some())
+            }
+            Event::PullRequestReviewComment(_) => {
+                has_paths(&self.on.pull_request_review_comment, |r| r.paths.is_some())
+            }
+            _ => false,
+        };
+
+        if !paths && self.components.is_empty() {
+            return Ok(());
+        }
+
+        let (repository, number) = match event.pull_request() {
+            Some(pr) => pr,
+            None => return Ok(()),
+        };
+
+        let files = changed_files(&GitHub::new(), repository, number)?;
+        let components = components(&self.components, &files)?;
+        log::debug!("Changed components: {:?}", components);
+
+        if components.len() == 1 {
+            scope.insert("component", json!(components.iter().next()));
+        }
+        scope.insert("components", json!(components));
+        scope.insert("changed_files", json!(files));
+
         Ok(())
     }
 }
 
+/// Check if the pull request changes any of the paths. Rules without paths always match.
+fn matches_paths(paths: &Option<Vec<Glob>>, context: &Scope) -> bool {
+    let paths = match paths {
+        Some(paths) => paths,
+        None => return true,
+    };
+
+    let files = context["changed_files"]
+        .as_array()
+        .map(|files| files.iter().filter_map(Value::as_str).collect::<Vec<_>>())
+        .unwrap_or_default();
+
+    touches(paths, &files)
+}
+
 impl<'c, T, P> Runner<'c> for Vec<T>
 where
     T: Runner<'c, Payload = P>,
@@ -62,22 +430,71 @@ where
 }
 
 impl<'c> Runner<'c> for OnIssueComment {
-    type Payload = Context<'c, IssueCommentEvent>;
+    type Payload = Context<'c, IssueCommentEvent, Scope<'c>>;
 
     fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
-        for i in &self.r#if {
-            if !i.eval(payload.payload)? {
-                log::debug!("Test rejected, aborting!");
-                return Ok(());
-            }
+        // the payload as it was received, not normalized
+        let event = &payload.context["github"]["event"];
+
+        if !accepts_rule(
+            &self.common,
+            &payload.payload.action,
+            &self.r#if,
+            &Context {
+                payload: payload.payload,
+                context: event,
+            },
+        )? {
+            return Ok(());
+        }
+
+        if !matches_paths(&self.paths, payload.context) {
+            log::debug!("No matching paths changed, aborting!");
+            return Ok(());
+        }
+
+        let body = event["comment"]["body"]
+            .as_str()
+            .unwrap_or(&payload.payload.comment.body);
+        if self.mark.is_some() && body.contains(EXECUTED_MARKER) {
+            log::debug!("Command was already executed, aborting!");
+            return Ok(());
         }
 
+        let command = SlashCommand::parse(&payload.payload.comment.body);
+        let current = &payload.payload.repository.full_name;
+        let repository = match &self.target {
+            Some(target) => match resolve_target(target, command.as_ref(), current) {
+                Some(repository) => repository,
+                None => {
+                    log::info!("Target repository is not allowed, aborting!");
+                    return Ok(());
+                }
+            },
+            None => current.clone(),
+        };
+
+        let mut context = payload
+            .context
+            .with("command", serde_json::to_value(&command)?);
+        context.insert("repository", Value::String(repository));
+
         // running steps
 
-        self.common.run(&Context {
-            context: payload.context,
-            payload: &payload.payload.common,
-        })?;
+        run_steps(&self.common, &context)?;
+
+        if let (Some(mark), false) = (self.mark, plan::is_active()) {
+            // the command did run, failing to mark it is no failure of the rule
+            if let Err(err) = mark_comment(
+                &GitHub::new(),
+                current,
+                payload.payload.comment.id,
+                body,
+                mark,
+            ) {
+                log::warn!("Failed to mark the comment of the command: {}", err);
+            }
+        }
 
         // done
 
@@ -85,157 +502,1592 @@ impl<'c> Runner<'c> for OnIssueComment {
     }
 }
 
-impl<'c> Runner<'c> for OnCommon {
-    type Payload = Context<'c, CommonEvent>;
+/// Implement the runner of rules which only check the action, their conditions, and optionally
+/// the changed paths, before running their steps.
+macro_rules! event_runner {
+    ($($on:ty => $event:ty $(, $paths:ident)?);* $(;)?) => {
+        $(
+            impl<'c> Runner<'c> for $on {
+                type Payload = Context<'c, $event, Scope<'c>>;
 
-    fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
-        self.steps.run(payload.context)?;
-        Ok(())
-    }
+                fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
+                    if !accepts_rule(
+                        &self.common,
+                        &payload.payload.action,
+                        &self.r#if,
+                        payload.payload,
+                    )? {
+                        return Ok(());
+                    }
+
+                    $(
+                        if !matches_paths(&self.$paths, payload.context) {
+                            log::debug!("No matching paths changed, aborting!");
+                            return Ok(());
+                        }
+                    )?
+
+                    run_steps(&self.common, payload.context)
+                }
+            }
+        )*
+    };
 }
 
-impl Runner<'_> for Step {
-    type Payload = serde_json::Value;
+event_runner!(
+    OnIssue => IssuesEvent;
+    OnPullRequest => PullRequestEvent, paths;
+    OnPush => PushEvent;
+    OnPullRequestReview => PullRequestReviewEvent, paths;
+    OnPullRequestReviewComment => PullRequestReviewCommentEvent, paths;
+    OnRepositoryDispatch => RepositoryDispatchEvent;
+    OnRelease => ReleaseEvent;
+    OnDiscussion => DiscussionEvent;
+    OnLabel => LabelEvent;
+    OnMilestone => MilestoneEvent;
+    OnCheckRun => CheckRunEvent;
+    OnCheckSuite => CheckSuiteEvent;
+    OnStatus => StatusEvent;
+    OnCreate => RefEvent;
+    OnDelete => RefEvent;
+    OnWorkflowRun => WorkflowRunEvent;
+);
+
+impl<'c> Runner<'c> for OnWorkflowDispatch {
+    type Payload = Context<'c, WorkflowDispatchEvent, Scope<'c>>;
 
     fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
-        match self {
-            Self::Run(command) => run(command, payload)?,
+        if !accepts_rule(
+            &self.common,
+            &payload.payload.action,
+            &self.r#if,
+            payload.payload,
+        )? {
+            return Ok(());
         }
 
-        Ok(())
+        let context = payload.context.with(
+            "inputs",
+            Value::Object(payload.payload.inputs.clone().unwrap_or_default()),
+        );
+
+        run_steps(&self.common, &context)
     }
 }
 
-impl<T, P> Eval for Vec<T>
-where
-    T: Eval<Payload = P>,
-{
-    type Payload = P;
+impl<'c> Runner<'c> for OnSchedule {
+    type Payload = Scope<'c>;
 
-    /// if all checks eval to true, we return true too. Unless we have no checks at all.
-    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
-        if self.is_empty() {
-            return Ok(false);
-        }
-        for s in self {
-            if !s.eval(payload)? {
-                return Ok(false);
-            }
-        }
-        Ok(true)
+    fn run(&self, context: &Self::Payload) -> anyhow::Result<()> {
+        run_steps(&self.common, context)
     }
 }
 
-impl Eval for IfIssueComment {
-    type Payload = IssueCommentEvent;
+impl<'c> Runner<'c> for OnDiscussionComment {
+    type Payload = Context<'c, DiscussionCommentEvent, Scope<'c>>;
 
-    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
-        let r = match self {
-            Self::Not(expr) => Ok(!expr.eval(payload)?),
-            Self::And(children) => children.eval(payload), // default is and
-            Self::Or(children) => {
-                // return true if at least one check returns true. No checks means false.
-                let mut result = false;
-                for c in children {
-                    if c.eval(payload)? {
-                        result = true;
-                        break;
-                    }
-                }
-                Ok(result)
-            }
-            Self::IsPr => Ok(payload.issue.pull_request.is_some()),
-            Self::UserIs(expected) => {
-                let result = expected.contains(&payload.comment.author_association);
-                log::debug!(
-                    "UserIs({:?}) == {:?} => {}",
-                    expected,
-                    &payload.comment.author_association,
-                    result
-                );
-                Ok(result)
-            }
-            Self::UserIn(expected) => Ok(expected.contains(&payload.comment.user.login)),
-            Self::Command(expected) => is_command(expected, &payload.comment.body),
-        };
+    fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
+        if !accepts_rule(
+            &self.common,
+            &payload.payload.action,
+            &self.r#if,
+            payload.payload,
+        )? {
+            return Ok(());
+        }
 
-        log::debug!("{:?} => {:?}", self, r);
+        let command = SlashCommand::parse(&payload.payload.comment.body);
 
-        r
+        let context = payload
+            .context
+            .with("command", serde_json::to_value(&command)?);
+
+        run_steps(&self.common, &context)
     }
 }
 
-fn is_command(command: &str, body: &str) -> anyhow::Result<bool> {
-    if let Some(line) = body.lines().next() {
-        Ok(line.trim().starts_with(&format!("/{}", command)))
-    } else {
-        Ok(false)
+impl<'c> Runner<'c> for OnRaw {
+    type Payload = Context<'c, RawEvent, Scope<'c>>;
+
+    fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
+        let action = payload.payload.payload["action"]
+            .as_str()
+            .unwrap_or_default();
+        if !accepts_rule(&self.common, action, &self.r#if, payload.payload)? {
+            return Ok(());
+        }
+
+        run_steps(&self.common, payload.context)
     }
 }
 
-fn run(command: &str, context: &serde_json::Value) -> anyhow::Result<()> {
-    let context = match context {
-        Value::Object(m) => Cow::Borrowed(m),
-        _ => Cow::Owned(Map::new()),
-    };
-
-    let mut cmd = Command::new("bash");
-    cmd.arg("--noprofile")
-        .arg("--norc")
-        .arg("-e")
-        .arg("-o")
-        .arg("pipefail")
-        .arg("-c")
-        .arg(eval(command, &context)?);
+impl<'c> Runner<'c> for OnOrganization {
+    type Payload = Context<'c, Event, Scope<'c>>;
 
-    log::info!("Running: {:?}", cmd);
+    fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
+        let action = payload
+            .payload
+            .common()
+            .map(|c| c.action.as_str())
+            .unwrap_or_default();
+        if !accepts_rule(&self.common, action, &self.r#if, payload.payload)? {
+            return Ok(());
+        }
 
-    let status = cmd.status()?;
+        payload
+            .payload
+            .common()
+            .ok_or_else(|| anyhow::anyhow!("Missing event information"))?;
 
-    if !status.success() {
-        log::warn!("Failed to run command: {:?} = {:?}", cmd, status);
-        anyhow::bail!("Failed run command");
+        run_steps(&self.common, payload.context)
     }
-
-    Ok(())
 }
 
-struct JsonPathReplacer<'a> {
-    pub context: Value,
-    pub errors: &'a mut Vec<anyhow::Error>,
-}
+impl<'c> Runner<'c> for OnCommon {
+    type Payload = Context<'c, CommonEvent, Scope<'c>>;
 
-impl<'a> JsonPathReplacer<'a> {
-    pub fn new(context: &Map<String, Value>, errors: &'a mut Vec<anyhow::Error>) -> Self {
-        Self {
-            context: Value::Object(context.clone()),
-            errors,
-        }
+    fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
+        run_steps(self, payload.context)
     }
 }
 
-impl<'a> regex::Replacer for JsonPathReplacer<'a> {
-    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
-        log::debug!("Replace: {:#?}", caps);
-
-        let expr = &caps[1];
+/// Run the steps of a matching rule.
+fn run_steps(common: &OnCommon, context: &Scope) -> anyhow::Result<()> {
+    summary::rule(common.name.as_deref());
+    let result = common.steps.run(context);
 
-        match self.replace(expr) {
-            Err(err) => self.errors.push(err),
-            Ok(s) => {
-                dst.push_str(s.as_str());
-            }
-        }
+    if let Err(err) = &result {
+        failures::report(common, context, err);
     }
+
+    result
 }
 
-impl<'a> JsonPathReplacer<'a> {
+impl<'c> Runner<'c> for Step {
+    type Payload = Scope<'c>;
+
+    fn run(&self, payload: &Self::Payload) -> anyhow::Result<()> {
+        // the steps of the template are the steps of the rule, also when planning
+        if let Step::Use(config) = self {
+            return use_template(config, payload);
+        }
+
+        if plan::is_active() {
+            let command = match self {
+                Step::Run(command) => Some(eval(command, payload)?),
+                _ => None,
+            };
+            plan::record(self.name(), command);
+            return Ok(());
+        }
+
+        let start = Instant::now();
+        let usage = summary::Usage::now();
+        let result = run_step(self, payload);
+        summary::step(
+            self.name(),
+            start.elapsed(),
+            summary::Usage::now().since(&usage),
+            &result,
+        );
+        result
+    }
+}
+
+/// Evaluate the expressions of names, like labels, skipping names which evaluate to nothing.
+fn eval_names(names: &[String], context: &Scope) -> anyhow::Result<Vec<String>> {
+    let mut result = Vec::with_capacity(names.len());
+    for name in names {
+        let name = eval(name, context)?;
+        let name = name.trim();
+        if !name.is_empty() {
+            result.push(name.to_string());
+        }
+    }
+    Ok(result)
+}
+
+/// Evaluate the expressions of user names, which may be mentions like `@ctron`.
+fn eval_users(users: &[String], context: &Scope) -> anyhow::Result<Vec<String>> {
+    Ok(eval_names(users, context)?
+        .into_iter()
+        .map(|user| user.trim_start_matches('@').to_string())
+        .filter(|user| !user.is_empty())
+        .collect())
+}
+
+/// Evaluate the expressions of the strings of a value.
+fn eval_value(value: &Value, context: &Scope) -> anyhow::Result<Value> {
+    Ok(match value {
+        Value::String(text) => Value::String(eval(text, context)?),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(|v| eval_value(v, context))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(k, v)| Ok((k.clone(), eval_value(v, context)?)))
+                .collect::<anyhow::Result<_>>()?,
+        ),
+        value => value.clone(),
+    })
+}
+
+/// Run the steps of a template, with its parameters as `params`.
+fn use_template(config: &Use, payload: &Scope) -> anyhow::Result<()> {
+    let params = eval_value(&serde_json::to_value(&config.with)?, payload)?;
+    config.steps.run(&payload.with("params", params))
+}
+
+fn run_step(step: &Step, payload: &Scope) -> anyhow::Result<()> {
+    match step {
+        Step::Run(command) => run(command, payload)?,
+        Step::Bootstrap(config) => {
+            let repository = match &config.repository {
+                Some(repository) => step_repository(repository, payload)?,
+                None => repository(payload)?,
+            };
+            bootstrap(&GitHub::new(), config, &repository)?
+        }
+        Step::DependencyReview(config) => {
+            review(&GitHub::new(), config, &IssueRef::from_context(payload)?)?
+        }
+        Step::CheckLinks(config) => {
+            check_links(&GitHub::new(), config, &IssueRef::from_context(payload)?)?
+        }
+        Step::SpellCheck(config) => {
+            spell_check(&GitHub::new(), config, &IssueRef::from_context(payload)?)?
+        }
+        Step::SuggestChange(config) => {
+            let config = SuggestChange {
+                path: eval(&config.path, payload)?,
+                replacement: eval(&config.replacement, payload)?,
+                message: config
+                    .message
+                    .as_ref()
+                    .map(|message| eval(message, payload).map(|m| emoji::render(&m).into_owned()))
+                    .transpose()?,
+                ..config.clone()
+            };
+            suggest_change(&GitHub::new(), &IssueRef::from_context(payload)?, &config)?
+        }
+        Step::ApplySuggestions(config) => {
+            apply_suggestions(&GitHub::new(), config, &IssueRef::from_context(payload)?)?
+        }
+        Step::ResolveThread(config) => {
+            resolve_threads(&GitHub::new(), config, &IssueRef::from_context(payload)?)?
+        }
+        Step::AddLabels(labels) => add_labels(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            &eval_names(labels, payload)?,
+        )?,
+        Step::RemoveLabels(labels) => {
+            let github = GitHub::new();
+            let issue = IssueRef::from_context(payload)?;
+            for label in eval_names(labels, payload)? {
+                remove_label(&github, &issue, &label)?;
+            }
+        }
+        Step::Comment(config) => {
+            let issue = IssueRef::from_context(payload)?;
+            let body = emoji::render(&eval(config.body(), payload)?).into_owned();
+            match config.key() {
+                Some(key) => comment_once(&GitHub::new(), &issue, &eval(key, payload)?, &body)?,
+                None => {
+                    GitHub::new().post(
+                        &format!("{}/comments", issue.path()),
+                        &json!({ "body": body }),
+                    )?;
+                }
+            }
+        }
+        Step::Close(config) => close(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            config.reason,
+        )?,
+        Step::Reopen => reopen(&GitHub::new(), &IssueRef::from_context(payload)?)?,
+        Step::Lock(config) => lock(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            config
+                .reason
+                .as_ref()
+                .map(|reason| eval(reason, payload))
+                .transpose()?
+                .as_deref(),
+        )?,
+        Step::Unlock => unlock(&GitHub::new(), &IssueRef::from_context(payload)?)?,
+        Step::DispatchWorkflow(config) => {
+            let mut inputs = BTreeMap::new();
+            for (name, value) in &config.inputs {
+                inputs.insert(name.clone(), eval(value, payload)?);
+            }
+            let r#ref = match &config.r#ref {
+                Some(r#ref) => Some(eval(r#ref, payload)?.trim().to_string()),
+                None => payload["github"]["event"]["repository"]["default_branch"]
+                    .as_str()
+                    .map(ToString::to_string),
+            };
+            dispatch_workflow(
+                &GitHub::new(),
+                &repository(payload)?,
+                eval(&config.workflow, payload)?.trim(),
+                r#ref.as_deref(),
+                &inputs,
+            )?;
+        }
+        Step::RepositoryDispatch(config) => repository_dispatch(
+            &GitHub::new(),
+            &step_repository(&config.repo, payload)?,
+            eval(&config.event_type, payload)?.trim(),
+            &eval_value(&Value::Object(config.client_payload.clone()), payload)?,
+        )?,
+        Step::Use(config) => use_template(config, payload)?,
+        Step::CreateIssue(config) => {
+            let repository = match &config.repo {
+                Some(repo) => step_repository(repo, payload)?,
+                None => repository(payload)?,
+            };
+            create_issue(
+                &GitHub::new(),
+                &repository,
+                &eval(&config.title, payload)?,
+                config
+                    .body
+                    .as_deref()
+                    .map(|body| eval(body, payload).map(|b| emoji::render(&b).into_owned()))
+                    .transpose()?
+                    .as_deref(),
+                &eval_names(&config.labels, payload)?,
+                &eval_users(&config.assignees, payload)?,
+            )?;
+        }
+        Step::Assign(users) => assign(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            &eval_users(users, payload)?,
+        )?,
+        Step::Unassign(users) => unassign(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            &eval_users(users, payload)?,
+        )?,
+        Step::Merge(config) => merge(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            config.method,
+            config
+                .commit_title
+                .as_deref()
+                .map(|title| eval(title, payload))
+                .transpose()?
+                .as_deref(),
+            config
+                .commit_message
+                .as_deref()
+                .map(|message| eval(message, payload))
+                .transpose()?
+                .as_deref(),
+        )?,
+        Step::Approve(config) => approve(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            config
+                .body
+                .as_deref()
+                .map(|body| eval(body, payload))
+                .transpose()?
+                .as_deref(),
+        )?,
+        Step::DismissReviews(config) => {
+            let dismissed = dismiss_reviews(
+                &GitHub::new(),
+                &IssueRef::from_context(payload)?,
+                &eval(&config.message, payload)?,
+                &eval_users(&config.users, payload)?,
+            )?;
+            log::info!("Dismissed {} review(s)", dismissed);
+        }
+        Step::RequestReview(config) => {
+            // teams may be mentioned with their organization, like `@drogue-iot/reviewers`
+            let teams = eval_users(&config.teams, payload)?
+                .into_iter()
+                .map(|team| match team.split_once('/') {
+                    Some((_, slug)) => slug.to_string(),
+                    None => team,
+                })
+                .collect::<Vec<_>>();
+            request_review(
+                &GitHub::new(),
+                &IssueRef::from_context(payload)?,
+                &eval_users(&config.users, payload)?,
+                &teams,
+            )?
+        }
+        Step::React(content) => react(
+            &GitHub::new(),
+            &payload["github"]["event"],
+            &eval(content, payload)?,
+        )?,
+        Step::SetMilestone(title) => set_milestone(
+            &GitHub::new(),
+            &IssueRef::from_context(payload)?,
+            eval(title, payload)?.trim(),
+        )?,
+        Step::SearchIssues(config) => {
+            let github = GitHub::new();
+            let query = eval(&config.query, payload)?;
+            let items = search_issues(&github, &query, config.limit)?;
+            log::info!("Found {} result(s) for: {}", items.len(), query);
+
+            for (n, item) in items.into_iter().enumerate() {
+                if n > 0 {
+                    pace(&github, Duration::from_secs(config.delay))?;
+                }
+                config.for_each.run(&payload.with("item", item))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl<'p, T, P> Eval<'p> for Vec<T>
+where
+    T: Eval<'p, Payload = P> + Sync,
+    P: Sync,
+{
+    type Payload = P;
+
+    /// if all checks eval to true, we return true too. Unless we have no checks at all.
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        if self.is_empty() {
+            return Ok(false);
+        }
+        eval_all(self, payload)
+    }
+
+    fn cost(&self) -> Cost {
+        max_cost(self)
+    }
+}
+
+fn max_cost<'p, T: Eval<'p>>(children: &[T]) -> Cost {
+    children.iter().map(T::cost).max().unwrap_or(Cost::Local)
+}
+
+/// Evaluate a condition which doesn't depend on the kind of event.
+fn eval_common(
+    condition: &IfCommon,
+    repository: &Repository,
+    sender: &Sender,
+) -> anyhow::Result<bool> {
+    match condition {
+        IfCommon::IsBot => Ok(sender.is_bot()),
+        IfCommon::NotBot => Ok(!sender.is_bot()),
+        IfCommon::Within(window) => is_within(window),
+        // ignoring the case, like GitHub does
+        IfCommon::RepoIn(expected) => Ok(expected
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(&repository.full_name))),
+        IfCommon::RepoMatches(pattern) => Ok(pattern.is_match(&repository.full_name)),
+    }
+}
+
+/// Check if a rule accepts the action of the event, and its conditions match.
+fn accepts_rule<'p, T, P>(
+    common: &OnCommon,
+    action: &str,
+    conditions: &[T],
+    payload: &P,
+) -> anyhow::Result<bool>
+where
+    T: Eval<'p, Payload = P> + Sync + Debug,
+    P: Sync,
+{
+    if !common.accepts(action) {
+        log::debug!("Action not accepted, aborting!");
+        return Ok(false);
+    }
+
+    if !eval_rule(common, conditions, payload)? {
+        log::debug!("Test rejected, aborting!");
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Evaluate the conditions of a rule, recording each of them when explaining.
+fn eval_rule<'p, T, P>(common: &OnCommon, conditions: &[T], payload: &P) -> anyhow::Result<bool>
+where
+    T: Eval<'p, Payload = P> + Sync + Debug,
+    P: Sync,
+{
+    if !explain::is_active() {
+        return eval_all(conditions, payload);
+    }
+
+    let conditions = conditions
+        .iter()
+        .map(|c| match c.eval(payload) {
+            Ok(result) => explain::Condition {
+                condition: format!("{:?}", c),
+                result,
+                error: None,
+            },
+            Err(err) => explain::Condition {
+                condition: format!("{:?}", c),
+                result: false,
+                error: Some(format!("{:#}", err)),
+            },
+        })
+        .collect::<Vec<_>>();
+    let matched = conditions.iter().all(|c| c.result);
+    explain::record(common.name.as_deref(), conditions);
+
+    Ok(matched)
+}
+
+/// Return true if all checks return true. No checks means true.
+fn eval_all<'p, T, P>(children: &[T], payload: &P) -> anyhow::Result<bool>
+where
+    T: Eval<'p, Payload = P> + Sync,
+    P: Sync,
+{
+    eval_until(children, payload, false).map(|r| !r)
+}
+
+/// Return true if at least one check returns true. No checks means false.
+fn eval_any<'p, T, P>(children: &[T], payload: &P) -> anyhow::Result<bool>
+where
+    T: Eval<'p, Payload = P> + Sync,
+    P: Sync,
+{
+    eval_until(children, payload, true)
+}
+
+/// Check if any of the checks evaluates to `expected`.
+///
+/// The checks are evaluated by cost, cheapest first, stopping at the first match. Checks of the
+/// same cost which call the API are evaluated concurrently, as they mostly wait for the response.
+fn eval_until<'p, T, P>(children: &[T], payload: &P, expected: bool) -> anyhow::Result<bool>
+where
+    T: Eval<'p, Payload = P> + Sync,
+    P: Sync,
+{
+    for (cost, stage) in plan(children) {
+        if cost == Cost::Local || stage.len() < 2 {
+            for c in stage {
+                if c.eval(payload)? == expected {
+                    return Ok(true);
+                }
+            }
+            continue;
+        }
+
+        let results = std::thread::scope(|scope| {
+            stage
+                .iter()
+                .map(|c| scope.spawn(move || c.eval(payload)))
+                .collect::<Vec<_>>()
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|err| std::panic::resume_unwind(err))
+                })
+                .collect::<Vec<_>>()
+        });
+
+        // a match decides the result, even if another check failed
+        let mut error = None;
+        for result in results {
+            match result {
+                Ok(r) if r == expected => return Ok(true),
+                Ok(_) => {}
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        if let Some(err) = error {
+            return Err(err);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Group the checks by cost, cheapest first, keeping the order of checks with the same cost.
+fn plan<'p, T: Eval<'p>>(children: &[T]) -> Vec<(Cost, Vec<&T>)> {
+    let mut stages: Vec<(Cost, Vec<&T>)> = Vec::new();
+    let mut children = children.iter().map(|c| (c.cost(), c)).collect::<Vec<_>>();
+    children.sort_by_key(|(cost, _)| *cost);
+    for (cost, c) in children {
+        match stages.last_mut() {
+            Some((last, stage)) if *last == cost => stage.push(c),
+            _ => stages.push((cost, vec![c])),
+        }
+    }
+    stages
+}
+
+/// Evaluated with the payload of the event as it was received, for `expr` conditions.
+impl<'p> Eval<'p> for IfIssueComment {
+    type Payload = Context<'p, IssueCommentEvent>;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload), // default is and
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::IsPr => Ok(payload.issue.pull_request.is_some()),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => {
+                let result = expected.contains(&payload.comment.author_association);
+                log::debug!(
+                    "UserIs({:?}) == {:?} => {}",
+                    expected,
+                    &payload.comment.author_association,
+                    result
+                );
+                Ok(result)
+            }
+            Self::UserIn(expected) => Ok(expected.contains(&payload.comment.user.login)),
+            Self::UserInTeam(team) => {
+                is_team_member(&GitHub::new(), team, &payload.comment.user.login)
+            }
+            Self::UserInOrg(org) => is_org_member(&GitHub::new(), org, &payload.comment.user.login),
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.comment.user.login,
+                expected,
+            ),
+            Self::Command(expected) => is_command(expected, &payload.comment.body),
+            Self::AddedLinesMatch(pattern) => match &payload.issue.pull_request {
+                Some(_) => added_lines_match(
+                    &GitHub::new(),
+                    &payload.repository.full_name,
+                    payload.issue.number,
+                    pattern,
+                ),
+                None => Ok(false),
+            },
+            Self::HasLabel(expected) => Ok(payload
+                .issue
+                .labels
+                .iter()
+                .any(|label| expected.contains(&label.name))),
+            Self::LabelMatches(pattern) => Ok(payload
+                .issue
+                .labels
+                .iter()
+                .any(|label| pattern.is_match(&label.name))),
+            Self::BodyMatches(pattern) => Ok(pattern.is_match(&payload.comment.body)),
+            Self::TitleMatches(pattern) => Ok(pattern.is_match(&payload.issue.title)),
+            Self::IssueBodyMatches(pattern) => Ok(payload
+                .issue
+                .body
+                .as_deref()
+                .is_some_and(|body| pattern.is_match(body))),
+            Self::IsDraft => Ok(comment_pull_request(payload)?.is_some_and(|pr| pr.draft)),
+            Self::BaseBranchIs(expected) => {
+                Ok(comment_pull_request(payload)?
+                    .is_some_and(|pr| expected.contains(&pr.base.r#ref)))
+            }
+            Self::HeadBranchMatches(pattern) => {
+                Ok(comment_pull_request(payload)?
+                    .is_some_and(|pr| pattern.is_match(&pr.head.r#ref)))
+            }
+            Self::FilesMatch(globs) => match &payload.issue.pull_request {
+                Some(_) => Ok(touches(
+                    globs,
+                    &changed_files(
+                        &GitHub::new(),
+                        &payload.repository.full_name,
+                        payload.issue.number,
+                    )?,
+                )),
+                None => Ok(false),
+            },
+            Self::IsOpen => Ok(payload.issue.state == IssueState::Open),
+            Self::IsClosed => Ok(payload.issue.state == IssueState::Closed),
+            Self::IsLocked => Ok(payload.issue.locked),
+            Self::Expr(path) => {
+                let mut event = Scope::new(payload.context);
+                if path.contains("all_comments") && event["issue"]["all_comments"].is_null() {
+                    let comments = all_comments(
+                        &GitHub::new(),
+                        &IssueRef {
+                            repository: payload.repository.full_name.clone(),
+                            number: payload.issue.number,
+                        },
+                    )?;
+                    event.insert("issue.all_comments", json!(comments));
+                }
+                Ok(event.select(path)?.into_iter().any(is_truthy))
+            }
+            Self::ApprovalsAtLeast(expected) => match &payload.issue.pull_request {
+                Some(_) => Ok(approvals(
+                    &GitHub::new(),
+                    &payload.repository.full_name,
+                    payload.issue.number,
+                )? >= *expected),
+                None => Ok(false),
+            },
+            Self::IsMergeable => match &payload.issue.pull_request {
+                Some(_) => Ok(is_mergeable(
+                    &GitHub::new(),
+                    &payload.repository.full_name,
+                    payload.issue.number,
+                )?
+                .is_some()),
+                None => Ok(false),
+            },
+            Self::ChecksPassed(required) => match &payload.issue.pull_request {
+                Some(_) => {
+                    let github = GitHub::new();
+                    let repository = &payload.repository.full_name;
+                    let sha = head_sha(&github, repository, payload.issue.number)?;
+                    checks_passed(&github, repository, &sha, required)
+                }
+                None => Ok(false),
+            },
+            Self::ReactionsAtLeast(expected) => {
+                let path = match expected.on {
+                    ReactionsOn::Comment => format!(
+                        "repos/{}/issues/comments/{}",
+                        payload.repository.full_name, payload.comment.id
+                    ),
+                    ReactionsOn::Issue => format!(
+                        "repos/{}/issues/{}",
+                        payload.repository.full_name, payload.issue.number
+                    ),
+                };
+                Ok(reactions(&GitHub::new(), &path, &expected.content)? >= expected.count)
+            }
+            Self::AssignedTo(expected) => Ok(payload
+                .issue
+                .assignees
+                .iter()
+                .any(|user| expected.contains(&user.login))),
+            Self::HasAssignee => Ok(!payload.issue.assignees.is_empty()),
+            Self::MilestoneIs(expected) => Ok(payload
+                .issue
+                .milestone
+                .as_ref()
+                .is_some_and(|milestone| expected.contains(&milestone.title))),
+            Self::PrSmallerThan(limit) => match &payload.issue.pull_request {
+                Some(_) => pr_smaller_than(
+                    &GitHub::new(),
+                    &payload.repository.full_name,
+                    payload.issue.number,
+                    limit,
+                ),
+                None => Ok(false),
+            },
+            Self::NotEdited => Ok(payload.action != "edited" && !payload.comment.is_edited()),
+            Self::Ref(name) => Err(anyhow::anyhow!("Condition set '{}' is not resolved", name)),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::Expr(path) if path.contains("all_comments") => Cost::Pages,
+            Self::UserInTeam(_)
+            | Self::UserInOrg(_)
+            | Self::UserHasPermission(_)
+            | Self::IsDraft
+            | Self::BaseBranchIs(_)
+            | Self::HeadBranchMatches(_)
+            | Self::IsMergeable
+            | Self::PrSmallerThan(_) => Cost::Api,
+            Self::AddedLinesMatch(_)
+            | Self::FilesMatch(_)
+            | Self::ApprovalsAtLeast(_)
+            | Self::ChecksPassed(_)
+            | Self::ReactionsAtLeast(_) => Cost::Pages,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfIssue {
+    type Payload = IssuesEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::HasLabel(expected) => Ok(payload
+                .issue
+                .labels
+                .iter()
+                .any(|label| expected.contains(&label.name))),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => Ok(expected.contains(&payload.issue.author_association)),
+            Self::TitleMatches(pattern) => Ok(pattern.is_match(&payload.issue.title)),
+            Self::BodyMatches(pattern) => Ok(payload
+                .issue
+                .body
+                .as_deref()
+                .is_some_and(|body| pattern.is_match(body))),
+            Self::IsOpen => Ok(payload.issue.state == IssueState::Open),
+            Self::IsClosed => Ok(payload.issue.state == IssueState::Closed),
+            Self::IsLocked => Ok(payload.issue.locked),
+            Self::ReactionsAtLeast(expected) => Ok(reactions(
+                &GitHub::new(),
+                &format!(
+                    "repos/{}/issues/{}",
+                    payload.repository.full_name, payload.issue.number
+                ),
+                &expected.content,
+            )? >= expected.count),
+            Self::AssignedTo(expected) => Ok(payload
+                .issue
+                .assignees
+                .iter()
+                .any(|user| expected.contains(&user.login))),
+            Self::HasAssignee => Ok(!payload.issue.assignees.is_empty()),
+            Self::MilestoneIs(expected) => Ok(payload
+                .issue
+                .milestone
+                .as_ref()
+                .is_some_and(|milestone| expected.contains(&milestone.title))),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::ReactionsAtLeast(_) => Cost::Pages,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfPullRequest {
+    type Payload = PullRequestEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::IsDraft => Ok(payload.pull_request.draft),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => {
+                Ok(expected.contains(&payload.pull_request.author_association))
+            }
+            Self::UserIn(expected) => Ok(expected.contains(&payload.pull_request.user.login)),
+            Self::UserInTeam(team) => {
+                is_team_member(&GitHub::new(), team, &payload.pull_request.user.login)
+            }
+            Self::UserInOrg(org) => {
+                is_org_member(&GitHub::new(), org, &payload.pull_request.user.login)
+            }
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.pull_request.user.login,
+                expected,
+            ),
+            Self::AddedLinesMatch(pattern) => added_lines_match(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                payload.number,
+                pattern,
+            ),
+            Self::ApprovalsAtLeast(expected) => Ok(approvals(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                payload.number,
+            )? >= *expected),
+            Self::IsMergeable => Ok(is_mergeable(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                payload.number,
+            )?
+            .is_some()),
+            Self::ChecksPassed(required) => checks_passed(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.pull_request.head.sha,
+                required,
+            ),
+            Self::ReactionsAtLeast(expected) => Ok(reactions(
+                &GitHub::new(),
+                &format!(
+                    "repos/{}/issues/{}",
+                    payload.repository.full_name, payload.number
+                ),
+                &expected.content,
+            )? >= expected.count),
+            Self::PrSmallerThan(limit) => pr_smaller_than(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                payload.number,
+                limit,
+            ),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::UserInTeam(_)
+            | Self::UserInOrg(_)
+            | Self::UserHasPermission(_)
+            | Self::IsMergeable
+            | Self::PrSmallerThan(_) => Cost::Api,
+            Self::AddedLinesMatch(_)
+            | Self::ApprovalsAtLeast(_)
+            | Self::ChecksPassed(_)
+            | Self::ReactionsAtLeast(_) => Cost::Pages,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfPullRequestReview {
+    type Payload = PullRequestReviewEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::ReviewState(expected) => Ok(expected.contains(&payload.review.state)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => Ok(expected.contains(&payload.review.author_association)),
+            Self::UserIn(expected) => Ok(expected.contains(&payload.review.user.login)),
+            Self::UserInTeam(team) => {
+                is_team_member(&GitHub::new(), team, &payload.review.user.login)
+            }
+            Self::UserInOrg(org) => is_org_member(&GitHub::new(), org, &payload.review.user.login),
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.review.user.login,
+                expected,
+            ),
+            Self::Command(expected) => {
+                is_command(expected, payload.review.body.as_deref().unwrap_or_default())
+            }
+            Self::ApprovalsAtLeast(expected) => Ok(approvals(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                payload.pull_request.number,
+            )? >= *expected),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::UserInTeam(_) | Self::UserInOrg(_) | Self::UserHasPermission(_) => Cost::Api,
+            Self::ApprovalsAtLeast(_) => Cost::Pages,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfPullRequestReviewComment {
+    type Payload = PullRequestReviewCommentEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => Ok(expected.contains(&payload.comment.author_association)),
+            Self::UserIn(expected) => Ok(expected.contains(&payload.comment.user.login)),
+            Self::UserInTeam(team) => {
+                is_team_member(&GitHub::new(), team, &payload.comment.user.login)
+            }
+            Self::UserInOrg(org) => is_org_member(&GitHub::new(), org, &payload.comment.user.login),
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.comment.user.login,
+                expected,
+            ),
+            Self::Command(expected) => is_command(expected, &payload.comment.body),
+            Self::NotEdited => Ok(payload.action != "edited" && !payload.comment.is_edited()),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::UserInTeam(_) | Self::UserInOrg(_) | Self::UserHasPermission(_) => Cost::Api,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfWorkflowDispatch {
+    type Payload = WorkflowDispatchEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::InputIs(expected) => {
+                let inputs = payload.inputs.clone().unwrap_or_default();
+                Ok(expected.iter().all(|(name, value)| {
+                    inputs
+                        .get(name)
+                        .map(|input| value_to_string(input) == value_to_string(value))
+                        .unwrap_or_default()
+                }))
+            }
+            Self::UserIn(expected) => Ok(expected.contains(&payload.sender.login)),
+            Self::UserInTeam(team) => is_team_member(&GitHub::new(), team, &payload.sender.login),
+            Self::UserInOrg(org) => is_org_member(&GitHub::new(), org, &payload.sender.login),
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.sender.login,
+                expected,
+            ),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::UserInTeam(_) | Self::UserInOrg(_) | Self::UserHasPermission(_) => Cost::Api,
+            _ => Cost::Local,
+        }
+    }
+}
+
+/// Inputs may be provided as strings, so compare them by their string value.
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl Eval<'_> for IfRepositoryDispatch {
+    type Payload = RepositoryDispatchEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::EventTypeIs(expected) => Ok(expected.iter().any(|e| e == payload.event_type())),
+            Self::UserIn(expected) => Ok(expected.contains(&payload.sender.login)),
+            Self::UserInTeam(team) => is_team_member(&GitHub::new(), team, &payload.sender.login),
+            Self::UserInOrg(org) => is_org_member(&GitHub::new(), org, &payload.sender.login),
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.sender.login,
+                expected,
+            ),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::UserInTeam(_) | Self::UserInOrg(_) | Self::UserHasPermission(_) => Cost::Api,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfDiscussion {
+    type Payload = DiscussionEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::CategoryIs(expected) => Ok(expected.contains(&payload.discussion.category.slug)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => Ok(expected.contains(&payload.discussion.author_association)),
+            Self::TitleMatches(pattern) => Ok(pattern.is_match(&payload.discussion.title)),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfDiscussionComment {
+    type Payload = DiscussionCommentEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::CategoryIs(expected) => Ok(expected.contains(&payload.discussion.category.slug)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+            Self::UserIs(expected) => Ok(expected.contains(&payload.comment.author_association)),
+            Self::UserIn(expected) => Ok(expected.contains(&payload.comment.user.login)),
+            Self::UserInTeam(team) => {
+                is_team_member(&GitHub::new(), team, &payload.comment.user.login)
+            }
+            Self::UserInOrg(org) => is_org_member(&GitHub::new(), org, &payload.comment.user.login),
+            Self::UserHasPermission(expected) => has_permission(
+                &GitHub::new(),
+                &payload.repository.full_name,
+                &payload.comment.user.login,
+                expected,
+            ),
+            Self::Command(expected) => is_command(expected, &payload.comment.body),
+            Self::NotEdited => Ok(payload.action != "edited" && !payload.comment.is_edited()),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+
+    fn cost(&self) -> Cost {
+        match self {
+            Self::Not(expr) => expr.cost(),
+            Self::And(children) | Self::Or(children) => max_cost(children),
+            Self::UserInTeam(_) | Self::UserInOrg(_) | Self::UserHasPermission(_) => Cost::Api,
+            _ => Cost::Local,
+        }
+    }
+}
+
+impl Eval<'_> for IfLabel {
+    type Payload = LabelEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::NameMatches(pattern) => Ok(pattern.is_match(&payload.label.name)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfMilestone {
+    type Payload = MilestoneEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::TitleMatches(pattern) => Ok(pattern.is_match(&payload.milestone.title)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfCheckRun {
+    type Payload = CheckRunEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::NameMatches(pattern) => Ok(pattern.is_match(&payload.check_run.name)),
+            Self::ConclusionIs(expected) => Ok(payload
+                .check_run
+                .conclusion
+                .as_ref()
+                .is_some_and(|c| expected.contains(c))),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfCheckSuite {
+    type Payload = CheckSuiteEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::ConclusionIs(expected) => Ok(payload
+                .check_suite
+                .conclusion
+                .as_ref()
+                .is_some_and(|c| expected.contains(c))),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfStatus {
+    type Payload = StatusEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ContextMatches(pattern) => Ok(pattern.is_match(&payload.context)),
+            Self::StateIs(expected) => Ok(expected.contains(&payload.state)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfRef {
+    type Payload = RefEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::RefTypeIs(expected) => Ok(payload.ref_type == *expected),
+            Self::RefMatches(pattern) => Ok(pattern.is_match(&payload.r#ref)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfWorkflowRun {
+    type Payload = WorkflowRunEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let run = &payload.workflow_run;
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::WorkflowIs(expected) => Ok(expected.contains(&run.name)),
+            Self::ConclusionIs(expected) => Ok(run
+                .conclusion
+                .as_ref()
+                .is_some_and(|c| expected.contains(c))),
+            Self::BranchIs(expected) => Ok(run
+                .head_branch
+                .as_ref()
+                .is_some_and(|b| expected.contains(b))),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfRaw {
+    type Payload = RawEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::EventIs(expected) => Ok(expected.contains(&payload.name)),
+            Self::ActionIs(expected) => Ok(payload.payload["action"]
+                .as_str()
+                .is_some_and(|action| expected.iter().any(|e| e == action))),
+            Self::Exists(path) => Ok(select(path, &payload.payload)?.iter().any(|v| !v.is_null())),
+            Self::Equals { path, value } => Ok(select(path, &payload.payload)?.contains(&value)),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfRelease {
+    type Payload = ReleaseEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::ActionIs(expected) => Ok(expected.contains(&payload.action)),
+            Self::IsDraft => Ok(payload.release.draft),
+            Self::IsPrerelease => Ok(payload.release.prerelease),
+            Self::TagMatches(pattern) => Ok(pattern.is_match(&payload.release.tag_name)),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfPush {
+    type Payload = PushEvent;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::BranchIs(expected) => Ok(payload
+                .branch()
+                .map(|branch| expected.iter().any(|e| e == branch))
+                .unwrap_or_default()),
+            Self::TagMatches(pattern) => Ok(payload
+                .tag()
+                .map(|tag| pattern.is_match(tag))
+                .unwrap_or_default()),
+            Self::Common(condition) => eval_common(condition, &payload.repository, &payload.sender),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+impl Eval<'_> for IfOrganization {
+    type Payload = Event;
+
+    fn eval(&self, payload: &Self::Payload) -> anyhow::Result<bool> {
+        let r = match self {
+            Self::Not(expr) => Ok(!expr.eval(payload)?),
+            Self::And(children) => children.eval(payload),
+            Self::Or(children) => eval_any(children, payload),
+            Self::EventIs(expected) => Ok(expected.iter().any(|e| e == payload.name())),
+            Self::ActionIs(expected) => Ok(payload
+                .common()
+                .map(|common| expected.contains(&common.action))
+                .unwrap_or_default()),
+        };
+
+        log::debug!("{:?} => {:?}", self, r);
+
+        r
+    }
+}
+
+/// Fetch the pull request the comment belongs to, `None` if it is an issue comment.
+fn comment_pull_request(payload: &IssueCommentEvent) -> anyhow::Result<Option<PullRequest>> {
+    match &payload.issue.pull_request {
+        Some(_) => Ok(Some(pull_request(
+            &GitHub::new(),
+            &payload.repository.full_name,
+            payload.issue.number,
+        )?)),
+        None => Ok(None),
+    }
+}
+
+/// Resolve the target repository of a command, `None` if the target is not allowed.
+fn resolve_target(
+    target: &Target,
+    command: Option<&SlashCommand>,
+    current: &str,
+) -> Option<String> {
+    let repository = match command.and_then(|c| c.args.get(target.argument)) {
+        Some(name) => resolve_repository(name, current),
+        None => return Some(current.to_string()),
+    };
+
+    if repository == current || target.allow.contains(&repository) {
+        Some(repository)
+    } else {
+        log::debug!("Target not in allow list: {}", repository);
+        None
+    }
+}
+
+/// Resolve the repository named by a step, like `other` or `ctron/other`.
+///
+/// Names using expressions may come from the comment of a command, so they must be the
+/// repository of the event, or the target which was allowed by `target.allow`.
+fn step_repository(repo: &str, context: &Scope) -> anyhow::Result<String> {
+    let current = repository(context)?;
+    let repository = resolve_repository(eval(repo, context)?.trim(), &current);
+
+    let event = context["github"]["event"]["repository"]["full_name"].as_str();
+    if template::EXPRESSION.is_match(repo) && repository != current && Some(&*repository) != event {
+        anyhow::bail!(
+            "Repository '{}' is not allowed, commands may only target the repositories of 'target.allow'",
+            repository
+        );
+    }
+
+    Ok(repository)
+}
+
+fn is_command(command: &str, body: &str) -> anyhow::Result<bool> {
+    if let Some(line) = body.lines().next() {
+        Ok(line.trim().starts_with(&format!("/{}", command)))
+    } else {
+        Ok(false)
+    }
+}
+
+/// Select the values of a JSON path, relative to the root of the value.
+fn select<'v>(path: &str, value: &'v Value) -> anyhow::Result<Vec<&'v Value>> {
+    let selector = template::selector(&template::path(path))?;
+    Ok(selector.find(value).collect())
+}
+
+/// Values other than `null`, `false`, `0` and empty strings, arrays or objects are truthy.
+fn is_truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(b) => *b,
+        Value::Number(n) => n.as_f64() != Some(0.0),
+        Value::String(s) => !s.is_empty(),
+        Value::Array(a) => !a.is_empty(),
+        Value::Object(o) => !o.is_empty(),
+    }
+}
+
+fn run(command: &str, context: &Scope) -> anyhow::Result<()> {
+    let mut cmd = Command::new("bash");
+    cmd.arg("--noprofile")
+        .arg("--norc")
+        .arg("-e")
+        .arg("-o")
+        .arg("pipefail")
+        .arg("-c")
+        .arg(eval(command, context)?);
+
+    log::info!("Running: {:?}", cmd);
+
+    // pass the output through, counting it for the summary
+    let mut child = cmd.stdout(Stdio::piped()).spawn()?;
+    let mut stdout = child
+        .stdout
+        .take()
+        .ok_or_else(|| anyhow::anyhow!("Missing stdout"))?;
+    summary::output(std::io::copy(&mut stdout, &mut output::commands())?);
+    let status = child.wait()?;
+
+    if !status.success() {
+        log::warn!("Failed to run command: {:?} = {:?}", cmd, status);
+        anyhow::bail!("Failed run command");
+    }
+
+    Ok(())
+}
+
+struct JsonPathReplacer<'a> {
+    pub context: &'a Scope<'a>,
+}
+
+impl<'a> JsonPathReplacer<'a> {
+    pub fn new(context: &'a Scope<'a>) -> Self {
+        Self { context }
+    }
     fn replace(&self, expr: &str) -> anyhow::Result<String> {
         let expr = expr.trim();
-        let path = format!("$.{}", expr);
-        let sel = Selector::new(&path).map_err(|err| anyhow::anyhow!("{}", err))?;
-        let val = sel
-            .find(&self.context)
+
+        if let Some(name) = expr
+            .strip_prefix("emoji(")
+            .and_then(|name| name.strip_suffix(')'))
+        {
+            let name = name
+                .trim()
+                .trim_matches(|c| c == '\'' || c == '"' || c == ':');
+            return emoji(name)
+                .map(ToString::to_string)
+                .ok_or_else(|| anyhow::anyhow!("Unknown emoji: {}", name));
+        }
+
+        let val = self
+            .context
+            .select(expr)?
+            .into_iter()
             .filter_map(|t| match t {
                 Value::String(s) => Some(s.to_string()),
                 Value::Number(n) => Some(n.to_string()),
@@ -244,7 +2096,7 @@ impl<'a> JsonPathReplacer<'a> {
             })
             .collect::<Vec<_>>();
 
-        log::debug!("{} ({}) => {:?}", expr, path, val);
+        log::debug!("{} => {:?}", expr, val);
 
         match val.as_slice() {
             [] => Ok(String::new()),
@@ -254,22 +2106,29 @@ impl<'a> JsonPathReplacer<'a> {
     }
 }
 
-fn eval(text: &str, context: &serde_json::Map<String, Value>) -> anyhow::Result<String> {
-    let mut errors = Vec::<anyhow::Error>::new();
+/// Fetch the comments of the issue for `github.event.issue.all_comments`, if the text refers to
+/// them and they are not part of the context yet.
+fn all_comments_of(text: &str, context: &Scope) -> anyhow::Result<Option<Value>> {
+    let issue = &context["github"]["event"]["issue"];
 
-    // let replacer = CelReplacer::new(context, &mut errors);
-    let replacer = JsonPathReplacer::new(context, &mut errors);
+    match issue.is_object() {
+        true if text.contains("all_comments") && issue["all_comments"].is_null() => {
+            let comments = all_comments(&GitHub::new(), &IssueRef::from_context(context)?)?;
+            Ok(Some(json!(comments)))
+        }
+        _ => Ok(None),
+    }
+}
 
-    let text = RE.replace_all(text, replacer);
+fn eval(text: &str, context: &Scope) -> anyhow::Result<String> {
+    let substitute = |context: &Scope| {
+        let replacer = JsonPathReplacer::new(context);
+        template::substitute(text, |expr| replacer.replace(expr))
+    };
 
-    if !errors.is_empty() {
-        if errors.len() == 1 {
-            Err(errors.into_iter().next().unwrap())
-        } else {
-            Err(anyhow::anyhow!("Failed with multiple errors: {:?}", errors))
-        }
-    } else {
-        Ok(text.into())
+    match all_comments_of(text, context)? {
+        Some(comments) => substitute(&context.with("github.event.issue.all_comments", comments)),
+        None => substitute(context),
     }
 }
 
@@ -277,7 +2136,313 @@ fn eval(text: &str, context: &serde_json::Map<String, Value>) -> anyhow::Result<
 mod test {
 
     use super::*;
-    use serde_json::json;
+    use crate::config::{Pattern, ReactionCount};
+    use crate::event::{User, UserType};
+    use regex::Regex;
+
+    /// The context of conditions of issue comments, without the payload as it was received.
+    fn comment(event: &IssueCommentEvent) -> Context<'_, IssueCommentEvent> {
+        Context {
+            payload: event,
+            context: &Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_resolve_target() {
+        let target = Target {
+            argument: 0,
+            allow: vec!["drogue-iot/drogue-cloud-testing".into()],
+        };
+        let command = |args: &[&str]| SlashCommand {
+            name: "backport".into(),
+            args: args.iter().map(ToString::to_string).collect(),
+        };
+        let current = "drogue-iot/drogue-cloud";
+
+        assert_eq!(
+            resolve_target(&target, Some(&command(&["drogue-cloud-testing"])), current),
+            Some("drogue-iot/drogue-cloud-testing".into())
+        );
+        assert_eq!(
+            resolve_target(&target, Some(&command(&[])), current),
+            Some(current.into())
+        );
+        assert_eq!(
+            resolve_target(&target, Some(&command(&["ctron/rodbot"])), current),
+            None
+        );
+    }
+
+    #[test]
+    fn test_raw() -> anyhow::Result<()> {
+        let event = RawEvent {
+            name: "deployment".into(),
+            payload: json!({
+                "action": "created",
+                "deployment": {"environment": "production", "task": "deploy"},
+            }),
+        };
+
+        assert!(IfRaw::ActionIs(vec!["created".into()]).eval(&event)?);
+        assert!(IfRaw::Exists("deployment.task".into()).eval(&event)?);
+        assert!(!IfRaw::Exists("deployment.ref".into()).eval(&event)?);
+        assert!(IfRaw::Equals {
+            path: "deployment.environment".into(),
+            value: "production".into(),
+        }
+        .eval(&event)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_has_label() -> anyhow::Result<()> {
+        let mut event: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        event["issue"]["labels"] = json!([{
+            "color": "0e8a16",
+            "default": false,
+            "description": null,
+            "id": 3012345678u64,
+            "name": "approved",
+            "node_id": "LA_kwDOFoo",
+            "url": "https://api.github.com/repos/drogue-iot/drogue-cloud/labels/approved",
+        }]);
+        let event: IssueCommentEvent = serde_json::from_value(event)?;
+
+        assert!(IfIssueComment::HasLabel(vec!["approved".into()]).eval(&comment(&event))?);
+        assert!(!IfIssueComment::HasLabel(vec!["lgtm".into()]).eval(&comment(&event))?);
+        assert!(
+            IfIssueComment::LabelMatches(Pattern(Regex::new("^approved|lgtm$")?))
+                .eval(&comment(&event))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_body_matches() -> anyhow::Result<()> {
+        let mut event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        event.comment.body = "Flaky test, please retest e2e-tests".into();
+
+        let retest = |pattern: &str| -> anyhow::Result<bool> {
+            IfIssueComment::BodyMatches(Pattern(Regex::new(pattern)?)).eval(&comment(&event))
+        };
+
+        assert!(retest(r"(?m)^.*\bretest [\w-]+$")?);
+        assert!(!retest(r"^/retest")?);
+
+        event.issue.title = "[RFC] Support more events".into();
+        event.issue.body = Some("<!-- rfc-template -->\nDetails".into());
+        assert!(
+            IfIssueComment::TitleMatches(Pattern(Regex::new(r"^\[RFC\]")?))
+                .eval(&comment(&event))?
+        );
+        assert!(
+            IfIssueComment::IssueBodyMatches(Pattern(Regex::new("rfc-template")?))
+                .eval(&comment(&event))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_files_match() -> anyhow::Result<()> {
+        let mut event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        event.issue.pull_request = None;
+
+        // comments on issues don't change files
+        assert!(!IfIssueComment::FilesMatch(vec![Glob::new("*.md")?]).eval(&comment(&event))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_issue_state() -> anyhow::Result<()> {
+        let mut event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        event.issue.state = IssueState::Closed;
+        event.issue.locked = true;
+
+        assert!(!IfIssueComment::IsOpen.eval(&comment(&event))?);
+        assert!(IfIssueComment::IsClosed.eval(&comment(&event))?);
+        assert!(IfIssueComment::IsLocked.eval(&comment(&event))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expr() -> anyhow::Result<()> {
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let event: IssueCommentEvent = serde_json::from_value(raw.clone())?;
+
+        let expr = |path: &str| {
+            IfIssueComment::Expr(path.into()).eval(&Context {
+                payload: &event,
+                context: &raw,
+            })
+        };
+
+        assert!(expr("comment.body")?);
+        assert!(expr("repository.owner.login")?);
+        assert!(!expr("comment.performed_via_github_app")?);
+        assert!(!expr("comment.missing")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_bot() -> anyhow::Result<()> {
+        let mut event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        assert!(IfIssueComment::Common(IfCommon::NotBot).eval(&comment(&event))?);
+        assert!(!IfIssueComment::Common(IfCommon::IsBot).eval(&comment(&event))?);
+
+        event.sender.r#type = UserType::Bot;
+        assert!(IfIssueComment::Common(IfCommon::IsBot).eval(&comment(&event))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assignees() -> anyhow::Result<()> {
+        let mut event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        assert!(!IfIssueComment::HasAssignee.eval(&comment(&event))?);
+        assert!(!IfIssueComment::MilestoneIs(vec!["v1.0".into()]).eval(&comment(&event))?);
+
+        event.issue.assignees = vec![User {
+            login: "ctron".into(),
+            r#type: UserType::User,
+        }];
+        assert!(IfIssueComment::HasAssignee.eval(&comment(&event))?);
+        assert!(IfIssueComment::AssignedTo(vec!["ctron".into()]).eval(&comment(&event))?);
+        assert!(!IfIssueComment::AssignedTo(vec!["someone".into()]).eval(&comment(&event))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reactions() -> anyhow::Result<()> {
+        let event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond(
+            "GET",
+            "repos/*/*/issues/comments/*/reactions",
+            json!([{"content": "+1"}]),
+        );
+        mock.respond(
+            "GET",
+            "repos/*/*/issues/*/reactions",
+            json!([{"content": "+1"}, {"content": "+1"}]),
+        );
+
+        let at_least = |count, on| {
+            IfIssueComment::ReactionsAtLeast(ReactionCount {
+                content: "+1".into(),
+                count,
+                on,
+            })
+            .eval(&comment(&event))
+        };
+
+        assert!(at_least(1, ReactionsOn::Comment)?);
+        assert!(!at_least(2, ReactionsOn::Comment)?);
+        assert!(at_least(2, ReactionsOn::Issue)?);
+
+        let calls = mock.calls_to("GET", "repos/*/*/issues/*/reactions");
+        assert_eq!(calls.len(), 1);
+        assert!(calls[0]
+            .path
+            .starts_with("repos/ctron/gha-test/issues/1/reactions?content=%2B1&"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_not_edited() -> anyhow::Result<()> {
+        let mut event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        assert!(IfIssueComment::ActionIs(vec!["created".into()]).eval(&comment(&event))?);
+        assert!(IfIssueComment::NotEdited.eval(&comment(&event))?);
+
+        event.comment.updated_at = Some("2021-10-23T08:00:00Z".into());
+        assert!(!IfIssueComment::NotEdited.eval(&comment(&event))?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repo() -> anyhow::Result<()> {
+        let event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let name = event.repository.full_name.clone();
+
+        assert!(IfIssueComment::Common(IfCommon::RepoIn(vec![
+            "org/other".into(),
+            name.to_uppercase()
+        ]))
+        .eval(&comment(&event))?);
+        assert!(
+            !IfIssueComment::Common(IfCommon::RepoIn(vec!["org/other".into()]))
+                .eval(&comment(&event))?
+        );
+        assert!(
+            IfIssueComment::Common(IfCommon::RepoMatches(Pattern(Regex::new("^ctron/")?)))
+                .eval(&comment(&event))?
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cheap_first() -> anyhow::Result<()> {
+        let event: IssueCommentEvent =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        let team = || IfIssueComment::UserInTeam("org/team".into());
+        assert_eq!(team().cost(), Cost::Api);
+        assert_eq!(IfIssueComment::Not(Box::new(team())).cost(), Cost::Api);
+        assert_eq!(IfIssueComment::Common(IfCommon::IsBot).cost(), Cost::Local);
+
+        let checks = vec![
+            IfIssueComment::ApprovalsAtLeast(1),
+            IfIssueComment::Common(IfCommon::IsBot),
+            team(),
+            IfIssueComment::Common(IfCommon::NotBot),
+        ];
+        assert_eq!(
+            plan(&checks),
+            vec![
+                (Cost::Local, vec![&checks[1], &checks[3]]),
+                (Cost::Api, vec![&checks[2]]),
+                (Cost::Pages, vec![&checks[0]]),
+            ]
+        );
+
+        // decided by the local checks, without calling the API
+        assert!(
+            !vec![team(), team(), IfIssueComment::Common(IfCommon::IsBot)]
+                .eval(&comment(&event))?
+        );
+        assert!(IfIssueComment::Or(vec![
+            team(),
+            team(),
+            IfIssueComment::Common(IfCommon::NotBot)
+        ])
+        .eval(&comment(&event))?);
+
+        Ok(())
+    }
 
     #[test]
     fn test_1() {
@@ -285,9 +2450,829 @@ mod test {
 
         let r = eval(
             "Hello ${{ foo.value }}!",
-            json!({"foo": {"value": "World"}}).as_object().unwrap(),
+            &Scope::new(&json!({"foo": {"value": "World"}})),
         )
         .expect("To compile");
         assert_eq!(r, "Hello World!");
     }
+
+    #[test]
+    fn test_emoji() {
+        let r = eval(
+            "${{ emoji('tada') }} Released ${{ version }}",
+            &Scope::new(&json!({"version": "1.2.0"})),
+        )
+        .expect("To compile");
+        assert_eq!(r, "🎉 Released 1.2.0");
+
+        assert!(eval("${{ emoji(unknown) }}", &Scope::new(&json!({}))).is_err());
+    }
+
+    #[test]
+    fn test_all_comments() {
+        // already fetched comments are used as they are
+        let r = eval(
+            "${{ github.event.issue.all_comments[0].user.login }}",
+            &Scope::new(&json!({"github": {"event": {"issue": {
+                "number": 1,
+                "all_comments": [{"user": {"login": "rodbot"}, "body": "Thanks!"}],
+            }}}})),
+        )
+        .expect("To compile");
+        assert_eq!(r, "rodbot");
+    }
+
+    #[test]
+    fn test_command_args() {
+        let r = eval(
+            "git cherry-pick --onto ${{ command.args[1] }} -R ${{ repository }}",
+            &Scope::new(&json!({"command": {"name": "backport", "args": ["other", "release-1.2"]}, "repository": "drogue-iot/other"})),
+        )
+        .expect("To compile");
+        assert_eq!(r, "git cherry-pick --onto release-1.2 -R drogue-iot/other");
+    }
+
+    #[test]
+    fn test_offline() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"---
+on:
+  issue_comment:
+    - if:
+        - command: test
+        - user_has_permission: ["write", "admin"]
+      steps:
+        - comment: "Testing"
+    - if:
+        - command: test
+      steps:
+        - comment: "Sorry ${{ github.event.comment.user.login }}, you may not test"
+"#,
+        )?;
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let event = Event::IssueComment(serde_json::from_value(raw.clone())?);
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        config.run(&Context {
+            payload: &event,
+            context: &json!({ "github": { "event": raw } }),
+        })?;
+
+        let comments = mock.calls_to("POST", "repos/*/*/issues/*/comments");
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].path, "repos/ctron/gha-test/issues/1/comments");
+        assert_eq!(
+            comments[0].body,
+            Some(json!({"body": "Sorry ctron, you may not test"}))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mark() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"---
+on:
+  issue_comment:
+    - if:
+        - command: test
+      mark: strike
+      steps:
+        - add_labels: ["tested"]
+"#,
+        )?;
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("PATCH", "repos/*/*/issues/comments/*", json!({}));
+        let run = |raw: &Value| {
+            let result = config.run(&Context {
+                payload: &Event::from_value("issue_comment", raw)?,
+                context: &json!({ "github": { "event": raw } }),
+            });
+            summary::take();
+            result
+        };
+        run(&raw)?;
+
+        assert_eq!(mock.calls_to("POST", "repos/*/*/issues/*/labels").len(), 1);
+        let edits = mock.calls_to("PATCH", "repos/ctron/gha-test/issues/comments/949514853");
+        assert_eq!(
+            edits.iter().map(|e| e.body.clone()).collect::<Vec<_>>(),
+            vec![Some(json!({"body": "~~/test~~\n<!-- rodbot:executed -->"}))]
+        );
+
+        // editing the comment doesn't run the command again
+        let mut edited = raw.clone();
+        edited["action"] = "edited".into();
+        edited["comment"]["body"] = "/test\n<!-- rodbot:executed -->".into();
+        run(&edited)?;
+        assert_eq!(mock.calls_to("POST", "repos/*/*/issues/*/labels").len(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_step_templates() -> anyhow::Result<()> {
+        let config = crate::parse_config(serde_yaml::from_str(
+            r#"---
+step_templates:
+  label:
+    params: [kind, target]
+    steps:
+      - add_labels: ["${{ params.kind }}/${{ params.target }}"]
+on:
+  issue_comment:
+    - if:
+        - command: test
+      steps:
+        - use: label
+          with:
+            kind: test
+            target: "${{ github.event.comment.user.login }}"
+        - add_labels: ["${{ params.kind }}"]
+"#,
+        )?)?;
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        let result = config.run(&Context {
+            payload: &Event::from_value("issue_comment", &raw)?,
+            context: &json!({ "github": { "event": raw } }),
+        });
+        summary::take();
+        result?;
+
+        // the parameters are only available to the steps of the template
+        assert_eq!(
+            mock.calls_to("POST", "repos/*/*/issues/*/labels")
+                .into_iter()
+                .map(|call| call.body)
+                .collect::<Vec<_>>(),
+            vec![Some(json!({"labels": ["test/ctron"]}))]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_labels() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 1},
+            }},
+            "command": {"name": "kind", "args": ["bug"]},
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        run_step(
+            &Step::AddLabels(vec!["kind/${{ command.args[0] }}".into()]),
+            &Scope::new(&context),
+        )?;
+        run_step(
+            &Step::RemoveLabels(vec![
+                "needs-triage".into(),
+                "kind/${{ command.args[0] }}".into(),
+            ]),
+            &Scope::new(&context),
+        )?;
+
+        let calls = mock.calls();
+        assert_eq!(
+            calls[1].body,
+            Some(json!({"labels": ["kind/bug"]})),
+            "{:?}",
+            calls
+        );
+        assert_eq!(
+            calls[2..]
+                .iter()
+                .map(|call| (call.method.as_str(), call.path.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("DELETE", "repos/ctron/rodbot/issues/1/labels/needs-triage"),
+                ("DELETE", "repos/ctron/rodbot/issues/1/labels/kind%2Fbug"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close() -> anyhow::Result<()> {
+        let context = json!({"github": {"event": {
+            "repository": {"full_name": "ctron/rodbot"},
+            "issue": {"number": 1},
+        }}});
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        run_step(
+            &Step::Close(crate::config::Close {
+                reason: Some(crate::config::CloseReason::NotPlanned),
+            }),
+            &Scope::new(&context),
+        )?;
+        run_step(&Step::Reopen, &Scope::new(&context))?;
+
+        assert_eq!(
+            mock.calls_to("PATCH", "repos/ctron/rodbot/issues/1")
+                .into_iter()
+                .map(|call| call.body)
+                .collect::<Vec<_>>(),
+            vec![
+                Some(json!({"state": "closed", "state_reason": "not_planned"})),
+                Some(json!({"state": "open"})),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_issue() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 7, "title": "Add steps"},
+            }},
+            "command": {"name": "followup", "args": ["other"]},
+        });
+
+        let followup = Step::CreateIssue(crate::config::CreateIssue {
+            title: "Follow up on: ${{ github.event.issue.title }}".into(),
+            body: Some("See ctron/rodbot#${{ github.event.issue.number }}".into()),
+            labels: vec!["followup".into()],
+            assignees: vec!["@ctron".into()],
+            repo: Some("${{ command.args[0] }}".into()),
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("POST", "repos/*/*/issues", json!({"number": 8}));
+        // the argument must be allowed as target first
+        let context = Scope::new(&context);
+        assert!(run_step(&followup, &context).is_err());
+        run_step(&followup, &context.with("repository", "ctron/other".into()))?;
+        run_step(
+            &Step::CreateIssue(crate::config::CreateIssue {
+                title: "Reminder".into(),
+                body: None,
+                labels: vec![],
+                assignees: vec![],
+                repo: None,
+            }),
+            &context,
+        )?;
+
+        assert_eq!(
+            mock.calls(),
+            vec![
+                crate::mock::Call {
+                    method: "POST".into(),
+                    path: "repos/ctron/other/issues".into(),
+                    body: Some(json!({
+                        "title": "Follow up on: Add steps",
+                        "body": "See ctron/rodbot#7",
+                        "labels": ["followup"],
+                        "assignees": ["ctron"],
+                    })),
+                },
+                crate::mock::Call {
+                    method: "POST".into(),
+                    path: "repos/ctron/rodbot/issues".into(),
+                    body: Some(json!({"title": "Reminder", "labels": [], "assignees": []})),
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispatch_workflow() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot", "default_branch": "main"},
+                "issue": {"number": 7},
+            }},
+            "command": {"name": "e2e", "args": ["staging"]},
+        });
+        let dispatch = |r#ref: Option<&str>| {
+            Step::DispatchWorkflow(crate::config::DispatchWorkflow {
+                workflow: "e2e.yaml".into(),
+                r#ref: r#ref.map(Into::into),
+                inputs: [
+                    ("env".to_string(), "${{ command.args[0] }}".to_string()),
+                    ("pr".into(), "${{ github.event.issue.number }}".into()),
+                ]
+                .into(),
+            })
+        };
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond(
+            "POST",
+            "repos/*/*/actions/workflows/*/dispatches",
+            Value::Null,
+        );
+        run_step(&dispatch(None), &Scope::new(&context))?;
+        run_step(&dispatch(Some("release-1.2")), &Scope::new(&context))?;
+
+        assert_eq!(
+            mock.calls_to(
+                "POST",
+                "repos/ctron/rodbot/actions/workflows/e2e.yaml/dispatches"
+            )
+            .into_iter()
+            .map(|call| call.body)
+            .collect::<Vec<_>>(),
+            vec![
+                Some(json!({"ref": "main", "inputs": {"env": "staging", "pr": "7"}})),
+                Some(json!({"ref": "release-1.2", "inputs": {"env": "staging", "pr": "7"}})),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_repository_dispatch() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "drogue-iot/drogue-cloud"},
+                "issue": {"number": 7},
+            }},
+            "command": {"name": "publish", "args": ["v0.9"]},
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("POST", "repos/*/*/dispatches", Value::Null);
+        run_step(
+            &Step::RepositoryDispatch(serde_yaml::from_str(
+                r#"
+repo: drogue-website
+event_type: rebuild
+client_payload:
+  version: "${{ command.args[0] }}"
+  sources: ["${{ github.event.repository.full_name }}"]
+  force: true
+"#,
+            )?),
+            &Scope::new(&context),
+        )?;
+
+        assert_eq!(
+            mock.calls(),
+            vec![crate::mock::Call {
+                method: "POST".into(),
+                path: "repos/drogue-iot/drogue-website/dispatches".into(),
+                body: Some(json!({
+                    "event_type": "rebuild",
+                    "client_payload": {
+                        "version": "v0.9",
+                        "sources": ["drogue-iot/drogue-cloud"],
+                        "force": true,
+                    },
+                })),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 1},
+            }},
+            "command": {"name": "lock", "args": ["too_heated"]},
+        });
+        let lock = |reason: &str| {
+            Step::Lock(crate::config::Lock {
+                reason: Some(reason.into()),
+            })
+        };
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("PUT", "repos/*/*/issues/*/lock", Value::Null);
+        mock.respond("DELETE", "repos/*/*/issues/*/lock", Value::Null);
+        run_step(&lock("${{ command.args[0] }}"), &Scope::new(&context))?;
+        run_step(&lock(""), &Scope::new(&context))?;
+        run_step(&Step::Unlock, &Scope::new(&context))?;
+        assert!(run_step(&lock("boring"), &Scope::new(&context)).is_err());
+
+        assert_eq!(
+            mock.calls()
+                .into_iter()
+                .map(|call| (call.method, call.body))
+                .collect::<Vec<_>>(),
+            vec![
+                ("PUT".into(), Some(json!({"lock_reason": "too heated"}))),
+                ("PUT".into(), Some(json!({}))),
+                ("DELETE".into(), None),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_assign() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 1},
+            }},
+            "command": {"name": "assign", "args": ["@ctron"]},
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        run_step(
+            &Step::Assign(vec!["${{ command.args[0] }}".into(), "@".into()]),
+            &Scope::new(&context),
+        )?;
+        run_step(
+            &Step::Unassign(vec!["${{ command.args[1] }}".into()]),
+            &Scope::new(&context),
+        )?;
+        run_step(
+            &Step::Unassign(vec!["octocat".into()]),
+            &Scope::new(&context),
+        )?;
+
+        assert_eq!(
+            mock.calls()
+                .into_iter()
+                .map(|call| (call.method, call.body))
+                .collect::<Vec<_>>(),
+            vec![
+                ("POST".into(), Some(json!({"assignees": ["ctron"]}))),
+                ("DELETE".into(), Some(json!({"assignees": ["octocat"]}))),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_set_milestone() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 1},
+            }},
+            "command": {"name": "milestone", "args": ["1.2"]},
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond(
+            "GET",
+            "repos/ctron/rodbot/milestones",
+            json!([{"number": 3, "title": "1.1"}, {"number": 4, "title": "1.2"}]),
+        );
+        run_step(
+            &Step::SetMilestone("${{ command.args[0] }}".into()),
+            &Scope::new(&context),
+        )?;
+        run_step(&Step::SetMilestone("".into()), &Scope::new(&context))?;
+        assert!(run_step(&Step::SetMilestone("2.0".into()), &Scope::new(&context)).is_err());
+
+        assert_eq!(
+            mock.calls_to("PATCH", "repos/ctron/rodbot/issues/1")
+                .into_iter()
+                .map(|call| call.body)
+                .collect::<Vec<_>>(),
+            vec![
+                Some(json!({"milestone": 4})),
+                Some(json!({"milestone": null})),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_request_review() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "drogue-iot/drogue-cloud"},
+                "issue": {"number": 7, "pull_request": {}},
+            }},
+            "command": {"name": "cc", "args": ["@ctron", "@drogue-iot/reviewers"]},
+        });
+
+        let mock = crate::mock::MockGitHub::new().install();
+        mock.respond("POST", "repos/*/*/pulls/*/requested_reviewers", json!({}));
+        run_step(
+            &Step::RequestReview(crate::config::RequestReview {
+                users: vec!["${{ command.args[0] }}".into()],
+                teams: vec!["${{ command.args[1] }}".into(), "maintainers".into()],
+            }),
+            &Scope::new(&context),
+        )?;
+
+        assert_eq!(
+            mock.calls(),
+            vec![crate::mock::Call {
+                method: "POST".into(),
+                path: "repos/drogue-iot/drogue-cloud/pulls/7/requested_reviewers".into(),
+                body: Some(json!({
+                    "reviewers": ["ctron"],
+                    "team_reviewers": ["reviewers", "maintainers"],
+                })),
+            }]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge() -> anyhow::Result<()> {
+        let mut pr: Value =
+            serde_json::from_reader(std::fs::File::open("test/pull_request_1.json")?)?;
+        let pr = &mut pr["pull_request"];
+        pr["mergeable_state"] = "clean".into();
+        let sha = pr["head"]["sha"].clone();
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 1, "title": "Fix it"},
+            }},
+        });
+        let step = Step::Merge(crate::config::Merge {
+            method: crate::config::MergeMethod::Squash,
+            commit_title: Some("${{ github.event.issue.title }}".into()),
+            commit_message: None,
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", pr.clone());
+        mock.respond(
+            "PUT",
+            "repos/ctron/rodbot/pulls/1/merge",
+            json!({"merged": true}),
+        );
+        run_step(&step, &Scope::new(&context))?;
+        assert_eq!(
+            mock.calls_to("PUT", "repos/*/*/pulls/*/merge")[0].body,
+            Some(json!({"merge_method": "squash", "sha": sha, "commit_title": "Fix it"}))
+        );
+
+        // a push after checking the pull request must not be merged
+        let mut pushed = pr.clone();
+        pushed["head"]["sha"] = "0000000000000000000000000000000000000000".into();
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", pushed);
+        mock.respond_once("GET", "repos/ctron/rodbot/pulls/1", pr.clone());
+        run_step(&step, &Scope::new(&context))?;
+        assert_eq!(
+            mock.calls_to("PUT", "repos/*/*/pulls/*/merge")[1].body,
+            Some(json!({"merge_method": "squash", "sha": sha, "commit_title": "Fix it"}))
+        );
+
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", pr.clone());
+        mock.fail("PUT", "repos/ctron/rodbot/pulls/1/merge", 409);
+        let err = run_step(&step, &Scope::new(&context)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Pull request ctron/rodbot#1 changed while merging, not merging"
+        );
+
+        pr["mergeable_state"] = "dirty".into();
+        mock.respond("GET", "repos/ctron/rodbot/pulls/1", pr.clone());
+        let err = run_step(&step, &Scope::new(&context)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Pull request ctron/rodbot#1 is not mergeable"
+        );
+        assert_eq!(mock.calls_to("PUT", "repos/*/*/pulls/*/merge").len(), 3);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reviews() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {"event": {
+                "repository": {"full_name": "ctron/rodbot"},
+                "issue": {"number": 1},
+            }},
+            "command": {"name": "dismiss", "args": ["@dependabot"]},
+        });
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("POST", "repos/*/*/pulls/*/reviews", json!({}));
+        mock.respond("PUT", "repos/*/*/pulls/*/reviews/*/dismissals", json!({}));
+        mock.respond(
+            "GET",
+            "repos/ctron/rodbot/pulls/1/reviews",
+            json!([
+                {"id": 1, "user": {"login": "ctron"}, "state": "APPROVED"},
+                {"id": 2, "user": {"login": "dependabot"}, "state": "COMMENTED"},
+                {"id": 3, "user": {"login": "Dependabot"}, "state": "CHANGES_REQUESTED"},
+            ]),
+        );
+
+        run_step(
+            &Step::Approve(crate::config::Approve {
+                body: Some("Approved by ${{ command.name }}".into()),
+            }),
+            &Scope::new(&context),
+        )?;
+        run_step(
+            &Step::DismissReviews(crate::config::DismissReviews {
+                message: "Dismissed".into(),
+                users: vec!["${{ command.args[0] }}".into()],
+            }),
+            &Scope::new(&context),
+        )?;
+
+        assert_eq!(
+            mock.calls_to("POST", "repos/ctron/rodbot/pulls/1/reviews")[0].body,
+            Some(json!({"event": "APPROVE", "body": "Approved by dismiss"}))
+        );
+        assert_eq!(
+            mock.calls_to("PUT", "repos/*/*/pulls/*/reviews/*/dismissals")
+                .into_iter()
+                .map(|call| call.path)
+                .collect::<Vec<_>>(),
+            vec!["repos/ctron/rodbot/pulls/1/reviews/3/dismissals"]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_plan() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"---
+on:
+  issue_comment:
+    - name: test
+      if:
+        - command: test
+      steps:
+        - run: echo "Testing ${{ github.event.issue.number }}"
+        - comment: "Testing"
+"#,
+        )?;
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let event = Event::IssueComment(serde_json::from_value(raw.clone())?);
+
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        plan::start();
+        let result = config.run(&Context {
+            payload: &event,
+            context: &json!({ "github": { "event": raw } }),
+        });
+        let decisions = plan::take();
+        summary::take();
+        result?;
+
+        assert_eq!(
+            decisions,
+            vec![
+                plan::Decision {
+                    rule: "test".into(),
+                    step: "run".into(),
+                    command: Some("echo \"Testing 1\"".into()),
+                },
+                plan::Decision {
+                    rule: "test".into(),
+                    step: "comment".into(),
+                    command: None,
+                },
+            ]
+        );
+        assert!(mock.calls().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"---
+on:
+  issue_comment:
+    - name: test
+      if:
+        - command: test
+        - is_bot
+      steps:
+        - add_labels: ["tested"]
+    - if:
+        - not_bot
+      steps:
+        - run: echo "Hello"
+    - name: other
+      if:
+        - command: other
+      steps:
+        - add_labels: ["other"]
+"#,
+        )?;
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let event = Event::IssueComment(serde_json::from_value(raw.clone())?);
+
+        let _mock = crate::mock::MockGitHub::with_fixtures().install();
+        plan::start();
+        explain::start();
+        let result = config.run(&Context {
+            payload: &event,
+            context: &json!({ "github": { "event": raw } }),
+        });
+        let explanations = explain::take();
+        plan::take();
+        summary::take();
+        result?;
+
+        let condition = |condition: &str, result| explain::Condition {
+            condition: condition.into(),
+            result,
+            error: None,
+        };
+        assert_eq!(
+            explanations,
+            vec![
+                explain::Explanation {
+                    rule: "test".into(),
+                    matched: false,
+                    // all conditions are evaluated, not only up to the rejecting one
+                    conditions: vec![
+                        condition("Command(\"test\")", true),
+                        condition("Common(IsBot)", false)
+                    ],
+                },
+                explain::Explanation {
+                    rule: "issue_comment".into(),
+                    matched: true,
+                    conditions: vec![condition("Common(NotBot)", true)],
+                },
+                // not a candidate of the index, as the command doesn't match
+                explain::Explanation {
+                    rule: "other".into(),
+                    matched: false,
+                    conditions: vec![condition("Command(\"other\")", false)],
+                },
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_within() -> anyhow::Result<()> {
+        let config: Config = serde_yaml::from_str(
+            r#"---
+on:
+  issue_comment:
+    - name: office hours
+      if:
+        - within:
+            days: [mon-fri]
+            hours: "09:00-17:00"
+      steps:
+        - add_labels: ["office"]
+    - name: weekend
+      if:
+        - within:
+            days: [sat-sun]
+      steps:
+        - add_labels: ["weekend"]
+"#,
+        )?;
+        let raw: Value =
+            serde_json::from_reader(std::fs::File::open("test/issue_comment_1.json")?)?;
+        let event = Event::IssueComment(serde_json::from_value(raw.clone())?);
+
+        let _mock = crate::mock::MockGitHub::with_fixtures().install();
+        let rules = |day, minute| {
+            let _clock = crate::clock::FixedClock::new(day, minute, 0).install();
+            plan::start();
+            let result = config.run(&Context {
+                payload: &event,
+                context: &json!({ "github": { "event": raw } }),
+            });
+            let decisions = plan::take();
+            summary::take();
+            result.map(|_| decisions.into_iter().map(|d| d.rule).collect::<Vec<_>>())
+        };
+
+        // tuesday, 10:00
+        assert_eq!(rules(2, 10 * 60)?, vec!["office hours"]);
+        // tuesday, 18:00
+        assert!(rules(2, 18 * 60)?.is_empty());
+        // saturday, 10:00
+        assert_eq!(rules(6, 10 * 60)?, vec!["weekend"]);
+
+        Ok(())
+    }
 }
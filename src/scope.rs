@@ -0,0 +1,135 @@
+use crate::template;
+use serde_json::Value;
+use std::ops::Index;
+
+static NULL: Value = Value::Null;
+
+/// The context of the steps of a rule: the borrowed context of the event, and the values which
+/// the rule adds to it, like the `command` or the current `item` of a search.
+///
+/// Values are added by their path, like `command` or `github.event.issue.all_comments`, and
+/// take precedence over the values of the context.
+pub struct Scope<'c> {
+    base: &'c Value,
+    parent: Option<&'c Scope<'c>>,
+    values: Vec<(String, Value)>,
+}
+
+impl<'c> Scope<'c> {
+    pub fn new(base: &'c Value) -> Self {
+        Self {
+            base,
+            parent: None,
+            values: Vec::new(),
+        }
+    }
+
+    /// Add a value, replacing the value of the same path.
+    pub fn insert(&mut self, path: impl Into<String>, value: Value) {
+        let path = path.into();
+        self.values.retain(|(p, _)| *p != path);
+        self.values.push((path, value));
+    }
+
+    /// Add a value to a new scope, without modifying this one.
+    pub fn with(&self, path: impl Into<String>, value: Value) -> Scope<'_> {
+        let mut scope = Scope {
+            base: self.base,
+            parent: Some(self),
+            values: Vec::new(),
+        };
+        scope.insert(path, value);
+        scope
+    }
+
+    /// Get the value of a top-level key, like `command` or `github`.
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        match self.values.iter().find(|(path, _)| path == key) {
+            Some((_, value)) => Some(value),
+            None => match self.parent {
+                Some(parent) => parent.get(key),
+                None => self.base.get(key),
+            },
+        }
+    }
+
+    /// Select the values of an expression, like `github.event.issue.number` or `item.title`.
+    ///
+    /// Expressions starting with the path of an added value select from that value, all others
+    /// from the context.
+    pub fn select(&self, expr: &str) -> anyhow::Result<Vec<&Value>> {
+        let expr = expr.trim().trim_start_matches("$.");
+
+        let mut scope = Some(self);
+        while let Some(current) = scope {
+            for (path, value) in &current.values {
+                if let Some(rest) = expr.strip_prefix(path.as_str()) {
+                    if rest.is_empty() || rest.starts_with('.') || rest.starts_with('[') {
+                        let selector = template::selector(&format!("${}", rest))?;
+                        return Ok(selector.find(value).collect());
+                    }
+                }
+            }
+            scope = current.parent;
+        }
+
+        let selector = template::selector(&template::path(expr))?;
+        Ok(selector.find(self.base).collect())
+    }
+}
+
+impl Index<&str> for Scope<'_> {
+    type Output = Value;
+
+    /// Get the value of a top-level key, or `null`, like indexing a [`Value`].
+    fn index(&self, key: &str) -> &Value {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_get() {
+        let base = json!({"github": {"event": {"number": 1}}, "command": "base"});
+        let mut scope = Scope::new(&base);
+        scope.insert("repository", json!("ctron/rodbot"));
+
+        assert_eq!(scope["github"]["event"]["number"], 1);
+        assert_eq!(scope["repository"], "ctron/rodbot");
+        assert_eq!(scope["command"], "base");
+        assert!(scope["missing"].is_null());
+
+        let inner = scope.with("command", json!({"name": "test"}));
+        assert_eq!(inner["command"]["name"], "test");
+        assert_eq!(inner["repository"], "ctron/rodbot");
+        // the outer scope is not modified
+        assert_eq!(scope["command"], "base");
+    }
+
+    #[test]
+    fn test_select() -> anyhow::Result<()> {
+        let base = json!({"github": {"event": {"issue": {"number": 1}}}});
+        let scope = Scope::new(&base);
+        let scope = scope.with("item", json!({"title": "Test", "labels": ["a", "b"]}));
+        let scope = scope.with(
+            "github.event.issue.all_comments",
+            json!([{"body": "First"}]),
+        );
+
+        assert_eq!(scope.select("github.event.issue.number")?, vec![&json!(1)]);
+        assert_eq!(scope.select("$.item.title")?, vec![&json!("Test")]);
+        assert_eq!(scope.select("item.labels[1]")?, vec![&json!("b")]);
+        assert_eq!(
+            scope.select("github.event.issue.all_comments[0].body")?,
+            vec![&json!("First")]
+        );
+        // only matching complete segments
+        assert!(scope.select("items")?.is_empty());
+
+        Ok(())
+    }
+}
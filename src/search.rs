@@ -0,0 +1,113 @@
+use crate::{
+    clock::{self, clock},
+    github::{encode, GitHub},
+};
+use serde::Deserialize;
+use serde_json::Value;
+use std::time::Duration;
+
+/// Pause when fewer API requests remain, until the rate limit resets.
+const MIN_REMAINING: u64 = 100;
+
+#[derive(Clone, Debug, Deserialize)]
+struct SearchResult {
+    items: Vec<Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RateLimit {
+    resources: Resources,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Resources {
+    core: Limit,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Limit {
+    remaining: u64,
+    /// Epoch seconds of the next reset.
+    reset: u64,
+}
+
+/// Search issues and pull requests, returning up to `limit` results.
+pub fn search_issues(github: &GitHub, query: &str, limit: usize) -> anyhow::Result<Vec<Value>> {
+    let per_page = limit.clamp(1, 100);
+    let mut result = Vec::new();
+
+    for page in 1.. {
+        let items = github
+            .get::<SearchResult>(&format!(
+                "search/issues?q={}&per_page={}&page={}",
+                encode(query),
+                per_page,
+                page
+            ))?
+            .items;
+        let len = items.len();
+        result.extend(items);
+        if len < per_page || result.len() >= limit {
+            break;
+        }
+    }
+
+    result.truncate(limit);
+    Ok(result)
+}
+
+/// Wait before the next bulk operation, and until the rate limit resets if it is nearly used up.
+pub fn pace(github: &GitHub, delay: Duration) -> anyhow::Result<()> {
+    clock::sleep(delay);
+
+    let limit = github.get::<RateLimit>("rate_limit")?.resources.core;
+    if limit.remaining < MIN_REMAINING {
+        let now = clock().epoch();
+        let wait = limit.reset.saturating_sub(now) + 1;
+        log::info!(
+            "Only {} API requests remaining, waiting {}s for the rate limit to reset",
+            limit.remaining,
+            wait
+        );
+        clock::sleep(Duration::from_secs(wait));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        clock::{Clock, FixedClock},
+        mock::MockGitHub,
+    };
+    use serde_json::json;
+
+    #[test]
+    fn test_pace() -> anyhow::Result<()> {
+        let mock = MockGitHub::new().install();
+        let clock = FixedClock::new(1, 0, 1_000).install();
+        let github = GitHub::new();
+
+        // plenty of requests remaining, only waiting for the delay
+        mock.respond_once(
+            "GET",
+            "rate_limit",
+            json!({"resources": {"core": {"limit": 5000, "remaining": 4000, "reset": 2_000}}}),
+        );
+        pace(&github, Duration::from_secs(1))?;
+        assert_eq!(clock.epoch(), 1_001);
+
+        // nearly used up, waiting until after the reset
+        mock.respond_once(
+            "GET",
+            "rate_limit",
+            json!({"resources": {"core": {"limit": 5000, "remaining": 10, "reset": 2_000}}}),
+        );
+        pace(&github, Duration::from_secs(1))?;
+        assert_eq!(clock.epoch(), 2_001);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,201 @@
+use crate::{
+    changes::{files, head_sha, numbered_added_lines},
+    config::SpellCheck,
+    github::GitHub,
+    steps::{suggestion, IssueRef},
+};
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+/// A typo, as reported by `typos --format json`.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Typo {
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub line_num: usize,
+    #[serde(default)]
+    pub byte_offset: usize,
+    #[serde(default)]
+    pub typo: String,
+    #[serde(default)]
+    pub corrections: Vec<String>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suggestion {
+    pub path: String,
+    pub line: u64,
+    pub body: String,
+}
+
+/// Parse the output of `typos`, skipping everything which isn't a typo with a correction.
+pub fn parse_typos(output: &str) -> Vec<Typo> {
+    output
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Typo>(line).ok())
+        .filter(|typo| typo.kind == "typo" && !typo.corrections.is_empty())
+        .collect()
+}
+
+/// Create a suggestion for each line with typos.
+///
+/// The line numbers of the typos refer to the lines, as they were passed to `typos`.
+pub fn suggestions(path: &str, lines: &[(u64, &str)], typos: &[Typo]) -> Vec<Suggestion> {
+    let mut by_line = BTreeMap::<usize, Vec<&Typo>>::new();
+    for typo in typos {
+        by_line.entry(typo.line_num).or_default().push(typo);
+    }
+
+    let mut result = Vec::new();
+
+    for (num, mut typos) in by_line {
+        let (line, text) = match num.checked_sub(1).and_then(|i| lines.get(i)) {
+            Some(line) => *line,
+            None => continue,
+        };
+
+        // replace from the end, so that the offsets stay valid
+        typos.sort_by_key(|typo| std::cmp::Reverse(typo.byte_offset));
+        let mut fixed = text.to_string();
+        for typo in &typos {
+            let end = typo.byte_offset + typo.typo.len();
+            if fixed.get(typo.byte_offset..end) == Some(typo.typo.as_str()) {
+                fixed.replace_range(typo.byte_offset..end, &typo.corrections[0]);
+            }
+        }
+
+        if fixed == text {
+            continue;
+        }
+
+        let found = typos
+            .iter()
+            .rev()
+            .map(|typo| format!("`{}` → `{}`", typo.typo, typo.corrections[0]))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        result.push(Suggestion {
+            path: path.to_string(),
+            line,
+            body: suggestion(Some(&format!("Possible typo: {}", found)), &fixed),
+        });
+    }
+
+    result
+}
+
+/// Run `typos` on the text.
+fn typos(command: &str, text: &str) -> anyhow::Result<Vec<Typo>> {
+    let mut child = Command::new(command)
+        .args(["--format", "json", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run: {}", command))?;
+
+    child
+        .stdin
+        .take()
+        .context("Missing stdin")?
+        .write_all(text.as_bytes())?;
+
+    let output = child.wait_with_output()?;
+    // exits with 2 if typos were found
+    match output.status.code() {
+        Some(0) | Some(2) => Ok(parse_typos(&String::from_utf8_lossy(&output.stdout))),
+        _ => anyhow::bail!("{} failed: {}", command, output.status),
+    }
+}
+
+/// Check the lines added by the pull request for typos, and post the suggestions as a review.
+pub fn spell_check(github: &GitHub, config: &SpellCheck, issue: &IssueRef) -> anyhow::Result<()> {
+    let mut result = Vec::new();
+
+    for file in files(github, &issue.repository, issue.number)? {
+        if file.status == "removed"
+            || !(config.paths.is_empty() || config.paths.iter().any(|p| p.is_match(&file.filename)))
+        {
+            continue;
+        }
+        let patch = match &file.patch {
+            Some(patch) => patch,
+            None => continue,
+        };
+
+        let lines = numbered_added_lines(patch);
+        if lines.is_empty() {
+            continue;
+        }
+        let text = lines
+            .iter()
+            .map(|(_, text)| *text)
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        result.extend(suggestions(
+            &file.filename,
+            &lines,
+            &typos(&config.command, &text)?,
+        ));
+    }
+
+    log::info!("Spell check: {} suggestion(s)", result.len());
+
+    if result.is_empty() {
+        return Ok(());
+    }
+
+    github.post(
+        &format!("repos/{}/pulls/{}/reviews", issue.repository, issue.number),
+        &json!({
+            "commit_id": head_sha(github, &issue.repository, issue.number)?,
+            "event": "COMMENT",
+            "body": format!("Found {} line(s) with possible typos.", result.len()),
+            "comments": result
+                .iter()
+                .map(|s| json!({
+                    "path": s.path,
+                    "line": s.line,
+                    "side": "RIGHT",
+                    "body": s.body,
+                }))
+                .collect::<Vec<_>>(),
+        }),
+    )?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_suggestions() {
+        let output = r#"{"type":"typo","path":"-","line_num":2,"byte_offset":4,"typo":"teh","corrections":["the"]}
+{"type":"typo","path":"-","line_num":2,"byte_offset":15,"typo":"recieve","corrections":["receive"]}
+{"type":"typo","path":"-","line_num":1,"byte_offset":0,"typo":"Foo","corrections":[]}"#;
+
+        let typos = parse_typos(output);
+        assert_eq!(typos.len(), 2);
+
+        let lines = vec![(10, "Foo bar"), (11, "Use teh API to recieve events")];
+
+        assert_eq!(
+            suggestions("README.md", &lines, &typos),
+            vec![Suggestion {
+                path: "README.md".into(),
+                line: 11,
+                body: "Possible typo: `teh` → `the`, `recieve` → `receive`\n\n```suggestion\nUse the API to receive events\n```".into(),
+            }]
+        );
+    }
+}
@@ -0,0 +1,770 @@
+use crate::{
+    changes::{head_sha, is_mergeable, Review},
+    clock::{clock, format_utc},
+    config::{CloseReason, Mark, MergeMethod, SuggestChange},
+    github::{encode, ApiError, GitHub},
+    scope::Scope,
+};
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+
+/// The issue or pull request an event belongs to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IssueRef {
+    pub repository: String,
+    pub number: u64,
+}
+
+impl IssueRef {
+    /// Find the issue or pull request of the event in the context.
+    ///
+    /// When running for the results of a search, this is the current `item`.
+    pub fn from_context(context: &Scope) -> anyhow::Result<Self> {
+        let item = &context["item"];
+        if let (Some(url), Some(number)) =
+            (item["repository_url"].as_str(), item["number"].as_u64())
+        {
+            if let Some((_, repository)) = url.split_once("/repos/") {
+                return Ok(Self {
+                    repository: repository.to_string(),
+                    number,
+                });
+            }
+        }
+
+        // the issue belongs to the repository of the event, not to the target of a command
+        let event = &context["github"]["event"];
+        let repository = event_repository(context)?;
+
+        let number = event["issue"]["number"]
+            .as_u64()
+            .or_else(|| event["pull_request"]["number"].as_u64())
+            .or_else(|| event["number"].as_u64())
+            .or_else(|| event["check_run"]["pull_requests"][0]["number"].as_u64())
+            .or_else(|| event["check_suite"]["pull_requests"][0]["number"].as_u64())
+            .or_else(|| event["workflow_run"]["pull_requests"][0]["number"].as_u64())
+            .context("Event has no issue or pull request")?;
+
+        Ok(Self { repository, number })
+    }
+
+    /// The API path of the issue.
+    pub fn path(&self) -> String {
+        format!("repos/{}/issues/{}", self.repository, self.number)
+    }
+}
+
+/// Find the repository of the event in the context.
+pub fn event_repository(context: &Scope) -> anyhow::Result<String> {
+    context["github"]["event"]["repository"]["full_name"]
+        .as_str()
+        .map(ToString::to_string)
+        .or_else(|| std::env::var("GITHUB_REPOSITORY").ok())
+        .context("Unable to find the repository of the event")
+}
+
+/// Find the repository which steps on a repository act on, the target of a command or the
+/// repository of the event.
+pub fn repository(context: &Scope) -> anyhow::Result<String> {
+    match context["repository"].as_str() {
+        Some(repository) => Ok(repository.to_string()),
+        None => event_repository(context),
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct IssueComment {
+    id: u64,
+    body: String,
+}
+
+/// Fetch all comments of the issue or pull request.
+pub fn all_comments(github: &GitHub, issue: &IssueRef) -> anyhow::Result<Vec<Value>> {
+    github.get_all(&format!("{}/comments", issue.path()))
+}
+
+/// Count the reactions of a kind on an issue, pull request or comment, given by its API path.
+pub fn reactions(github: &GitHub, path: &str, content: &str) -> anyhow::Result<usize> {
+    Ok(github
+        .get_all::<Value>(&format!("{}/reactions?content={}", path, encode(content)))?
+        .len())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct IssueLabel {
+    name: String,
+}
+
+/// Add labels, skipping the ones which are already present.
+pub fn add_labels(github: &GitHub, issue: &IssueRef, labels: &[String]) -> anyhow::Result<()> {
+    let present = github
+        .get_all::<IssueLabel>(&format!("{}/labels", issue.path()))?
+        .into_iter()
+        .map(|label| label.name)
+        .collect::<Vec<_>>();
+    let missing = labels
+        .iter()
+        .filter(|label| !present.contains(label))
+        .collect::<Vec<_>>();
+
+    if missing.is_empty() {
+        log::info!("Labels already present: {:?}", labels);
+        return Ok(());
+    }
+
+    github.post(
+        &format!("{}/labels", issue.path()),
+        &json!({ "labels": missing }),
+    )?;
+    Ok(())
+}
+
+/// Create a comment, unless a comment with the same key exists already.
+pub fn comment_once(
+    github: &GitHub,
+    issue: &IssueRef,
+    key: &str,
+    body: &str,
+) -> anyhow::Result<()> {
+    let marker = format!("<!-- rodbot:{} -->", key);
+
+    if github
+        .get_all::<IssueComment>(&format!("{}/comments", issue.path()))?
+        .iter()
+        .any(|c| c.body.contains(&marker))
+    {
+        log::info!("Comment '{}' exists already, skipping", key);
+        return Ok(());
+    }
+
+    github.post(
+        &format!("{}/comments", issue.path()),
+        &json!({ "body": format!("{}\n{}", marker, body) }),
+    )?;
+    Ok(())
+}
+
+/// Remove a label, ignoring labels which are not present.
+pub fn remove_label(github: &GitHub, issue: &IssueRef, label: &str) -> anyhow::Result<()> {
+    match github.delete(&format!("{}/labels/{}", issue.path(), encode(label))) {
+        Ok(_) => Ok(()),
+        Err(err)
+            if matches!(
+                err.downcast_ref::<ApiError>(),
+                Some(ApiError {
+                    status: Some(404),
+                    ..
+                })
+            ) =>
+        {
+            Ok(())
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Create or update a comment, identified by a hidden marker.
+///
+/// Without a body, an existing comment gets deleted.
+pub fn sticky_comment(
+    github: &GitHub,
+    issue: &IssueRef,
+    marker: &str,
+    body: Option<&str>,
+) -> anyhow::Result<()> {
+    let marker = format!("<!-- rodbot:{} -->", marker);
+
+    let existing = github
+        .get_all::<IssueComment>(&format!("{}/comments", issue.path()))?
+        .into_iter()
+        .find(|c| c.body.contains(&marker));
+
+    let path = |id: u64| format!("repos/{}/issues/comments/{}", issue.repository, id);
+
+    match (existing, body) {
+        (Some(comment), Some(body)) => {
+            github.patch(
+                &path(comment.id),
+                &json!({ "body": format!("{}\n{}", marker, body) }),
+            )?;
+        }
+        (Some(comment), None) => {
+            github.delete(&path(comment.id))?;
+        }
+        (None, Some(body)) => {
+            github.post(
+                &format!("{}/comments", issue.path()),
+                &json!({ "body": format!("{}\n{}", marker, body) }),
+            )?;
+        }
+        (None, None) => {}
+    }
+
+    Ok(())
+}
+
+/// Format a review comment, suggesting to replace the commented lines.
+pub fn suggestion(message: Option<&str>, replacement: &str) -> String {
+    match message {
+        Some(message) => format!("{}\n\n```suggestion\n{}\n```", message, replacement),
+        None => format!("```suggestion\n{}\n```", replacement),
+    }
+}
+
+/// Post a review comment on the pull request, suggesting a change.
+pub fn suggest_change(
+    github: &GitHub,
+    issue: &IssueRef,
+    config: &SuggestChange,
+) -> anyhow::Result<()> {
+    let mut comment = json!({
+        "commit_id": head_sha(github, &issue.repository, issue.number)?,
+        "path": config.path,
+        "line": config.line,
+        "side": "RIGHT",
+        "body": suggestion(config.message.as_deref(), &config.replacement),
+    });
+    if let Some(start_line) = config.start_line {
+        comment["start_line"] = start_line.into();
+        comment["start_side"] = "RIGHT".into();
+    }
+
+    github.post(
+        &format!("repos/{}/pulls/{}/comments", issue.repository, issue.number),
+        &comment,
+    )?;
+
+    Ok(())
+}
+
+/// Close the issue or pull request.
+pub fn close(github: &GitHub, issue: &IssueRef, reason: Option<CloseReason>) -> anyhow::Result<()> {
+    let mut body = json!({ "state": "closed" });
+    if let Some(reason) = reason {
+        body["state_reason"] = reason.as_str().into();
+    }
+    github.patch(&issue.path(), &body)?;
+    Ok(())
+}
+
+/// Reopen the issue or pull request.
+pub fn reopen(github: &GitHub, issue: &IssueRef) -> anyhow::Result<()> {
+    github.patch(&issue.path(), &json!({ "state": "open" }))?;
+    Ok(())
+}
+
+/// Create an issue, returning its number.
+pub fn create_issue(
+    github: &GitHub,
+    repository: &str,
+    title: &str,
+    body: Option<&str>,
+    labels: &[String],
+    assignees: &[String],
+) -> anyhow::Result<u64> {
+    if title.trim().is_empty() {
+        anyhow::bail!("Missing title of the issue");
+    }
+    let mut issue = json!({ "title": title, "labels": labels, "assignees": assignees });
+    if let Some(body) = body {
+        issue["body"] = body.into();
+    }
+    let number = github.post(&format!("repos/{}/issues", repository), &issue)?["number"]
+        .as_u64()
+        .context("Missing number of the created issue")?;
+    log::info!("Created issue {}#{}", repository, number);
+    Ok(number)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RepositoryInfo {
+    default_branch: String,
+}
+
+/// Trigger a workflow, on the default branch if there is no ref.
+pub fn dispatch_workflow(
+    github: &GitHub,
+    repository: &str,
+    workflow: &str,
+    r#ref: Option<&str>,
+    inputs: &BTreeMap<String, String>,
+) -> anyhow::Result<()> {
+    let r#ref = match r#ref {
+        Some(r#ref) => r#ref.to_string(),
+        None => {
+            github
+                .get::<RepositoryInfo>(&format!("repos/{}", repository))?
+                .default_branch
+        }
+    };
+    github.post(
+        &format!(
+            "repos/{}/actions/workflows/{}/dispatches",
+            repository,
+            encode(workflow)
+        ),
+        &json!({ "ref": r#ref, "inputs": inputs }),
+    )?;
+    log::info!(
+        "Triggered workflow {} of {} on {}",
+        workflow,
+        repository,
+        r#ref
+    );
+    Ok(())
+}
+
+/// Send a `repository_dispatch` event to a repository.
+pub fn repository_dispatch(
+    github: &GitHub,
+    repository: &str,
+    event_type: &str,
+    client_payload: &Value,
+) -> anyhow::Result<()> {
+    if event_type.trim().is_empty() {
+        anyhow::bail!("Missing event type of the repository dispatch");
+    }
+    github.post(
+        &format!("repos/{}/dispatches", repository),
+        &json!({ "event_type": event_type, "client_payload": client_payload }),
+    )?;
+    log::info!("Sent event {} to {}", event_type, repository);
+    Ok(())
+}
+
+/// Marks comments of commands which were executed.
+pub const EXECUTED_MARKER: &str = "<!-- rodbot:executed -->";
+
+/// The body of a comment, marked as executed.
+pub fn marked(body: &str, mark: Mark, epoch: u64) -> String {
+    match mark {
+        Mark::Footer => format!(
+            "{}\n\n{}\n✅ executed at {} by rodbot",
+            body.trim_end(),
+            EXECUTED_MARKER,
+            format_utc(epoch)
+        ),
+        // the command is the first line
+        Mark::Strike => {
+            let (command, rest) = body.split_once('\n').unwrap_or((body, ""));
+            let command = command.trim_end();
+            let mut result = format!("~~{}~~", command);
+            if !rest.is_empty() {
+                result = format!("{}\n{}", result, rest);
+            }
+            format!("{}\n{}", result.trim_end(), EXECUTED_MARKER)
+        }
+    }
+}
+
+/// Mark a comment of an issue or pull request as executed.
+pub fn mark_comment(
+    github: &GitHub,
+    repository: &str,
+    id: u64,
+    body: &str,
+    mark: Mark,
+) -> anyhow::Result<()> {
+    github.patch(
+        &format!("repos/{}/issues/comments/{}", repository, id),
+        &json!({ "body": marked(body, mark, clock().epoch()) }),
+    )?;
+    Ok(())
+}
+
+/// The reasons for locking a conversation.
+const LOCK_REASONS: &[&str] = &["off-topic", "too heated", "resolved", "spam"];
+
+/// Lock the conversation of the issue or pull request.
+///
+/// The reason may use dashes, underscores or spaces, like `too-heated` or `off_topic`.
+pub fn lock(github: &GitHub, issue: &IssueRef, reason: Option<&str>) -> anyhow::Result<()> {
+    let mut body = json!({});
+    if let Some(reason) = reason.map(str::trim).filter(|r| !r.is_empty()) {
+        let normalize = |r: &str| r.to_lowercase().replace(['-', '_'], " ");
+        let reason = LOCK_REASONS
+            .iter()
+            .find(|r| normalize(r) == normalize(reason))
+            .with_context(|| {
+                format!(
+                    "Unknown lock reason '{}', must be one of: {}",
+                    reason,
+                    LOCK_REASONS.join(", ")
+                )
+            })?;
+        body["lock_reason"] = (*reason).into();
+    }
+    github.request("PUT", &format!("{}/lock", issue.path()), Some(&body))?;
+    Ok(())
+}
+
+/// Unlock the conversation of the issue or pull request.
+pub fn unlock(github: &GitHub, issue: &IssueRef) -> anyhow::Result<()> {
+    github.request("DELETE", &format!("{}/lock", issue.path()), None)?;
+    Ok(())
+}
+
+/// Assign users to the issue or pull request.
+pub fn assign(github: &GitHub, issue: &IssueRef, users: &[String]) -> anyhow::Result<()> {
+    if users.is_empty() {
+        return Ok(());
+    }
+    github.post(
+        &format!("{}/assignees", issue.path()),
+        &json!({ "assignees": users }),
+    )?;
+    Ok(())
+}
+
+/// Unassign users from the issue or pull request.
+pub fn unassign(github: &GitHub, issue: &IssueRef, users: &[String]) -> anyhow::Result<()> {
+    if users.is_empty() {
+        return Ok(());
+    }
+    github.request(
+        "DELETE",
+        &format!("{}/assignees", issue.path()),
+        Some(&json!({ "assignees": users })),
+    )?;
+    Ok(())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Milestone {
+    number: u64,
+    title: String,
+}
+
+/// Set the milestone of the issue or pull request by its title, clearing it if the title is empty.
+pub fn set_milestone(github: &GitHub, issue: &IssueRef, title: &str) -> anyhow::Result<()> {
+    let milestone = match title {
+        "" => Value::Null,
+        title => {
+            let milestones: Vec<Milestone> =
+                github.get_all(&format!("repos/{}/milestones?state=all", issue.repository))?;
+            let milestone = milestones
+                .iter()
+                .find(|m| m.title == title)
+                .with_context(|| {
+                    format!(
+                        "Unknown milestone '{}', must be one of: {}",
+                        title,
+                        milestones
+                            .iter()
+                            .map(|m| m.title.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    )
+                })?;
+            milestone.number.into()
+        }
+    };
+    github.patch(&issue.path(), &json!({ "milestone": milestone }))?;
+    Ok(())
+}
+
+/// Request reviews of the pull request from users and teams.
+pub fn request_review(
+    github: &GitHub,
+    issue: &IssueRef,
+    users: &[String],
+    teams: &[String],
+) -> anyhow::Result<()> {
+    if users.is_empty() && teams.is_empty() {
+        return Ok(());
+    }
+    github.post(
+        &format!(
+            "repos/{}/pulls/{}/requested_reviewers",
+            issue.repository, issue.number
+        ),
+        &json!({ "reviewers": users, "team_reviewers": teams }),
+    )?;
+    Ok(())
+}
+
+/// Approve the pull request.
+pub fn approve(github: &GitHub, issue: &IssueRef, body: Option<&str>) -> anyhow::Result<()> {
+    let mut review = json!({ "event": "APPROVE" });
+    if let Some(body) = body {
+        review["body"] = body.into();
+    }
+    github.post(
+        &format!("repos/{}/pulls/{}/reviews", issue.repository, issue.number),
+        &review,
+    )?;
+    Ok(())
+}
+
+/// Dismiss the reviews which approve or request changes, of all users if none are given.
+pub fn dismiss_reviews(
+    github: &GitHub,
+    issue: &IssueRef,
+    message: &str,
+    users: &[String],
+) -> anyhow::Result<usize> {
+    let path = format!("repos/{}/pulls/{}/reviews", issue.repository, issue.number);
+    let reviews = github
+        .get_all::<Review>(&path)?
+        .into_iter()
+        .filter(|review| matches!(review.state.as_str(), "APPROVED" | "CHANGES_REQUESTED"))
+        .filter(|review| {
+            users.is_empty()
+                || review
+                    .user
+                    .as_ref()
+                    .is_some_and(|user| users.iter().any(|u| u.eq_ignore_ascii_case(&user.login)))
+        })
+        .collect::<Vec<_>>();
+
+    for review in &reviews {
+        github.put(
+            &format!("{}/{}/dismissals", path, review.id),
+            &json!({ "message": message, "event": "DISMISS" }),
+        )?;
+    }
+    Ok(reviews.len())
+}
+
+/// Merge the pull request, failing if it can't be merged.
+pub fn merge(
+    github: &GitHub,
+    issue: &IssueRef,
+    method: MergeMethod,
+    title: Option<&str>,
+    message: Option<&str>,
+) -> anyhow::Result<()> {
+    let pull_request =
+        is_mergeable(github, &issue.repository, issue.number)?.with_context(|| {
+            format!(
+                "Pull request {}#{} is not mergeable",
+                issue.repository, issue.number
+            )
+        })?;
+
+    // only merge what was checked
+    let mut body = json!({
+        "merge_method": method.as_str(),
+        "sha": pull_request.head.sha,
+    });
+    if let Some(title) = title {
+        body["commit_title"] = title.into();
+    }
+    if let Some(message) = message {
+        body["commit_message"] = message.into();
+    }
+
+    match github.put(
+        &format!("repos/{}/pulls/{}/merge", issue.repository, issue.number),
+        &body,
+    ) {
+        Ok(_) => Ok(()),
+        Err(err) => match err.downcast_ref::<ApiError>() {
+            Some(ApiError {
+                status: Some(405),
+                message,
+            }) => anyhow::bail!(
+                "Pull request {}#{} can't be merged: {}",
+                issue.repository,
+                issue.number,
+                message
+            ),
+            Some(ApiError {
+                status: Some(409), ..
+            }) => anyhow::bail!(
+                "Pull request {}#{} changed while merging, not merging",
+                issue.repository,
+                issue.number
+            ),
+            _ => Err(err),
+        },
+    }
+}
+
+/// The reactions GitHub supports, with their name in the GraphQL API.
+const REACTIONS: &[(&str, &str)] = &[
+    ("+1", "THUMBS_UP"),
+    ("-1", "THUMBS_DOWN"),
+    ("laugh", "LAUGH"),
+    ("confused", "CONFUSED"),
+    ("heart", "HEART"),
+    ("hooray", "HOORAY"),
+    ("rocket", "ROCKET"),
+    ("eyes", "EYES"),
+];
+
+/// React to the comment of the event.
+pub fn react(github: &GitHub, event: &Value, content: &str) -> anyhow::Result<()> {
+    let (_, graphql) = REACTIONS
+        .iter()
+        .find(|(name, _)| *name == content)
+        .with_context(|| {
+            format!(
+                "Unknown reaction '{}', must be one of: {}",
+                content,
+                REACTIONS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })?;
+
+    let comment = &event["comment"];
+    let id = comment["id"].as_u64().context("Event has no comment")?;
+
+    // discussions are only available using GraphQL
+    if event.get("discussion").is_some() {
+        let subject = comment["node_id"]
+            .as_str()
+            .context("Comment has no node ID")?;
+        github.graphql(
+            r#"mutation($subject: ID!, $content: ReactionContent!) {
+                addReaction(input: {subjectId: $subject, content: $content}) {
+                    reaction { content }
+                }
+            }"#,
+            json!({ "subject": subject, "content": graphql }),
+        )?;
+        return Ok(());
+    }
+
+    let repository = event["repository"]["full_name"]
+        .as_str()
+        .context("Unable to find the repository of the event")?;
+    // review comments belong to the pull request, not its issue
+    let kind = match comment.get("pull_request_review_id") {
+        Some(_) => "pulls",
+        None => "issues",
+    };
+    github.post(
+        &format!("repos/{}/{}/comments/{}/reactions", repository, kind, id),
+        &json!({ "content": content }),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_marked() {
+        assert_eq!(
+            marked("/deploy staging\r\n", Mark::Footer, 1_634_900_038),
+            "/deploy staging\n\n<!-- rodbot:executed -->\n✅ executed at 2021-10-22 10:53 UTC by rodbot"
+        );
+        assert_eq!(
+            marked("/deploy staging\r\nPlease!", Mark::Strike, 0),
+            "~~/deploy staging~~\nPlease!\n<!-- rodbot:executed -->"
+        );
+    }
+
+    #[test]
+    fn test_issue_ref() -> anyhow::Result<()> {
+        let context = json!({
+            "github": {
+                "event": {
+                    "issue": {"number": 42},
+                    "repository": {"full_name": "drogue-iot/drogue-cloud"},
+                }
+            },
+        });
+
+        assert_eq!(
+            IssueRef::from_context(&Scope::new(&context))?,
+            IssueRef {
+                repository: "drogue-iot/drogue-cloud".into(),
+                number: 42
+            }
+        );
+
+        // the issue stays in the repository of the event, even with the target of a command
+        let target = json!({
+            "github": context["github"],
+            "repository": "drogue-iot/drogue-device",
+        });
+
+        assert_eq!(
+            IssueRef::from_context(&Scope::new(&target))?,
+            IssueRef {
+                repository: "drogue-iot/drogue-cloud".into(),
+                number: 42
+            }
+        );
+        assert_eq!(
+            repository(&Scope::new(&target))?,
+            "drogue-iot/drogue-device"
+        );
+        assert_eq!(
+            repository(&Scope::new(&context))?,
+            "drogue-iot/drogue-cloud"
+        );
+
+        let context = json!({
+            "github": context["github"],
+            "item": {
+                "number": 7,
+                "repository_url": "https://api.github.com/repos/drogue-iot/drogue-device",
+            },
+        });
+
+        assert_eq!(
+            IssueRef::from_context(&Scope::new(&context))?,
+            IssueRef {
+                repository: "drogue-iot/drogue-device".into(),
+                number: 7
+            }
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_react() -> anyhow::Result<()> {
+        let mock = crate::mock::MockGitHub::with_fixtures().install();
+        mock.respond("POST", "repos/*/*/*/comments/*/reactions", json!({}));
+        mock.respond("POST", "graphql", json!({"data": {}}));
+
+        let github = GitHub::new();
+        let repository = json!({"full_name": "ctron/rodbot"});
+        react(
+            &github,
+            &json!({"comment": {"id": 1}, "repository": repository}),
+            "rocket",
+        )?;
+        react(
+            &github,
+            &json!({"comment": {"id": 2, "pull_request_review_id": 3}, "repository": repository}),
+            "+1",
+        )?;
+        react(
+            &github,
+            &json!({"comment": {"id": 4, "node_id": "DC_4"}, "discussion": {}, "repository": repository}),
+            "eyes",
+        )?;
+        assert!(react(&github, &json!({"comment": {"id": 1}}), "tada").is_err());
+
+        let calls = mock.calls();
+        assert_eq!(
+            calls[0].path,
+            "repos/ctron/rodbot/issues/comments/1/reactions"
+        );
+        assert_eq!(calls[0].body, Some(json!({"content": "rocket"})));
+        assert_eq!(
+            calls[1].path,
+            "repos/ctron/rodbot/pulls/comments/2/reactions"
+        );
+        assert_eq!(calls[2].path, "graphql");
+        assert_eq!(
+            calls[2].body.as_ref().map(|body| &body["variables"]),
+            Some(&json!({"subject": "DC_4", "content": "EYES"}))
+        );
+        assert_eq!(calls.len(), 3);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use crate::{
+    config::ApplySuggestions,
+    github::GitHub,
+    steps::IssueRef,
+    threads::{resolve_thread, review_threads, ReviewThread},
+};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Debug, Deserialize)]
+struct PullRequestHead {
+    head: Head,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Head {
+    r#ref: String,
+    sha: String,
+    repo: HeadRepository,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct HeadRepository {
+    full_name: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct GitCommit {
+    tree: Sha,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Sha {
+    sha: String,
+}
+
+/// Replace a range of lines.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edit {
+    /// The first line, starting with 1.
+    pub start: u64,
+    /// The last line, inclusive.
+    pub end: u64,
+    pub replacement: String,
+}
+
+/// Extract the content of the suggestion block of a comment.
+pub fn parse_suggestion(body: &str) -> Option<String> {
+    let mut lines = body.lines();
+    lines.find(|line| line.trim_start().starts_with("```suggestion"))?;
+
+    let mut result = Vec::new();
+    for line in lines {
+        if line.trim() == "```" {
+            return Some(result.join("\n"));
+        }
+        result.push(line);
+    }
+
+    None
+}
+
+/// Apply the edits to the content, skipping edits which overlap.
+///
+/// Returns the new content, and the indexes of the edits which were applied.
+pub fn apply(content: &str, edits: &[Edit]) -> (String, Vec<usize>) {
+    let mut lines = content.lines().map(ToString::to_string).collect::<Vec<_>>();
+
+    let mut order = (0..edits.len()).collect::<Vec<_>>();
+    order.sort_by_key(|i| std::cmp::Reverse(edits[*i].start));
+
+    let mut applied = Vec::new();
+    let mut limit = lines.len() as u64 + 1;
+
+    for i in order {
+        let edit = &edits[i];
+        if edit.start < 1 || edit.start > edit.end || edit.end >= limit {
+            log::info!("Skipping overlapping or invalid suggestion: {:?}", edit);
+            continue;
+        }
+        lines.splice(
+            (edit.start - 1) as usize..edit.end as usize,
+            edit.replacement.lines().map(ToString::to_string),
+        );
+        limit = edit.start;
+        applied.push(i);
+    }
+
+    let mut result = lines.join("\n");
+    if content.ends_with('\n') {
+        result.push('\n');
+    }
+
+    applied.sort_unstable();
+    (result, applied)
+}
+
+/// Commit the open suggestions of the pull request in a single commit, and resolve their threads.
+pub fn apply_suggestions(
+    github: &GitHub,
+    config: &ApplySuggestions,
+    issue: &IssueRef,
+) -> anyhow::Result<()> {
+    let head = github
+        .get::<PullRequestHead>(&format!(
+            "repos/{}/pulls/{}",
+            issue.repository, issue.number
+        ))?
+        .head;
+    let repository = &head.repo.full_name;
+
+    let mut by_path = BTreeMap::<String, Vec<(ReviewThread, Edit)>>::new();
+    for thread in review_threads(github, issue)? {
+        if thread.is_resolved
+            || thread.is_outdated
+            || !(config.authors.is_empty() || thread.is_authored_by(&config.authors))
+        {
+            continue;
+        }
+        let (line, replacement) = match (
+            thread.line,
+            thread.comment().and_then(|c| parse_suggestion(&c.body)),
+        ) {
+            (Some(line), Some(replacement)) => (line, replacement),
+            _ => continue,
+        };
+        let edit = Edit {
+            start: thread.start_line.unwrap_or(line),
+            end: line,
+            replacement,
+        };
+        by_path
+            .entry(thread.path.clone())
+            .or_default()
+            .push((thread, edit));
+    }
+
+    let mut tree = Vec::new();
+    let mut resolved = Vec::new();
+
+    for (path, suggestions) in by_path {
+        let content = github.get_raw(&format!(
+            "repos/{}/contents/{}?ref={}",
+            repository, path, head.sha
+        ))?;
+        let edits = suggestions
+            .iter()
+            .map(|(_, e)| e.clone())
+            .collect::<Vec<_>>();
+        let (content, applied) = apply(&content, &edits);
+        if applied.is_empty() {
+            continue;
+        }
+
+        tree.push(json!({
+            "path": path,
+            "mode": "100644",
+            "type": "blob",
+            "content": content,
+        }));
+        resolved.extend(applied.into_iter().map(|i| suggestions[i].0.clone()));
+    }
+
+    if resolved.is_empty() {
+        log::info!("No suggestions to apply");
+        return Ok(());
+    }
+
+    log::info!("Applying {} suggestion(s)", resolved.len());
+
+    let base =
+        github.get::<GitCommit>(&format!("repos/{}/git/commits/{}", repository, head.sha))?;
+    let tree: Sha = serde_json::from_value(github.post(
+        &format!("repos/{}/git/trees", repository),
+        &json!({ "base_tree": base.tree.sha, "tree": tree }),
+    )?)?;
+    let commit: Sha = serde_json::from_value(github.post(
+        &format!("repos/{}/git/commits", repository),
+        &json!({
+            "message": config.message,
+            "tree": tree.sha,
+            "parents": [head.sha],
+        }),
+    )?)?;
+    // not forced, fails if the branch was updated in the meantime
+    github.patch(
+        &format!("repos/{}/git/refs/heads/{}", repository, head.r#ref),
+        &json!({ "sha": commit.sha }),
+    )?;
+
+    for thread in &resolved {
+        resolve_thread(github, thread)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_apply() {
+        let body = "Possible typo: `teh` → `the`\n\n```suggestion\nUse the API\n```";
+        assert_eq!(parse_suggestion(body), Some("Use the API".into()));
+        assert_eq!(parse_suggestion("Looks good"), None);
+
+        let edit = |start, end, replacement: &str| Edit {
+            start,
+            end,
+            replacement: replacement.into(),
+        };
+
+        let (content, applied) = apply(
+            "a\nb\nc\nd\ne\n",
+            &[
+                edit(2, 2, "B"),
+                edit(4, 5, ""),
+                edit(2, 3, "overlapping"),
+                edit(1, 1, "a1\na2"),
+            ],
+        );
+
+        assert_eq!(content, "a1\na2\nB\nc\n");
+        assert_eq!(applied, vec![0, 1, 3]);
+    }
+}
@@ -0,0 +1,327 @@
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+lazy_static! {
+    static ref SUMMARY: Mutex<Summary> = Mutex::new(Summary::default());
+}
+
+static API_CALLS: AtomicU64 = AtomicU64::new(0);
+static OUTPUT_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// What happened during a run, for printing it at the end.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Summary {
+    pub event: String,
+    pub rules: Vec<RuleSummary>,
+}
+
+/// A rule which matched, and the steps it ran.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RuleSummary {
+    pub name: String,
+    pub steps: Vec<StepSummary>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StepSummary {
+    pub name: String,
+    pub duration: Duration,
+    pub usage: Usage,
+    /// The error, if the step failed.
+    pub error: Option<String>,
+}
+
+/// Resources used by a step.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Usage {
+    /// CPU time of the process and its children, if available.
+    pub cpu: Option<Duration>,
+    pub api_calls: u64,
+    /// Bytes written to the output by commands.
+    pub output_bytes: u64,
+}
+
+impl Usage {
+    /// The resources used so far.
+    pub fn now() -> Self {
+        Self {
+            cpu: cpu_time(),
+            api_calls: API_CALLS.load(Ordering::Relaxed),
+            output_bytes: OUTPUT_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// The resources used since an earlier snapshot.
+    pub fn since(&self, start: &Self) -> Self {
+        Self {
+            cpu: match (self.cpu, start.cpu) {
+                (Some(end), Some(start)) => Some(end.saturating_sub(start)),
+                _ => None,
+            },
+            api_calls: self.api_calls - start.api_calls,
+            output_bytes: self.output_bytes - start.output_bytes,
+        }
+    }
+}
+
+/// Count a call to the GitHub API.
+pub fn api_call() {
+    API_CALLS.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Count bytes written to the output.
+pub fn output(bytes: u64) {
+    OUTPUT_BYTES.fetch_add(bytes, Ordering::Relaxed);
+}
+
+/// The user and system time of the process and its waited-for children.
+///
+/// This is only available on Linux, assuming the usual 100 clock ticks per second.
+fn cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // the process name may contain spaces, the fields after it start with the state (field 3)
+    let fields = stat.rsplit_once(')')?.1.split_whitespace();
+    let ticks = fields
+        .skip(11)
+        .take(4)
+        .map(|f| f.parse::<u64>().ok())
+        .sum::<Option<u64>>()?;
+    Some(Duration::from_millis(ticks * 10))
+}
+
+/// Record the event being processed.
+pub fn event(name: &str) {
+    SUMMARY.lock().unwrap().event = name.to_string();
+}
+
+/// Record a matching rule, unnamed rules are named after the event.
+pub fn rule(name: Option<&str>) {
+    let mut summary = SUMMARY.lock().unwrap();
+    let name = name.unwrap_or(&summary.event).to_string();
+    summary.rules.push(RuleSummary {
+        name,
+        steps: vec![],
+    });
+}
+
+/// Record a step of the current rule.
+pub fn step<T>(name: &str, duration: Duration, usage: Usage, result: &anyhow::Result<T>) {
+    let mut summary = SUMMARY.lock().unwrap();
+    if summary.rules.is_empty() {
+        let event = summary.event.clone();
+        summary.rules.push(RuleSummary {
+            name: event,
+            steps: vec![],
+        });
+    }
+    if let Some(rule) = summary.rules.last_mut() {
+        rule.steps.push(StepSummary {
+            name: name.to_string(),
+            duration,
+            usage,
+            error: result.as_ref().err().map(|err| format!("{:#}", err)),
+        });
+    }
+}
+
+/// Get the summary of the run so far, keeping it.
+pub fn current() -> Summary {
+    SUMMARY.lock().unwrap().clone()
+}
+
+/// Take the summary of the run so far.
+pub fn take() -> Summary {
+    std::mem::take(&mut *SUMMARY.lock().unwrap())
+}
+
+const GREEN: &str = "\x1b[32m";
+const RED: &str = "\x1b[31m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+impl Summary {
+    pub fn to_json(&self) -> Value {
+        json!({
+            "event": self.event,
+            "rules": self.rules.iter().map(|rule| json!({
+                "name": rule.name,
+                "steps": rule.steps.iter().map(|step| json!({
+                    "name": step.name,
+                    "duration_ms": step.duration.as_millis() as u64,
+                    "cpu_ms": step.usage.cpu.map(|cpu| cpu.as_millis() as u64),
+                    "api_calls": step.usage.api_calls,
+                    "output_bytes": step.usage.output_bytes,
+                    "error": step.error,
+                })).collect::<Vec<_>>(),
+            })).collect::<Vec<_>>(),
+        })
+    }
+
+    /// Render the summary as a table.
+    pub fn render(&self, color: bool) -> String {
+        let paint = |code: &str, text: &str| match color {
+            true => format!("{}{}{}", code, text, RESET),
+            false => text.to_string(),
+        };
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{}",
+            paint(
+                BOLD,
+                &format!(
+                    "rodbot: {} rule(s) matched for '{}'",
+                    self.rules.len(),
+                    self.event
+                )
+            )
+        );
+
+        let mut rows = Vec::new();
+        for rule in &self.rules {
+            if rule.steps.is_empty() {
+                rows.push((rule.name.as_str(), "-", Default::default(), None));
+            }
+            for step in &rule.steps {
+                let result = match &step.error {
+                    None => Some(Ok(())),
+                    Some(err) => Some(Err(err.as_str())),
+                };
+                let usage = [
+                    format!("{:.2}s", step.duration.as_secs_f64()),
+                    step.usage
+                        .cpu
+                        .map(|cpu| format!("{:.2}s", cpu.as_secs_f64()))
+                        .unwrap_or_else(|| "-".into()),
+                    step.usage.api_calls.to_string(),
+                    format_bytes(step.usage.output_bytes),
+                ];
+                rows.push((rule.name.as_str(), step.name.as_str(), usage, result));
+            }
+        }
+
+        if rows.is_empty() {
+            return out;
+        }
+
+        const HEADERS: [&str; 4] = ["Duration", "CPU", "API", "Output"];
+
+        let rule_width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(4);
+        let step_width = rows.iter().map(|r| r.1.len()).max().unwrap_or(0).max(4);
+        let widths: Vec<usize> = HEADERS
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                rows.iter()
+                    .map(|r| r.2[i].len())
+                    .max()
+                    .unwrap_or(0)
+                    .max(header.len())
+            })
+            .collect();
+        let columns = |values: &[&str]| {
+            values
+                .iter()
+                .zip(&widths)
+                .map(|(value, width)| format!("{:>width$}", value, width = width))
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+
+        let _ = writeln!(
+            out,
+            "{:rule_width$}  {:step_width$}  {}  Result",
+            "Rule",
+            "Step",
+            columns(&HEADERS)
+        );
+        for (rule, step, usage, result) in rows {
+            let result = match result {
+                None => String::new(),
+                Some(Ok(())) => paint(GREEN, "ok"),
+                Some(Err(err)) => paint(RED, &format!("failed: {}", err)),
+            };
+            let usage = match usage[0].is_empty() {
+                true => String::new(),
+                false => columns(&usage.iter().map(String::as_str).collect::<Vec<_>>()),
+            };
+            let line = format!(
+                "{:rule_width$}  {:step_width$}  {}  {}",
+                rule, step, usage, result
+            );
+            let _ = writeln!(out, "{}", line.trim_end());
+        }
+
+        out
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..=1023 => format!("{}B", bytes),
+        1024..=1_048_575 => format!("{:.1}KiB", bytes as f64 / 1024.0),
+        _ => format!("{:.1}MiB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let summary = Summary {
+            event: "issue_comment".into(),
+            rules: vec![
+                RuleSummary {
+                    name: "preview".into(),
+                    steps: vec![
+                        StepSummary {
+                            name: "run".into(),
+                            duration: Duration::from_millis(1250),
+                            usage: Usage {
+                                cpu: Some(Duration::from_millis(800)),
+                                api_calls: 0,
+                                output_bytes: 2560,
+                            },
+                            error: None,
+                        },
+                        StepSummary {
+                            name: "comment".into(),
+                            duration: Duration::from_millis(300),
+                            usage: Usage {
+                                cpu: None,
+                                api_calls: 2,
+                                output_bytes: 0,
+                            },
+                            error: Some("GitHub API call failed".into()),
+                        },
+                    ],
+                },
+                RuleSummary {
+                    name: "issue_comment".into(),
+                    steps: vec![],
+                },
+            ],
+        };
+
+        assert_eq!(
+            summary.render(false),
+            r#"rodbot: 2 rule(s) matched for 'issue_comment'
+Rule           Step     Duration    CPU  API  Output  Result
+preview        run         1.25s  0.80s    0  2.5KiB  ok
+preview        comment     0.30s      -    2      0B  failed: GitHub API call failed
+issue_comment  -
+"#
+        );
+    }
+}
@@ -0,0 +1,609 @@
+use crate::{github::GitHub, output::Output};
+use anyhow::Context;
+use serde::Deserialize;
+use serde_json::json;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::{Display, Formatter},
+    fs::File,
+};
+
+/// Declarative definition of the teams of an organization.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct TeamsConfig {
+    pub org: String,
+    pub teams: Vec<TeamConfig>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct TeamConfig {
+    pub name: String,
+    /// The slug of the team, derived from the name if missing.
+    #[serde(default)]
+    pub slug: Option<String>,
+    #[serde(default)]
+    pub maintainers: Vec<String>,
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Repositories (name only) and the permission the team has on them.
+    #[serde(default)]
+    pub repos: BTreeMap<String, Permission>,
+}
+
+impl TeamConfig {
+    pub fn slug(&self) -> String {
+        self.slug.clone().unwrap_or_else(|| slug(&self.name))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Ord, PartialOrd)]
+#[serde(rename_all = "lowercase")]
+pub enum Permission {
+    Pull,
+    Triage,
+    Push,
+    Maintain,
+    Admin,
+}
+
+impl Permission {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pull => "pull",
+            Self::Triage => "triage",
+            Self::Push => "push",
+            Self::Maintain => "maintain",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Parse a permission, by the names of teams, or of collaborators and their roles.
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "pull" | "read" => Some(Self::Pull),
+            "triage" => Some(Self::Triage),
+            "push" | "write" => Some(Self::Push),
+            "maintain" => Some(Self::Maintain),
+            "admin" => Some(Self::Admin),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Role {
+    Member,
+    Maintainer,
+}
+
+impl Role {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Member => "member",
+            Self::Maintainer => "maintainer",
+        }
+    }
+}
+
+/// The current state of a team, as reported by GitHub.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct TeamState {
+    pub maintainers: BTreeSet<String>,
+    pub members: BTreeSet<String>,
+    pub repos: BTreeMap<String, Permission>,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    CreateTeam {
+        team: String,
+        name: String,
+    },
+    SetMember {
+        team: String,
+        user: String,
+        role: Role,
+    },
+    RemoveMember {
+        team: String,
+        user: String,
+    },
+    SetRepo {
+        team: String,
+        repo: String,
+        permission: Permission,
+        previous: Option<Permission>,
+    },
+    RemoveRepo {
+        team: String,
+        repo: String,
+    },
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CreateTeam { team, name } => write!(f, "+ team {} ({})", team, name),
+            Self::SetMember { team, user, role } => {
+                write!(f, "+ {}: {} ({})", team, user, role.as_str())
+            }
+            Self::RemoveMember { team, user } => write!(f, "- {}: {}", team, user),
+            Self::SetRepo {
+                team,
+                repo,
+                permission,
+                previous: Some(previous),
+            } => write!(
+                f,
+                "~ {}: repo {} ({} -> {})",
+                team,
+                repo,
+                previous.as_str(),
+                permission.as_str()
+            ),
+            Self::SetRepo {
+                team,
+                repo,
+                permission,
+                previous: None,
+            } => write!(f, "+ {}: repo {} ({})", team, repo, permission.as_str()),
+            Self::RemoveRepo { team, repo } => write!(f, "- {}: repo {}", team, repo),
+        }
+    }
+}
+
+/// Derive the slug of a team from its name, the same way GitHub does.
+pub fn slug(name: &str) -> String {
+    let mut result = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() || c == '_' {
+            result.push(c.to_ascii_lowercase());
+        } else if !result.is_empty() && !result.ends_with('-') {
+            result.push('-');
+        }
+    }
+    result.trim_end_matches('-').to_string()
+}
+
+/// Compute the changes required to get from the current state to the desired one.
+pub fn diff(team: &TeamConfig, state: Option<&TeamState>) -> Vec<Change> {
+    let slug = team.slug();
+    let mut changes = Vec::new();
+
+    let empty = TeamState::default();
+    let state = match state {
+        Some(state) => state,
+        None => {
+            changes.push(Change::CreateTeam {
+                team: slug.clone(),
+                name: team.name.clone(),
+            });
+            &empty
+        }
+    };
+
+    let desired = team
+        .maintainers
+        .iter()
+        .map(|user| (user, Role::Maintainer))
+        .chain(
+            team.members
+                .iter()
+                .filter(|user| !team.maintainers.contains(user))
+                .map(|user| (user, Role::Member)),
+        );
+
+    for (user, role) in desired {
+        let current = if state.maintainers.contains(user) {
+            Some(Role::Maintainer)
+        } else if state.members.contains(user) {
+            Some(Role::Member)
+        } else {
+            None
+        };
+        if current != Some(role) {
+            changes.push(Change::SetMember {
+                team: slug.clone(),
+                user: user.clone(),
+                role,
+            });
+        }
+    }
+
+    for user in state.maintainers.iter().chain(state.members.iter()) {
+        if !team.maintainers.contains(user) && !team.members.contains(user) {
+            changes.push(Change::RemoveMember {
+                team: slug.clone(),
+                user: user.clone(),
+            });
+        }
+    }
+
+    for (repo, permission) in &team.repos {
+        let previous = state.repos.get(repo).copied();
+        if previous != Some(*permission) {
+            changes.push(Change::SetRepo {
+                team: slug.clone(),
+                repo: repo.clone(),
+                permission: *permission,
+                previous,
+            });
+        }
+    }
+
+    for repo in state.repos.keys() {
+        if !team.repos.contains_key(repo) {
+            changes.push(Change::RemoveRepo {
+                team: slug.clone(),
+                repo: repo.clone(),
+            });
+        }
+    }
+
+    changes
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Member {
+    login: String,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct TeamRepository {
+    name: String,
+    permissions: RepositoryPermissions,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct RepositoryPermissions {
+    #[serde(default)]
+    admin: bool,
+    #[serde(default)]
+    maintain: bool,
+    #[serde(default)]
+    push: bool,
+    #[serde(default)]
+    triage: bool,
+}
+
+impl From<RepositoryPermissions> for Permission {
+    fn from(permissions: RepositoryPermissions) -> Self {
+        if permissions.admin {
+            Self::Admin
+        } else if permissions.maintain {
+            Self::Maintain
+        } else if permissions.push {
+            Self::Push
+        } else if permissions.triage {
+            Self::Triage
+        } else {
+            Self::Pull
+        }
+    }
+}
+
+fn fetch(github: &GitHub, org: &str, slug: &str) -> anyhow::Result<Option<TeamState>> {
+    let base = format!("orgs/{}/teams/{}", org, slug);
+
+    if github.get_optional::<serde_json::Value>(&base)?.is_none() {
+        return Ok(None);
+    }
+
+    let logins = |role: &str| -> anyhow::Result<BTreeSet<String>> {
+        Ok(github
+            .get_all::<Member>(&format!("{}/members?role={}", base, role))?
+            .into_iter()
+            .map(|m| m.login)
+            .collect())
+    };
+
+    let repos = github
+        .get_all::<TeamRepository>(&format!("{}/repos", base))?
+        .into_iter()
+        .map(|r| (r.name, r.permissions.into()))
+        .collect();
+
+    Ok(Some(TeamState {
+        maintainers: logins("maintainer")?,
+        members: logins("member")?,
+        repos,
+    }))
+}
+
+fn apply(github: &GitHub, org: &str, change: &Change) -> anyhow::Result<()> {
+    match change {
+        Change::CreateTeam { name, .. } => {
+            github.post(
+                &format!("orgs/{}/teams", org),
+                &json!({"name": name, "privacy": "closed"}),
+            )?;
+        }
+        Change::SetMember { team, user, role } => {
+            github.put(
+                &format!("orgs/{}/teams/{}/memberships/{}", org, team, user),
+                &json!({"role": role.as_str()}),
+            )?;
+        }
+        Change::RemoveMember { team, user } => {
+            github.delete(&format!("orgs/{}/teams/{}/memberships/{}", org, team, user))?;
+        }
+        Change::SetRepo {
+            team,
+            repo,
+            permission,
+            ..
+        } => {
+            github.put(
+                &format!("orgs/{}/teams/{}/repos/{}/{}", org, team, org, repo),
+                &json!({"permission": permission.as_str()}),
+            )?;
+        }
+        Change::RemoveRepo { team, repo } => {
+            github.delete(&format!(
+                "orgs/{}/teams/{}/repos/{}/{}",
+                org, team, org, repo
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// Reconcile the teams of an organization with the configuration.
+///
+/// Teams which are not part of the configuration are not touched.
+pub fn sync(github: &GitHub, config: &TeamsConfig, dry_run: bool) -> anyhow::Result<Vec<Change>> {
+    let mut result = Vec::new();
+
+    for team in &config.teams {
+        let state = fetch(github, &config.org, &team.slug())?;
+        for change in diff(team, state.as_ref()) {
+            log::info!("{}", change);
+            if !dry_run {
+                apply(github, &config.org, &change)?;
+            }
+            result.push(change);
+        }
+    }
+
+    Ok(result)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct Membership {
+    state: String,
+}
+
+/// Check if the user is an active member of the team, referenced as `org/team-slug`.
+pub fn is_team_member(github: &GitHub, team: &str, user: &str) -> anyhow::Result<bool> {
+    let (org, slug) = team
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("Invalid team, expected 'org/team-slug': {}", team))?;
+
+    Ok(github
+        .get_optional::<Membership>(&format!("orgs/{}/teams/{}/memberships/{}", org, slug, user))?
+        .is_some_and(|m| m.state == "active"))
+}
+
+/// Check if the user is a member of the organization.
+pub fn is_org_member(github: &GitHub, org: &str, user: &str) -> anyhow::Result<bool> {
+    // responds with 204 for members, and 404 otherwise
+    Ok(github
+        .get_optional::<()>(&format!("orgs/{}/members/{}", org, user))?
+        .is_some())
+}
+
+#[derive(Clone, Debug, Deserialize)]
+struct CollaboratorPermission {
+    /// One of `admin`, `write`, `read` or `none`.
+    permission: String,
+    /// The name of the role, e.g. `maintain` or `triage`.
+    role_name: Option<String>,
+}
+
+/// Check if the user has at least one of the permissions on the repository.
+///
+/// Permissions are compared by their level, so `admin` satisfies `write`. Custom roles only
+/// satisfy their own name.
+pub fn has_permission(
+    github: &GitHub,
+    repository: &str,
+    user: &str,
+    expected: &[String],
+) -> anyhow::Result<bool> {
+    // responds with 404 for users which are not collaborators
+    let permission = match github.get_optional::<CollaboratorPermission>(&format!(
+        "repos/{}/collaborators/{}/permission",
+        repository, user
+    ))? {
+        Some(permission) => permission,
+        None => return Ok(false),
+    };
+
+    let granted = Permission::parse(&permission.permission)
+        .max(permission.role_name.as_deref().and_then(Permission::parse));
+
+    Ok(expected.iter().any(|e| {
+        *e == permission.permission
+            || Some(e) == permission.role_name.as_ref()
+            || matches!(
+                (Permission::parse(e), granted),
+                (Some(expected), Some(granted)) if granted >= expected
+            )
+    }))
+}
+
+pub fn sync_command(matches: &clap::ArgMatches) -> anyhow::Result<()> {
+    let from = matches.value_of("from").unwrap_or("teams.yaml");
+    log::debug!("Loading teams from: {}", from);
+
+    let config: TeamsConfig =
+        serde_yaml::from_reader(File::open(from)?).context("Loading teams")?;
+
+    let dry_run = matches.is_present("dry-run");
+    let changes = sync(&GitHub::new(), &config, dry_run)?;
+
+    Output::from_matches(matches).print(
+        &json!({
+            "dry_run": dry_run,
+            "changes": changes.iter().map(ToString::to_string).collect::<Vec<_>>(),
+        }),
+        || {
+            for change in &changes {
+                println!("{}", change);
+            }
+        },
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::mock::MockGitHub;
+    use serde_json::Value;
+
+    #[test]
+    fn test_slug() {
+        assert_eq!(slug("Drogue Cloud Maintainers"), "drogue-cloud-maintainers");
+        assert_eq!(slug("IoT / Devices!"), "iot-devices");
+    }
+
+    #[test]
+    fn test_diff() {
+        let team = TeamConfig {
+            name: "Maintainers".into(),
+            slug: None,
+            maintainers: vec!["ctron".into()],
+            members: vec!["foo".into()],
+            repos: vec![
+                ("drogue-cloud".to_string(), Permission::Maintain),
+                ("rodbot".to_string(), Permission::Push),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let state = TeamState {
+            maintainers: Default::default(),
+            members: vec!["ctron".to_string(), "bar".to_string()]
+                .into_iter()
+                .collect(),
+            repos: vec![
+                ("drogue-cloud".to_string(), Permission::Push),
+                ("old".to_string(), Permission::Pull),
+                ("rodbot".to_string(), Permission::Push),
+            ]
+            .into_iter()
+            .collect(),
+        };
+
+        let changes = diff(&team, Some(&state))
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            changes,
+            vec![
+                "+ maintainers: ctron (maintainer)",
+                "+ maintainers: foo (member)",
+                "- maintainers: bar",
+                "~ maintainers: repo drogue-cloud (push -> maintain)",
+                "- maintainers: repo old",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_is_team_member() -> anyhow::Result<()> {
+        let mock = MockGitHub::new().install();
+        // everyone else is not a member
+        mock.fail(
+            "GET",
+            "orgs/drogue-iot/teams/maintainers/memberships/*",
+            404,
+        );
+        mock.respond(
+            "GET",
+            "orgs/drogue-iot/teams/maintainers/memberships/ctron",
+            json!({"state": "active"}),
+        );
+        mock.respond(
+            "GET",
+            "orgs/drogue-iot/teams/maintainers/memberships/foo",
+            json!({"state": "pending"}),
+        );
+
+        let github = GitHub::new();
+        assert!(is_team_member(&github, "drogue-iot/maintainers", "ctron")?);
+        assert!(!is_team_member(&github, "drogue-iot/maintainers", "foo")?);
+        assert!(!is_team_member(&github, "drogue-iot/maintainers", "bar")?);
+        assert!(is_team_member(&github, "maintainers", "ctron").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_org_member() -> anyhow::Result<()> {
+        let mock = MockGitHub::new().install();
+        mock.fail("GET", "orgs/drogue-iot/members/*", 404);
+        // responds without a body
+        mock.respond("GET", "orgs/drogue-iot/members/ctron", Value::Null);
+
+        let github = GitHub::new();
+        assert!(is_org_member(&github, "drogue-iot", "ctron")?);
+        assert!(!is_org_member(&github, "drogue-iot", "bar")?);
+
+        mock.fail("GET", "orgs/drogue-iot/members/*", 500);
+        assert!(is_org_member(&github, "drogue-iot", "ctron").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_has_permission() -> anyhow::Result<()> {
+        let mock = MockGitHub::new().install();
+        mock.fail(
+            "GET",
+            "repos/drogue-iot/rodbot/collaborators/*/permission",
+            404,
+        );
+        mock.respond(
+            "GET",
+            "repos/drogue-iot/rodbot/collaborators/ctron/permission",
+            json!({"permission": "admin", "role_name": "admin"}),
+        );
+        mock.respond(
+            "GET",
+            "repos/drogue-iot/rodbot/collaborators/foo/permission",
+            json!({"permission": "write", "role_name": "triage-plus"}),
+        );
+        mock.respond(
+            "GET",
+            "repos/drogue-iot/rodbot/collaborators/bar/permission",
+            json!({"permission": "read", "role_name": "triage"}),
+        );
+
+        let github = GitHub::new();
+        let check = |user: &str, expected: &[&str]| {
+            let expected = expected.iter().map(ToString::to_string).collect::<Vec<_>>();
+            has_permission(&github, "drogue-iot/rodbot", user, &expected)
+        };
+
+        // compared by level
+        assert!(check("ctron", &["write"])?);
+        assert!(check("ctron", &["maintain"])?);
+        assert!(check("foo", &["write"])?);
+        assert!(!check("foo", &["maintain", "admin"])?);
+        assert!(check("bar", &["triage"])?);
+        assert!(!check("bar", &["write"])?);
+        // custom roles only satisfy their name
+        assert!(check("foo", &["triage-plus"])?);
+        assert!(!check("ctron", &["triage-plus"])?);
+        // not a collaborator
+        assert!(!check("baz", &["read"])?);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,226 @@
+use jsonpath::Selector;
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+lazy_static! {
+    /// An expression in a template, like `${{ github.event.issue.number }}`.
+    pub static ref EXPRESSION: Regex = Regex::new(r#"\$\{\{(.*?)\}\}"#).unwrap();
+    static ref SELECTORS: Mutex<HashMap<String, Arc<Selector>>> = Mutex::new(HashMap::new());
+}
+
+/// Get the compiled selector of a JSON path, compiling it only once.
+pub fn selector(path: &str) -> anyhow::Result<Arc<Selector>> {
+    if let Some(selector) = SELECTORS.lock().unwrap().get(path) {
+        return Ok(selector.clone());
+    }
+
+    let selector = Arc::new(
+        Selector::new(path).map_err(|err| anyhow::anyhow!("Invalid path '{}': {}", path, err))?,
+    );
+    SELECTORS
+        .lock()
+        .unwrap()
+        .insert(path.to_string(), selector.clone());
+    Ok(selector)
+}
+
+/// Replace the expressions of a template, using the result of `lookup` for each expression.
+///
+/// This doesn't depend on any state, so that it can be fuzzed with arbitrary input.
+pub fn substitute<F>(text: &str, mut lookup: F) -> anyhow::Result<String>
+where
+    F: FnMut(&str) -> anyhow::Result<String>,
+{
+    let mut errors = Vec::new();
+
+    let text = EXPRESSION.replace_all(text, |caps: &Captures| match lookup(&caps[1]) {
+        Ok(value) => value,
+        Err(err) => {
+            errors.push(err);
+            String::new()
+        }
+    });
+
+    match errors.len() {
+        0 => Ok(text.into_owned()),
+        1 => Err(errors.remove(0)),
+        _ => Err(anyhow::anyhow!("Failed with multiple errors: {:?}", errors)),
+    }
+}
+
+/// The JSON path of an expression, relative to the root of the context.
+pub fn path(expr: &str) -> String {
+    format!("$.{}", expr.trim().trim_start_matches("$."))
+}
+
+/// Compile the expressions of the templates, and the paths of the `expr`, `exists` and `equals`
+/// conditions of the configuration.
+///
+/// This reports invalid expressions when loading the configuration, instead of when running
+/// the step, and avoids compiling them again for every execution.
+pub fn precompile(config: &serde_yaml::Value) -> anyhow::Result<()> {
+    fn walk(value: &serde_yaml::Value, errors: &mut Vec<String>) {
+        match value {
+            serde_yaml::Value::String(text) => {
+                for caps in EXPRESSION.captures_iter(text) {
+                    let expr = caps[1].trim();
+                    if !expr.starts_with("emoji(") {
+                        compile(expr, errors);
+                    }
+                }
+            }
+            serde_yaml::Value::Mapping(map) => {
+                for (key, value) in map {
+                    match (key.as_str(), value) {
+                        (Some("expr" | "exists"), serde_yaml::Value::String(expr)) => {
+                            compile(expr, errors)
+                        }
+                        (Some("equals"), serde_yaml::Value::Mapping(equals)) => {
+                            match equals.get(&"path".into()).and_then(|path| path.as_str()) {
+                                Some(path) => compile(path, errors),
+                                None => walk(value, errors),
+                            }
+                        }
+                        _ => walk(value, errors),
+                    }
+                }
+            }
+            serde_yaml::Value::Sequence(seq) => {
+                for value in seq {
+                    walk(value, errors);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn compile(expr: &str, errors: &mut Vec<String>) {
+        if let Err(err) = selector(&path(expr)) {
+            errors.push(err.to_string());
+        }
+    }
+
+    let mut errors = Vec::new();
+    walk(config, &mut errors);
+
+    match errors.is_empty() {
+        true => Ok(()),
+        false => anyhow::bail!("{}", errors.join(", ")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Generate arbitrary templates from fragments, which are likely to confuse the parser.
+    fn templates(count: usize) -> Vec<String> {
+        const FRAGMENTS: &[&str] = &[
+            "${{", "}}", "$", "{", "}", "{{", " ", "\n", "a.b", "[0]", "ü", "🎉", "${{ x }}",
+            "text",
+        ];
+
+        // a fixed seed, for reproducible results
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state as usize
+        };
+
+        (0..count)
+            .map(|_| {
+                let len = next() % 12;
+                (0..len)
+                    .map(|_| FRAGMENTS[next() % FRAGMENTS.len()])
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_substitute_round_trip() {
+        for text in templates(2000) {
+            // putting the expressions back must result in the original text
+            let result = substitute(&text, |expr| Ok(format!("${{{{{}}}}}", expr)));
+            assert_eq!(result.ok().as_deref(), Some(text.as_str()), "{:?}", text);
+
+            // text without expressions is never changed
+            let result = substitute(&text, |_| Ok("value".into())).unwrap();
+            if !EXPRESSION.is_match(&text) {
+                assert_eq!(result, text);
+            }
+
+            // failing lookups fail the template
+            let result = substitute(&text, |expr| Err(anyhow::anyhow!("{}", expr)));
+            assert_eq!(result.is_err(), EXPRESSION.is_match(&text), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn test_substitute_untrusted() {
+        // values are not evaluated again, even if they look like expressions
+        let result = substitute("Hello ${{ user }}!", |_| Ok("${{ secrets.TOKEN }}".into()));
+        assert_eq!(result.unwrap(), "Hello ${{ secrets.TOKEN }}!");
+    }
+
+    #[test]
+    fn test_precompile() {
+        let config: serde_yaml::Value = serde_yaml::from_str(
+            r#"---
+on:
+  issue_comment:
+    - if:
+        - expr: comment.body
+      steps:
+        - comment: "Hello ${{ github.event.comment.user.login }} ${{ emoji(wave) }}"
+"#,
+        )
+        .unwrap();
+        assert!(precompile(&config).is_ok());
+        assert!(SELECTORS
+            .lock()
+            .unwrap()
+            .contains_key("$.github.event.comment.user.login"));
+
+        let config: serde_yaml::Value =
+            serde_yaml::from_str("steps:\n  - run: echo ${{ github.event[ }}\n").unwrap();
+        assert!(precompile(&config).is_err());
+    }
+
+    #[test]
+    fn test_precompile_raw_paths() {
+        let config: serde_yaml::Value = serde_yaml::from_str(
+            r#"---
+on:
+  raw:
+    - if:
+        - exists: deployment.task
+        - equals:
+            path: deployment.environment
+            value: production
+      steps: []
+"#,
+        )
+        .unwrap();
+        assert!(precompile(&config).is_ok());
+        let selectors = SELECTORS.lock().unwrap();
+        assert!(selectors.contains_key("$.deployment.task"));
+        assert!(selectors.contains_key("$.deployment.environment"));
+        drop(selectors);
+
+        for condition in [
+            "exists: deployment[",
+            "equals: {path: \"deployment[\", value: 1}",
+        ] {
+            let config: serde_yaml::Value =
+                serde_yaml::from_str(&format!("if:\n  - {}\n", condition)).unwrap();
+            assert!(precompile(&config).is_err(), "{}", condition);
+        }
+    }
+}
@@ -0,0 +1,231 @@
+use crate::{
+    config::{ResolveThread, ThreadMatch},
+    github::GitHub,
+    steps::IssueRef,
+};
+use serde::Deserialize;
+use serde_json::json;
+
+const REVIEW_THREADS: &str = r#"
+query($owner: String!, $name: String!, $number: Int!, $cursor: String) {
+  repository(owner: $owner, name: $name) {
+    pullRequest(number: $number) {
+      reviewThreads(first: 100, after: $cursor) {
+        pageInfo { hasNextPage endCursor }
+        nodes {
+          id
+          isResolved
+          isOutdated
+          path
+          line
+          startLine
+          comments(first: 1) {
+            nodes { body author { login } }
+          }
+        }
+      }
+    }
+  }
+}
+"#;
+
+const RESOLVE_THREAD: &str = r#"
+mutation($id: ID!) {
+  resolveReviewThread(input: { threadId: $id }) { thread { id } }
+}
+"#;
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewThread {
+    pub id: String,
+    pub is_resolved: bool,
+    pub is_outdated: bool,
+    pub path: String,
+    /// The (last) line in the new version of the file, missing for outdated threads.
+    pub line: Option<u64>,
+    pub start_line: Option<u64>,
+    pub comments: Nodes<ThreadComment>,
+}
+
+impl ReviewThread {
+    /// The first comment, which started the thread.
+    pub fn comment(&self) -> Option<&ThreadComment> {
+        self.comments.nodes.first()
+    }
+
+    /// Check if the thread was started by one of the users. Bots are matched with or without the
+    /// `[bot]` suffix, as GraphQL reports them without it.
+    pub fn is_authored_by(&self, users: &[String]) -> bool {
+        let login = match self.comment().and_then(|c| c.author.as_ref()) {
+            Some(author) => author.login.trim_end_matches("[bot]"),
+            None => return false,
+        };
+        users.iter().any(|u| u.trim_end_matches("[bot]") == login)
+    }
+}
+
+impl ThreadMatch {
+    /// Check if the (unresolved) thread matches the criteria.
+    pub fn matches(&self, thread: &ReviewThread) -> bool {
+        if thread.is_resolved {
+            return false;
+        }
+        if !self.authors.is_empty() && !thread.is_authored_by(&self.authors) {
+            return false;
+        }
+        if self
+            .outdated
+            .is_some_and(|outdated| outdated != thread.is_outdated)
+        {
+            return false;
+        }
+        if let Some(pattern) = &self.body_matches {
+            if !thread.comment().is_some_and(|c| pattern.is_match(&c.body)) {
+                return false;
+            }
+        }
+        self.paths.is_empty() || self.paths.iter().any(|glob| glob.is_match(&thread.path))
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct ThreadComment {
+    pub body: String,
+    /// Missing for deleted accounts.
+    pub author: Option<Author>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Author {
+    pub login: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq)]
+pub struct Nodes<T> {
+    pub nodes: Vec<T>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PageInfo {
+    has_next_page: bool,
+    end_cursor: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewThreads {
+    page_info: PageInfo,
+    nodes: Vec<ReviewThread>,
+}
+
+/// Fetch all review threads of the pull request.
+pub fn review_threads(github: &GitHub, issue: &IssueRef) -> anyhow::Result<Vec<ReviewThread>> {
+    let (owner, name) = issue
+        .repository
+        .split_once('/')
+        .ok_or_else(|| anyhow::anyhow!("Invalid repository name: {}", issue.repository))?;
+
+    let mut result = Vec::new();
+    let mut cursor = None;
+
+    loop {
+        let mut data = github.graphql(
+            REVIEW_THREADS,
+            json!({
+                "owner": owner,
+                "name": name,
+                "number": issue.number,
+                "cursor": cursor,
+            }),
+        )?;
+        let threads: ReviewThreads =
+            serde_json::from_value(data["repository"]["pullRequest"]["reviewThreads"].take())?;
+
+        result.extend(threads.nodes);
+        match (
+            threads.page_info.has_next_page,
+            threads.page_info.end_cursor,
+        ) {
+            (true, Some(next)) => cursor = Some(next),
+            _ => break,
+        }
+    }
+
+    Ok(result)
+}
+
+pub fn resolve_thread(github: &GitHub, thread: &ReviewThread) -> anyhow::Result<()> {
+    github.graphql(RESOLVE_THREAD, json!({ "id": thread.id }))?;
+    Ok(())
+}
+
+/// Resolve the review threads of the pull request which match.
+pub fn resolve_threads(
+    github: &GitHub,
+    config: &ResolveThread,
+    issue: &IssueRef,
+) -> anyhow::Result<()> {
+    let threads = review_threads(github, issue)?
+        .into_iter()
+        .filter(|thread| config.r#match.matches(thread))
+        .collect::<Vec<_>>();
+
+    log::info!("Resolving {} review thread(s)", threads.len());
+
+    for thread in &threads {
+        resolve_thread(github, thread)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::Pattern;
+
+    #[test]
+    fn test_matches() {
+        let thread = ReviewThread {
+            id: "PRRT_kwDOABCD".into(),
+            is_resolved: false,
+            is_outdated: true,
+            path: "README.md".into(),
+            line: None,
+            start_line: None,
+            comments: Nodes {
+                nodes: vec![ThreadComment {
+                    body: "Possible typo: `teh` → `the`".into(),
+                    author: Some(Author {
+                        login: "github-actions".into(),
+                    }),
+                }],
+            },
+        };
+
+        let criteria = ThreadMatch {
+            authors: vec!["github-actions[bot]".into()],
+            outdated: Some(true),
+            body_matches: Some(Pattern(regex::Regex::new("^Possible typo").unwrap())),
+            paths: vec![],
+        };
+        assert!(criteria.matches(&thread));
+
+        assert!(!ThreadMatch {
+            authors: vec!["ctron".into()],
+            ..Default::default()
+        }
+        .matches(&thread));
+        assert!(!ThreadMatch {
+            outdated: Some(false),
+            ..Default::default()
+        }
+        .matches(&thread));
+        assert!(!criteria.matches(&ReviewThread {
+            is_resolved: true,
+            ..thread.clone()
+        }));
+    }
+}
@@ -0,0 +1,122 @@
+use crate::github::GitHub;
+use serde::Deserialize;
+use std::str::FromStr;
+
+/// The version of rodbot.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// The repository rodbot is released from.
+const REPOSITORY: &str = "ctron/rodbot";
+
+/// A version, like `0.1.6` or `v0.2`, ignoring pre-release and build information.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct Version(Vec<u64>);
+
+impl FromStr for Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let core = s
+            .trim()
+            .trim_start_matches('v')
+            .split(['-', '+'])
+            .next()
+            .unwrap_or_default();
+        let mut parts = core
+            .split('.')
+            .map(|p| p.parse::<u64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| anyhow::anyhow!("Invalid version '{}': {}", s, err))?;
+        // 0.2 is the same as 0.2.0
+        while parts.len() > 1 && parts.last() == Some(&0) {
+            parts.pop();
+        }
+        Ok(Self(parts))
+    }
+}
+
+/// Fail if the configuration requires a newer version of rodbot.
+///
+/// This works on the raw configuration, so that it fails before the configuration is parsed,
+/// which would fail with a less helpful error if it uses newer features.
+pub fn check_min_version(config: &serde_yaml::Value) -> anyhow::Result<()> {
+    let min = match config.get("min_version") {
+        None | Some(serde_yaml::Value::Null) => return Ok(()),
+        Some(serde_yaml::Value::String(min)) => min.clone(),
+        // like `min_version: 0.2`
+        Some(serde_yaml::Value::Number(min)) => min.to_string(),
+        Some(other) => anyhow::bail!("Invalid min_version: {:?}", other),
+    };
+
+    if VERSION.parse::<Version>()? < min.parse::<Version>()? {
+        anyhow::bail!(
+            "The configuration requires rodbot {} or newer, this is rodbot {}",
+            min,
+            VERSION
+        );
+    }
+    Ok(())
+}
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+}
+
+/// Check the latest release, returning its version if it is newer than this version.
+pub fn check_for_update(github: &GitHub) -> anyhow::Result<Option<String>> {
+    let release: Release = github.get(&format!("repos/{}/releases/latest", REPOSITORY))?;
+    match release.tag_name.parse::<Version>()? > VERSION.parse()? {
+        true => Ok(Some(release.tag_name)),
+        false => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_version() -> anyhow::Result<()> {
+        assert!("0.1.6".parse::<Version>()? < "0.2".parse()?);
+        assert!("v0.10.0".parse::<Version>()? > "0.9.9".parse()?);
+        assert_eq!("0.2".parse::<Version>()?, "v0.2.0-rc1".parse()?);
+        assert!("latest".parse::<Version>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_version() {
+        let config = |yaml: &str| serde_yaml::from_str::<serde_yaml::Value>(yaml).unwrap();
+
+        assert!(check_min_version(&config("on: {}")).is_ok());
+        assert!(check_min_version(&config("min_version: 0.1")).is_ok());
+        assert!(check_min_version(&config(&format!("min_version: {}", VERSION))).is_ok());
+        assert!(check_min_version(&config("min_version: 999.0")).is_err());
+        assert!(check_min_version(&config("min_version: \"v999.1.0\"")).is_err());
+        assert!(check_min_version(&config("min_version: [1]")).is_err());
+    }
+
+    #[test]
+    fn test_check_for_update() -> anyhow::Result<()> {
+        let mock = crate::mock::MockGitHub::new().install();
+        let github = GitHub::new();
+
+        mock.respond(
+            "GET",
+            "repos/ctron/rodbot/releases/latest",
+            json!({"tag_name": "v999.0.0"}),
+        );
+        assert_eq!(check_for_update(&github)?, Some("v999.0.0".into()));
+
+        mock.respond(
+            "GET",
+            "repos/ctron/rodbot/releases/latest",
+            json!({"tag_name": format!("v{}", VERSION)}),
+        );
+        assert_eq!(check_for_update(&github)?, None);
+
+        Ok(())
+    }
+}
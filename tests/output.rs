@@ -0,0 +1,38 @@
+use std::process::Command;
+
+#[test]
+fn test_run_json_output() -> anyhow::Result<()> {
+    let dir = std::env::temp_dir().join(format!("rodbot-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir)?;
+    let config = dir.join("rodbot.yaml");
+    std::fs::write(
+        &config,
+        r#"
+on:
+  push:
+    - steps:
+        - run: echo hi
+"#,
+    )?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_rodbot"))
+        .arg("--output")
+        .arg("json")
+        .arg("--config")
+        .arg(&config)
+        .arg("run")
+        .env("GITHUB_EVENT_NAME", "push")
+        .env("GITHUB_EVENT_PATH", "test/push_1.json")
+        .env_remove("GITHUB_STEP_SUMMARY")
+        .env_remove("GITHUB_OUTPUT")
+        .output()?;
+    std::fs::remove_dir_all(&dir)?;
+
+    assert!(output.status.success(), "{:?}", output);
+    // the output of the command must not break the JSON result
+    let result: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(result["event"], "push");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("hi"));
+
+    Ok(())
+}